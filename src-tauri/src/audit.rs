@@ -1,40 +1,344 @@
+use std::collections::HashMap;
 use std::fs::{OpenOptions, create_dir_all};
 use std::io::Write;
-use serde::Serialize;
+use std::sync::{mpsc, Mutex, OnceLock};
+use serde::{Deserialize, Serialize};
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 struct AuditEntry {
     timestamp: String,
     event: String,
+    #[serde(default)]
     details: serde_json::Value,
 }
 
-/// Log an audit event to ~/.ghostpen/logs/audit.jsonl
-/// Fire-and-forget: spawns a thread so it never blocks the caller.
+/// Usage summary over a window of the audit log
+#[derive(Serialize, Debug, PartialEq)]
+pub struct UsageMetrics {
+    pub days: u32,
+    pub total_checks: usize,
+    pub total_rewrites: usize,
+    pub failed_rewrites: usize,
+    /// Mean duration of rewrites that recorded one; None if none did
+    pub avg_rewrite_duration_ms: Option<f64>,
+    pub most_used_mode: Option<String>,
+}
+
+/// Everything logged under one writing session (see session::new_session_id)
+#[derive(Serialize, Debug, PartialEq, Default)]
+pub struct SessionReport {
+    pub session_id: String,
+    /// Timestamps of the session's first and latest events
+    pub started_at: Option<String>,
+    pub last_activity: Option<String>,
+    pub checks_run: usize,
+    /// Issues found by the session's latest check
+    pub open_issues: Option<usize>,
+    /// Suggestions applied through apply_fixes
+    pub issues_fixed: usize,
+    pub rewrites: usize,
+    pub failed_rewrites: usize,
+    /// Rewrites rated up in feedback
+    pub rewrites_accepted: usize,
+    /// Rewrites rated down in feedback
+    pub rewrites_rejected: usize,
+}
+
+/// Serialized details larger than this get their strings truncated
+const MAX_DETAIL_BYTES: usize = 8 * 1024;
+/// Length strings are cut to when details are over budget
+const TRUNCATED_STRING_CHARS: usize = 256;
+/// Keys that may carry user writing. Never logged, whatever their size.
+const SENSITIVE_KEYS: &[&str] = &["text", "original_text", "rewritten_text", "raw"];
+
+/// Replace sensitive values with a length marker, recursively
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if SENSITIVE_KEYS.contains(&key.as_str()) {
+                    let len = v.as_str().map(|s| s.chars().count()).unwrap_or(0);
+                    *v = serde_json::Value::String(format!("[redacted {} chars]", len));
+                } else {
+                    redact(v);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+fn truncate_strings(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(s) if s.chars().count() > TRUNCATED_STRING_CHARS => {
+            *s = s.chars().take(TRUNCATED_STRING_CHARS).collect::<String>() + "…";
+        }
+        serde_json::Value::Object(map) => map.values_mut().for_each(truncate_strings),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(truncate_strings),
+        _ => {}
+    }
+}
+
+/// Keep details small and free of user text before they hit disk.
+/// Oversized payloads get long strings cut and a `_truncated: true` marker;
+/// if that still isn't enough, only the marker and original size survive.
+fn cap_details(mut details: serde_json::Value) -> serde_json::Value {
+    redact(&mut details);
+
+    let size = serde_json::to_string(&details).map(|s| s.len()).unwrap_or(0);
+    if size <= MAX_DETAIL_BYTES {
+        return details;
+    }
+
+    truncate_strings(&mut details);
+    if let serde_json::Value::Object(map) = &mut details {
+        map.insert("_truncated".to_string(), serde_json::Value::Bool(true));
+    }
+
+    let truncated_size = serde_json::to_string(&details).map(|s| s.len()).unwrap_or(0);
+    if truncated_size <= MAX_DETAIL_BYTES {
+        details
+    } else {
+        serde_json::json!({ "_truncated": true, "_original_bytes": size })
+    }
+}
+
+/// Events logged during tests, so tests can assert on them without touching disk
+#[cfg(test)]
+pub(crate) static CAPTURED: std::sync::Mutex<Vec<(String, serde_json::Value)>> = std::sync::Mutex::new(Vec::new());
+
+/// Sender for the single writer thread; created on first use
+fn writer() -> &'static Mutex<mpsc::Sender<AuditEntry>> {
+    static WRITER: OnceLock<Mutex<mpsc::Sender<AuditEntry>>> = OnceLock::new();
+    WRITER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<AuditEntry>();
+        std::thread::spawn(move || {
+            for entry in rx {
+                write_entry(&entry);
+            }
+        });
+        Mutex::new(tx)
+    })
+}
+
+fn write_entry(entry: &AuditEntry) {
+    let log_dir = crate::paths::log_dir();
+    let _ = create_dir_all(&log_dir);
+
+    let log_file = log_dir.join("audit.jsonl");
+    if let Ok(json) = serde_json::to_string(entry) {
+        if let Ok(mut file) = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_file)
+        {
+            let _ = writeln!(file, "{}", json);
+        }
+    }
+}
+
+/// Log an audit event to <log dir>/audit.jsonl (see paths::log_dir)
+/// Fire-and-forget: entries are queued to one writer thread, so the caller
+/// never blocks and events land in the order they were logged.
+/// Details pass through cap_details, so callers can't leak user text or bloat the log.
 pub fn log_event(event: &str, details: serde_json::Value) {
-    let event = event.to_string();
-    std::thread::spawn(move || {
-        let log_dir = dirs::data_local_dir()
-            .unwrap_or_default()
-            .join("ghostpen")
-            .join("logs");
-        let _ = create_dir_all(&log_dir);
-
-        let log_file = log_dir.join("audit.jsonl");
-        let entry = AuditEntry {
-            timestamp: chrono::Local::now().to_rfc3339(),
-            event,
-            details,
-        };
-
-        if let Ok(json) = serde_json::to_string(&entry) {
-            if let Ok(mut file) = OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&log_file)
-            {
-                let _ = writeln!(file, "{}", json);
+    let details = cap_details(details);
+
+    #[cfg(test)]
+    CAPTURED.lock().unwrap_or_else(|e| e.into_inner()).push((event.to_string(), details.clone()));
+
+    let entry = AuditEntry {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        event: event.to_string(),
+        details,
+    };
+    if let Ok(tx) = writer().lock() {
+        let _ = tx.send(entry);
+    }
+}
+
+/// Summarize the last `days` days of audit.jsonl
+pub fn usage_metrics(days: u32) -> UsageMetrics {
+    let log_file = crate::paths::log_dir().join("audit.jsonl");
+    let content = std::fs::read_to_string(log_file).unwrap_or_default();
+    let since = chrono::Utc::now() - chrono::Duration::days(days as i64);
+    summarize(&content, since, days)
+}
+
+/// Aggregate audit lines at or after `since`. Details vary by event type and
+/// have drifted across versions, so every field is optional and malformed or
+/// unknown entries are skipped rather than failing the whole summary.
+fn summarize(content: &str, since: chrono::DateTime<chrono::Utc>, days: u32) -> UsageMetrics {
+    let mut total_checks = 0;
+    let mut total_rewrites = 0;
+    let mut failed_rewrites = 0;
+    let mut duration_sum = 0.0;
+    let mut duration_count = 0usize;
+    let mut mode_counts: HashMap<String, usize> = HashMap::new();
+
+    for line in content.lines() {
+        let Ok(entry) = serde_json::from_str::<AuditEntry>(line) else { continue };
+        let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(&entry.timestamp) else { continue };
+        if timestamp < since {
+            continue;
+        }
+
+        match entry.event.as_str() {
+            "grammar_check" => total_checks += 1,
+            "rewrite" | "rewrite_stream" => {
+                total_rewrites += 1;
+                if entry.details.get("success").and_then(|v| v.as_bool()) == Some(false) {
+                    failed_rewrites += 1;
+                }
+                if let Some(ms) = entry.details.get("duration_ms").and_then(|v| v.as_f64()) {
+                    duration_sum += ms;
+                    duration_count += 1;
+                }
+                if let Some(mode) = entry.details.get("mode").and_then(|v| v.as_str()) {
+                    *mode_counts.entry(mode.to_string()).or_default() += 1;
+                }
             }
+            _ => {}
+        }
+    }
+
+    // Ties break alphabetically so the result is stable
+    let most_used_mode = mode_counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+        .map(|(mode, _)| mode);
+
+    UsageMetrics {
+        days,
+        total_checks,
+        total_rewrites,
+        failed_rewrites,
+        avg_rewrite_duration_ms: (duration_count > 0).then(|| duration_sum / duration_count as f64),
+        most_used_mode,
+    }
+}
+
+/// Aggregate every audit.jsonl entry tagged with `session_id`
+pub fn session_report(session_id: &str) -> SessionReport {
+    let log_file = crate::paths::log_dir().join("audit.jsonl");
+    let content = std::fs::read_to_string(log_file).unwrap_or_default();
+    summarize_session(&content, session_id)
+}
+
+pub(crate) fn summarize_session(content: &str, session_id: &str) -> SessionReport {
+    let mut report = SessionReport { session_id: session_id.to_string(), ..SessionReport::default() };
+    for line in content.lines() {
+        let Ok(entry) = serde_json::from_str::<AuditEntry>(line) else { continue };
+        if entry.details.get("session_id").and_then(|v| v.as_str()) != Some(session_id) {
+            continue;
         }
-    });
+        report.started_at.get_or_insert_with(|| entry.timestamp.clone());
+        report.last_activity = Some(entry.timestamp.clone());
+
+        let details = &entry.details;
+        match entry.event.as_str() {
+            "grammar_check" => {
+                report.checks_run += 1;
+                report.open_issues = details.get("issue_count").and_then(|v| v.as_u64()).map(|n| n as usize);
+            }
+            "apply_fixes" => {
+                report.issues_fixed += details.get("applied").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+            }
+            "rewrite" | "rewrite_stream" => {
+                report.rewrites += 1;
+                if details.get("success").and_then(|v| v.as_bool()) == Some(false) {
+                    report.failed_rewrites += 1;
+                }
+            }
+            "feedback" => match details.get("rating").and_then(|v| v.as_str()) {
+                Some("up") => report.rewrites_accepted += 1,
+                Some("down") => report.rewrites_rejected += 1,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    report
+}
+
+/// Audit lines as they'd appear on disk, for summarizing what tests logged
+#[cfg(test)]
+pub(crate) fn captured_log() -> String {
+    CAPTURED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|(event, details)| {
+            serde_json::json!({ "timestamp": "2026-03-10T10:00:00+00:00", "event": event, "details": details }).to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_details_are_truncated() {
+        let details = serde_json::json!({
+            "mode": "clarity",
+            "note": "x".repeat(20_000),
+        });
+        let capped = cap_details(details);
+        assert_eq!(capped["_truncated"], true);
+        assert_eq!(capped["mode"], "clarity");
+        assert_eq!(capped["note"].as_str().unwrap().chars().count(), TRUNCATED_STRING_CHARS + 1);
+        assert!(serde_json::to_string(&capped).unwrap().len() <= MAX_DETAIL_BYTES);
+    }
+
+    #[test]
+    fn huge_field_counts_collapse_to_marker() {
+        let items: Vec<String> = (0..2_000).map(|i| format!("item-{}", i)).collect();
+        let capped = cap_details(serde_json::json!({ "items": items }));
+        assert_eq!(capped["_truncated"], true);
+        assert!(capped["_original_bytes"].as_u64().unwrap() > MAX_DETAIL_BYTES as u64);
+    }
+
+    #[test]
+    fn user_text_is_never_logged() {
+        let capped = cap_details(serde_json::json!({
+            "rating": "up",
+            "original_text": "my private draft",
+            "nested": { "rewritten_text": "secret" },
+        }));
+        assert_eq!(capped["rating"], "up");
+        assert_eq!(capped["original_text"], "[redacted 16 chars]");
+        assert_eq!(capped["nested"]["rewritten_text"], "[redacted 6 chars]");
+        assert!(capped.get("_truncated").is_none());
+    }
+
+    #[test]
+    fn summarize_synthetic_log() {
+        let log = [
+            r#"{"timestamp":"2026-03-10T10:00:00+00:00","event":"grammar_check","details":{"word_count":12}}"#,
+            r#"{"timestamp":"2026-03-10T10:01:00+00:00","event":"rewrite","details":{"mode":"clarity","success":true,"duration_ms":1000}}"#,
+            r#"{"timestamp":"2026-03-10T10:02:00+00:00","event":"rewrite_stream","details":{"mode":"clarity","success":true,"duration_ms":3000}}"#,
+            r#"{"timestamp":"2026-03-10T10:03:00+00:00","event":"rewrite","details":{"mode":"formal","success":false}}"#,
+            r#"{"timestamp":"2026-03-10T10:04:00+00:00","event":"llm_status_check","details":{"available":true}}"#,
+            r#"not json at all"#,
+            r#"{"timestamp":"yesterday","event":"grammar_check","details":{}}"#,
+            r#"{"timestamp":"2026-01-01T00:00:00+00:00","event":"grammar_check","details":{}}"#,
+        ]
+        .join("\n");
+        let since = chrono::DateTime::parse_from_rfc3339("2026-03-01T00:00:00+00:00")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+
+        let metrics = summarize(&log, since, 9);
+        assert_eq!(metrics, UsageMetrics {
+            days: 9,
+            total_checks: 1,
+            total_rewrites: 3,
+            failed_rewrites: 1,
+            avg_rewrite_duration_ms: Some(2000.0),
+            most_used_mode: Some("clarity".to_string()),
+        });
+    }
 }
@@ -0,0 +1,790 @@
+//! Supplemental style and grammar checks for gaps in Harper's coverage.
+//! Each check returns GrammarIssues with char offsets, like the rest of check_grammar.
+
+use crate::{segment, GrammarIssue, Severity};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Lowercase-initial names that legitimately start a sentence
+const LOWERCASE_NAMES: &[&str] = &["iphone", "ipad", "ipod", "imac", "ios", "ebay", "npm", "macos"];
+
+/// Bundled "wordy phrase: replacement" list
+const WORDY_PHRASES_DATA: &str = include_str!("../data/wordy_phrases.txt");
+
+/// Bundled "term: neutral alternative" list, extendable by the user
+const INCLUSIVE_TERMS_DATA: &str = include_str!("../data/inclusive_terms.txt");
+const USER_INCLUSIVE_TERMS_FILE: &str = "inclusive_terms.txt";
+
+/// Bundled "american: british" spelling pairs
+const SPELLING_VARIANTS_DATA: &str = include_str!("../data/spelling_variants.txt");
+
+/// Bundled "written form: preferred form: when" compound-word rules
+const COMPOUNDS_DATA: &str = include_str!("../data/compounds.txt");
+
+/// Bundled always-capitalized names (days, months, languages, countries)
+const PROPER_NOUNS_DATA: &str = include_str!("../data/proper_nouns.txt");
+
+fn char_offset(text: &str, byte: usize) -> usize {
+    text[..byte].chars().count()
+}
+
+/// Flag sentences whose first letter is lowercase, suggesting the capital.
+/// The span covers exactly that letter. Brand-style words (iPhone, eBay) are skipped.
+pub fn check_sentence_start(text: &str) -> Vec<GrammarIssue> {
+    let mut issues = Vec::new();
+
+    for span in segment::sentence_spans(text) {
+        let sentence = &text[span.clone()];
+        // Skip opening quotes/brackets; bail if the sentence starts with a digit or symbol
+        let Some((offset, first)) = sentence
+            .char_indices()
+            .find(|(_, c)| !matches!(c, '"' | '\'' | '(' | '[' | '“' | '‘'))
+        else {
+            continue;
+        };
+        if !first.is_lowercase() {
+            continue;
+        }
+
+        let word: String = sentence[offset..]
+            .chars()
+            .take_while(|c| c.is_alphanumeric())
+            .collect();
+        let is_camel_case = word.chars().skip(1).any(|c| c.is_uppercase());
+        let is_url = word.starts_with("www") || word.starts_with("http");
+        if is_camel_case || is_url || LOWERCASE_NAMES.iter().any(|n| n.eq_ignore_ascii_case(&word)) {
+            continue;
+        }
+
+        let byte_start = span.start + offset;
+        let start = char_offset(text, byte_start);
+        issues.push(GrammarIssue {
+            start,
+            end: start + 1,
+            message: "Sentences should start with a capital letter.".to_string(),
+            suggestions: vec![first.to_uppercase().collect()],
+            severity: Severity::Warning,
+            rule: "Capitalization".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
+        });
+    }
+
+    issues
+}
+
+/// "phrase: replacement" lines as lowercase word sequences, longest first so the
+/// longest match wins. Blank lines and # comments are skipped.
+fn parse_phrase_list(data: &str) -> Vec<(Vec<String>, String)> {
+    let mut phrases: Vec<(Vec<String>, String)> = data
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| {
+            let (phrase, replacement) = l.split_once(':')?;
+            let words: Vec<String> = phrase.split_whitespace().map(str::to_lowercase).collect();
+            (!words.is_empty()).then(|| (words, replacement.trim().to_string()))
+        })
+        .collect();
+    phrases.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+    phrases
+}
+
+/// Wordy phrases as lowercase word sequences, longest first so the longest match wins
+fn wordy_phrases() -> &'static [(Vec<String>, String)] {
+    static PHRASES: OnceLock<Vec<(Vec<String>, String)>> = OnceLock::new();
+    PHRASES.get_or_init(|| parse_phrase_list(WORDY_PHRASES_DATA))
+}
+
+/// Byte ranges of each word (letters and apostrophes) in text
+pub(crate) fn word_spans(text: &str) -> Vec<std::ops::Range<usize>> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        let in_word = c.is_alphanumeric() || c == '\'' || c == '’';
+        match (in_word, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                spans.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        spans.push(s..text.len());
+    }
+    spans
+}
+
+/// Carry the matched phrase's capitalization over to its replacement
+fn match_case(matched: &str, replacement: &str) -> String {
+    let letters: Vec<char> = matched.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.len() > 1 && letters.iter().all(|c| c.is_uppercase()) {
+        return replacement.to_uppercase();
+    }
+    match (matched.chars().next(), replacement.chars().next()) {
+        (Some(m), Some(r)) if m.is_uppercase() => {
+            r.to_uppercase().chain(replacement.chars().skip(1)).collect()
+        }
+        _ => replacement.to_string(),
+    }
+}
+
+/// Whole-word, case-insensitive matches of any listed phrase: the byte range
+/// from first to last word, and the replacement. Matches don't overlap.
+fn find_phrases<'a>(text: &str, phrases: &'a [(Vec<String>, String)]) -> Vec<(std::ops::Range<usize>, &'a str)> {
+    let words = word_spans(text);
+    let lowered: Vec<String> = words.iter().map(|w| text[w.clone()].to_lowercase()).collect();
+    let mut found = Vec::new();
+    let mut i = 0;
+
+    while i < words.len() {
+        let matched = phrases.iter().find(|(phrase, _)| {
+            lowered.len() >= i + phrase.len() && lowered[i..i + phrase.len()] == phrase[..]
+        });
+        let Some((phrase, replacement)) = matched else {
+            i += 1;
+            continue;
+        };
+        found.push((words[i].start..words[i + phrase.len() - 1].end, replacement.as_str()));
+        i += phrase.len();
+    }
+
+    found
+}
+
+/// Flag wordy multi-word phrases ("in order to") and suggest the concise form.
+/// The span covers the whole phrase, from its first to its last word. A phrase
+/// listed without a replacement is filler: the suggestion deletes it along with
+/// the spaces after it ("we are in the process of moving" → "we are moving").
+pub fn check_wordy_phrases(text: &str) -> Vec<GrammarIssue> {
+    find_phrases(text, wordy_phrases())
+        .into_iter()
+        .map(|(mut range, replacement)| {
+            if replacement.is_empty() {
+                range.end += text[range.end..].len() - text[range.end..].trim_start_matches(' ').len();
+            }
+            let original = &text[range.clone()];
+            let start = char_offset(text, range.start);
+            let message = if replacement.is_empty() {
+                format!("\"{}\" is wordy. The sentence reads the same without it.", original.trim_end())
+            } else {
+                format!("\"{}\" is wordy. \"{}\" says the same thing more directly.", original, replacement)
+            };
+            GrammarIssue {
+                start,
+                end: start + original.chars().count(),
+                message,
+                suggestions: vec![match_case(original, replacement)],
+                severity: Severity::Suggestion,
+                rule: "Style".to_string(),
+                best_fix: None,
+                matched_text: String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Bundled terms plus any from ~/.ghostpen/inclusive_terms.txt (same format),
+/// so the list can grow without a rebuild. Loaded once per run.
+fn inclusive_terms() -> &'static [(Vec<String>, String)] {
+    static TERMS: OnceLock<Vec<(Vec<String>, String)>> = OnceLock::new();
+    TERMS.get_or_init(|| {
+        let user = crate::paths::data_dir()
+            .and_then(|d| std::fs::read_to_string(d.join(USER_INCLUSIVE_TERMS_FILE)).ok())
+            .unwrap_or_default();
+        parse_phrase_list(&format!("{}\n{}", INCLUSIVE_TERMS_DATA, user))
+    })
+}
+
+/// Flag terms some readers find exclusionary and offer a neutral alternative.
+/// Opt-in via `inclusive_language` in config.toml.
+pub fn check_inclusive_language(text: &str) -> Vec<GrammarIssue> {
+    find_phrases(text, inclusive_terms())
+        .into_iter()
+        .map(|(range, alternative)| {
+            let original = &text[range.clone()];
+            let start = char_offset(text, range.start);
+            GrammarIssue {
+                start,
+                end: start + original.chars().count(),
+                message: format!(
+                    "Some readers may find \"{}\" exclusionary. \"{}\" means the same and works for everyone.",
+                    original, alternative
+                ),
+                suggestions: vec![match_case(original, alternative)],
+                severity: Severity::Suggestion,
+                rule: "Inclusivity".to_string(),
+                best_fix: None,
+                matched_text: String::new(),
+            }
+        })
+        .collect()
+}
+
+/// A confusable word, the word after it that makes the confusion near-certain,
+/// the intended word, and why. Only high-confidence pairs are listed — "your going
+/// away party" is rare next to "your going to love it", but "your right hand" is not.
+struct Homophone {
+    word: &'static str,
+    next_words: &'static [&'static str],
+    /// Previous words that make the word right after all ("Is there car trouble?")
+    not_after: &'static [&'static str],
+    correction: &'static str,
+    explanation: &'static str,
+}
+
+const HOMOPHONES: &[Homophone] = &[
+    Homophone {
+        word: "your",
+        next_words: &["welcome", "kidding", "joking", "going", "getting", "gonna", "not"],
+        not_after: &[],
+        correction: "you're",
+        explanation: "\"You're\" is short for \"you are\"; \"your\" shows ownership (your book).",
+    },
+    Homophone {
+        word: "there",
+        next_words: &["own", "car", "house", "home", "friends", "parents", "dog", "cat", "job", "phone", "phones"],
+        not_after: &["is", "are", "was", "were", "isn't", "aren't", "wasn't", "weren't"],
+        correction: "their",
+        explanation: "\"Their\" shows ownership (their car); \"there\" points to a place or starts \"there is\".",
+    },
+    Homophone {
+        word: "their",
+        next_words: &["is", "are", "was", "were"],
+        not_after: &[],
+        correction: "there",
+        explanation: "\"There is/are\" uses \"there\"; \"their\" shows ownership (their car).",
+    },
+    Homophone {
+        word: "its",
+        next_words: &["a", "an", "been", "not", "going"],
+        not_after: &[],
+        correction: "it's",
+        explanation: "\"It's\" is short for \"it is\" or \"it has\"; \"its\" shows ownership (its tail).",
+    },
+];
+
+/// Flag common homophone mix-ups (your/you're, there/their, its/it's) when the
+/// following word makes the intended one clear. Suggests the likely fix first,
+/// then the original so either can be picked.
+pub fn check_homophones(text: &str) -> Vec<GrammarIssue> {
+    let words = word_spans(text);
+    let mut issues = Vec::new();
+
+    for (i, pair) in words.windows(2).enumerate() {
+        let word = &text[pair[0].clone()];
+        let next = text[pair[1].clone()].to_lowercase();
+        // Words split by sentence punctuation aren't related ("...over there. Cars")
+        if !text[pair[0].end..pair[1].start].trim().is_empty() {
+            continue;
+        }
+        let previous = i
+            .checked_sub(1)
+            .filter(|&p| text[words[p].end..pair[0].start].trim().is_empty())
+            .map(|p| text[words[p].clone()].to_lowercase());
+        let Some(rule) = HOMOPHONES.iter().find(|h| {
+            h.word.eq_ignore_ascii_case(word)
+                && h.next_words.contains(&next.as_str())
+                && !previous.as_deref().is_some_and(|p| h.not_after.contains(&p))
+        }) else {
+            continue;
+        };
+
+        let start = char_offset(text, pair[0].start);
+        issues.push(GrammarIssue {
+            start,
+            end: start + word.chars().count(),
+            message: format!("Did you mean \"{}\"? {}", rule.correction, rule.explanation),
+            suggestions: vec![match_case(word, rule.correction), word.to_string()],
+            severity: Severity::Warning,
+            rule: "WordChoice".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
+        });
+    }
+
+    issues
+}
+
+/// Lowercase spelling → (pair index, the other spelling), covering both sides of every pair
+fn spelling_variants() -> &'static HashMap<String, (usize, String)> {
+    static VARIANTS: OnceLock<HashMap<String, (usize, String)>> = OnceLock::new();
+    VARIANTS.get_or_init(|| {
+        let mut map = HashMap::new();
+        let pairs = SPELLING_VARIANTS_DATA
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(|l| l.split_once(':'))
+            .map(|(a, b)| (a.trim().to_lowercase(), b.trim().to_lowercase()));
+        for (index, (american, british)) in pairs.enumerate() {
+            map.insert(american.clone(), (index, british.clone()));
+            map.insert(british, (index, american));
+        }
+        map
+    })
+}
+
+/// Flag spellings that disagree with the rest of the document ("organise" in a
+/// piece that mostly says "organize"). Each variant pair is judged on its own:
+/// the minority spelling is flagged with the majority one as the suggestion.
+/// On a tie, whichever spelling appears first wins.
+pub fn check_spelling_consistency(text: &str) -> Vec<GrammarIssue> {
+    let variants = spelling_variants();
+    // Per pair: each spelling seen, with its occurrences in order
+    let mut seen: HashMap<usize, Vec<(String, Vec<std::ops::Range<usize>>)>> = HashMap::new();
+    for span in word_spans(text) {
+        let word = text[span.clone()].to_lowercase();
+        let Some((index, _)) = variants.get(&word) else { continue };
+        let spellings = seen.entry(*index).or_default();
+        match spellings.iter_mut().find(|(w, _)| *w == word) {
+            Some((_, spans)) => spans.push(span),
+            None => spellings.push((word, vec![span])),
+        }
+    }
+
+    let mut issues = Vec::new();
+    for spellings in seen.values() {
+        let [first, second] = spellings.as_slice() else { continue };
+        let (majority, minority) = if second.1.len() > first.1.len() { (second, first) } else { (first, second) };
+        for span in &minority.1 {
+            let original = &text[span.clone()];
+            let start = char_offset(text, span.start);
+            issues.push(GrammarIssue {
+                start,
+                end: start + original.chars().count(),
+                message: format!(
+                    "\"{}\" is spelled \"{}\" elsewhere in this document. Pick one spelling and stick with it.",
+                    original, majority.0
+                ),
+                suggestions: vec![match_case(original, &majority.0)],
+                severity: Severity::Suggestion,
+                rule: "Consistency".to_string(),
+                best_fix: None,
+                matched_text: String::new(),
+            });
+        }
+    }
+    issues.sort_by_key(|issue| issue.start);
+    issues
+}
+
+/// One compound-word rule from compounds.txt
+struct Compound {
+    /// Lowercase words of the written form; hyphens count as word breaks
+    words: Vec<String>,
+    preferred: String,
+    /// Only flag before a noun ("a well-known author", not "is well known")
+    modifier: bool,
+}
+
+/// Compound rules, longest first so "state of the art" wins over anything shorter
+fn compounds() -> &'static [Compound] {
+    static COMPOUNDS: OnceLock<Vec<Compound>> = OnceLock::new();
+    COMPOUNDS.get_or_init(|| {
+        let mut compounds: Vec<Compound> = COMPOUNDS_DATA
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(|l| {
+                let mut parts = l.splitn(3, ':').map(str::trim);
+                let (written, preferred) = (parts.next()?, parts.next()?);
+                let words: Vec<String> = word_spans(written).into_iter().map(|w| written[w].to_lowercase()).collect();
+                (words.len() > 1).then(|| Compound {
+                    words,
+                    preferred: preferred.to_string(),
+                    modifier: parts.next() == Some("modifier"),
+                })
+            })
+            .collect();
+        compounds.sort_by(|a, b| b.words.len().cmp(&a.words.len()));
+        compounds
+    })
+}
+
+/// Words that open a noun phrase. A compound modifier must follow one, which keeps
+/// adverbial uses like "in real time today" or "met one time yesterday" quiet.
+const DETERMINERS: &[&str] = &[
+    "a", "an", "the", "this", "that", "these", "those", "my", "your", "our", "their", "his", "her", "its",
+    "each", "every", "some", "any", "no", "another", "such",
+];
+
+/// Whether the compound spanning words `start..end` sits in a noun phrase: right
+/// after a determiner, with what looks like a noun after it
+fn modifier_position(text: &str, words: &[std::ops::Range<usize>], lowered: &[String], start: usize, end: usize) -> bool {
+    start > 0 && DETERMINERS.contains(&lowered[start - 1].as_str()) && looks_like_noun(text, words, lowered, end)
+}
+
+/// Rough noun test for the word at `index`, which follows a compound: it's on the
+/// same line after a single space, all letters, and neither a function word nor
+/// an -ly adverb. Good enough to pass "a well known author" and skip "is well known."
+fn looks_like_noun(text: &str, words: &[std::ops::Range<usize>], lowered: &[String], index: usize) -> bool {
+    let (Some(next), Some(word)) = (words.get(index), lowered.get(index)) else {
+        return false;
+    };
+    &text[words[index - 1].end..next.start] == " "
+        && word.chars().all(char::is_alphabetic)
+        && !word.ends_with("ly")
+        && !crate::repetition::bundled_stopwords().contains(word)
+}
+
+/// Flag compounds written the wrong way: open modifiers before a noun ("a well
+/// known author" → "well-known") and outdated forms ("e-mail" → "email").
+pub fn check_compounds(text: &str) -> Vec<GrammarIssue> {
+    let words = word_spans(text);
+    let lowered: Vec<String> = words.iter().map(|w| text[w.clone()].to_lowercase()).collect();
+    let mut issues = Vec::new();
+    let mut i = 0;
+
+    while i < words.len() {
+        let matched = compounds().iter().find(|c| {
+            let end = i + c.words.len();
+            end <= words.len()
+                && lowered[i..end] == c.words[..]
+                // Only spaces or hyphens between the parts — not "well. Known"
+                && words[i..end].windows(2).all(|pair| text[pair[0].end..pair[1].start].chars().all(|ch| ch == ' ' || ch == '-'))
+                && (!c.modifier || modifier_position(text, &words, &lowered, i, end))
+        });
+        let Some(compound) = matched else {
+            i += 1;
+            continue;
+        };
+        let range = words[i].start..words[i + compound.words.len() - 1].end;
+        i += compound.words.len();
+
+        let original = &text[range.clone()];
+        if original.eq_ignore_ascii_case(&compound.preferred) {
+            continue;
+        }
+        let message = if compound.modifier {
+            format!("Hyphenate a compound modifier before a noun: \"{}\".", compound.preferred)
+        } else {
+            format!("\"{}\" is now usually written \"{}\".", original, compound.preferred)
+        };
+        let start = char_offset(text, range.start);
+        issues.push(GrammarIssue {
+            start,
+            end: start + original.chars().count(),
+            message,
+            suggestions: vec![match_case(original, &compound.preferred)],
+            severity: Severity::Suggestion,
+            rule: "Style".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
+        });
+    }
+
+    issues
+}
+
+/// Values for the `oxford_comma` setting
+pub const SERIAL_COMMA_STYLES: &[&str] = &["require", "forbid", "off"];
+
+/// Whether the words before the conjunction at `conj` end a simple list: an item
+/// of up to three content words, a comma, and another content word ("apples,
+/// pears"). Items with function words ("When we left, the dog and cat") don't count,
+/// nor does a lone word opening the sentence, which is usually an introductory
+/// word ("Yesterday, Mary and John left") rather than a first item.
+fn ends_list(text: &str, words: &[std::ops::Range<usize>], lowered: &[String], conj: usize) -> bool {
+    let stopwords = crate::repetition::bundled_stopwords();
+    let mut first = conj - 1;
+    loop {
+        if stopwords.contains(&lowered[first]) || conj - first > 3 || first == 0 {
+            return false;
+        }
+        let gap = &text[words[first - 1].end..words[first].start];
+        if gap == " " {
+            first -= 1;
+            continue;
+        }
+        let after_comma = gap.strip_prefix(',');
+        let previous = first - 1;
+        let opens_sentence = previous == 0 || text[words[previous - 1].end..words[previous].start].contains(['.', '!', '?', '\n']);
+        return after_comma.is_some_and(|rest| !rest.is_empty() && rest.trim().is_empty())
+            && !stopwords.contains(&lowered[previous])
+            && !opens_sentence;
+    }
+}
+
+/// Flag the serial comma in lists of three or more ("A, B and C") per house style:
+/// "require" flags a missing one, "forbid" flags one that's there, anything else
+/// checks nothing. Only simple lists of short items are recognized.
+pub fn check_serial_comma(text: &str, style: &str) -> Vec<GrammarIssue> {
+    let require = match style {
+        "require" => true,
+        "forbid" => false,
+        _ => return vec![],
+    };
+    let words = word_spans(text);
+    let lowered: Vec<String> = words.iter().map(|w| text[w.clone()].to_lowercase()).collect();
+    let mut issues = Vec::new();
+
+    for conj in 1..words.len().saturating_sub(1) {
+        if lowered[conj] != "and" && lowered[conj] != "or" {
+            continue;
+        }
+        let has_comma = match text[words[conj - 1].end..words[conj].start].trim() {
+            "," => true,
+            "" => false,
+            _ => continue,
+        };
+        let last_item_follows = text[words[conj].end..words[conj + 1].start].trim().is_empty();
+        if has_comma == require || !last_item_follows || !ends_list(text, &words, &lowered, conj) {
+            continue;
+        }
+
+        let range = words[conj - 1].start..words[conj].end;
+        let original = &text[range.clone()];
+        let (item, conjunction) = (&text[words[conj - 1].clone()], &text[words[conj].clone()]);
+        let (message, suggestion) = if require {
+            (
+                format!("Add a serial comma before \"{}\" to end this list.", conjunction),
+                format!("{}, {}", item, conjunction),
+            )
+        } else {
+            (
+                format!("House style leaves out the serial comma before \"{}\".", conjunction),
+                format!("{} {}", item, conjunction),
+            )
+        };
+        let start = char_offset(text, range.start);
+        issues.push(GrammarIssue {
+            start,
+            end: start + original.chars().count(),
+            message,
+            suggestions: vec![suggestion],
+            severity: Severity::Suggestion,
+            rule: "Style".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
+        });
+    }
+    issues
+}
+
+/// Lowercase name → its capitalized form
+fn proper_nouns() -> &'static HashMap<String, String> {
+    static NOUNS: OnceLock<HashMap<String, String>> = OnceLock::new();
+    NOUNS.get_or_init(|| {
+        PROPER_NOUNS_DATA
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|l| (l.to_lowercase(), l.to_string()))
+            .collect()
+    })
+}
+
+/// Flag listed proper nouns written in lowercase ("english", "monday"), suggesting
+/// the capitalized form. Sentence-initial words are left to check_sentence_start.
+pub fn check_proper_nouns(text: &str) -> Vec<GrammarIssue> {
+    let sentence_starts: Vec<usize> = segment::sentence_spans(text)
+        .into_iter()
+        .filter_map(|span| word_spans(&text[span.clone()]).first().map(|w| span.start + w.start))
+        .collect();
+
+    let mut issues = Vec::new();
+    for span in word_spans(text) {
+        let word = &text[span.clone()];
+        if word.chars().any(|c| c.is_uppercase()) || sentence_starts.contains(&span.start) {
+            continue;
+        }
+        let Some(name) = proper_nouns().get(word) else { continue };
+        let start = char_offset(text, span.start);
+        issues.push(GrammarIssue {
+            start,
+            end: start + word.chars().count(),
+            message: format!("\"{}\" is a proper noun and should be capitalized.", name),
+            suggestions: vec![name.clone()],
+            severity: Severity::Warning,
+            rule: "Capitalization".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
+        });
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lowercase_sentence_starts_are_flagged() {
+        let issues = check_sentence_start("the cat sat. it was happy.");
+        let spans: Vec<(usize, usize)> = issues.iter().map(|i| (i.start, i.end)).collect();
+        assert_eq!(spans, vec![(0, 1), (13, 14)]);
+        assert_eq!(issues[0].suggestions, vec!["T"]);
+        assert_eq!(issues[1].suggestions, vec!["I"]);
+    }
+
+    #[test]
+    fn brand_names_and_capitals_are_skipped() {
+        assert!(check_sentence_start("iPhone sales rose. eBay did too. Fine here.").is_empty());
+        assert!(check_sentence_start("\"Quoted start.\" 42 is a number.").is_empty());
+    }
+
+    #[test]
+    fn in_order_to_becomes_to() {
+        let text = "We left early in order to catch the train.";
+        let issues = check_wordy_phrases(text);
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].start, issues[0].end), (14, 25));
+        assert_eq!(issues[0].suggestions, vec!["to"]);
+        assert_eq!(issues[0].rule, "Style");
+    }
+
+    #[test]
+    fn wordy_match_preserves_capitalization_and_prefers_longest() {
+        let issues = check_wordy_phrases("Due to the fact that it rained, we stayed in.");
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].start, issues[0].end), (0, 20));
+        assert_eq!(issues[0].suggestions, vec!["Because"]);
+        assert!(check_wordy_phrases("The order took time.").is_empty());
+    }
+
+    #[test]
+    fn filler_phrase_is_deleted_with_its_space() {
+        let text = "We are in the process of moving.";
+        let issues = check_wordy_phrases(text);
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].start, issues[0].end), (7, 25));
+        assert_eq!(issues[0].suggestions, vec![""]);
+        let fixed = format!("{}{}", &text[..7], &text[25..]);
+        assert_eq!(fixed, "We are moving.");
+    }
+
+    #[test]
+    fn serial_comma_required() {
+        let text = "We bought apples, pears and plums.";
+        let issues = check_serial_comma(text, "require");
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].start, issues[0].end), (18, 27));
+        assert_eq!(issues[0].suggestions, vec!["pears, and"]);
+        assert_eq!(issues[0].rule, "Style");
+
+        assert!(check_serial_comma("We bought apples, pears, and plums.", "require").is_empty());
+        assert!(check_serial_comma("Tea or coffee and cake.", "require").is_empty());
+        assert!(check_serial_comma("When we arrived, the dog and cat hissed.", "require").is_empty());
+        assert!(check_serial_comma("Yesterday, Mary and John left.", "require").is_empty());
+        assert_eq!(check_serial_comma("Yesterday, Mary, Sue and John left.", "require")[0].suggestions, vec!["Sue, and"]);
+        assert!(check_serial_comma(text, "off").is_empty());
+    }
+
+    #[test]
+    fn serial_comma_forbidden() {
+        let text = "Bring red paint, blue paint, or chalk.";
+        let issues = check_serial_comma(text, "forbid");
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].start, issues[0].end), (22, 31));
+        assert_eq!(issues[0].suggestions, vec!["paint or"]);
+
+        assert!(check_serial_comma("Bring red paint, blue paint or chalk.", "forbid").is_empty());
+    }
+
+    #[test]
+    fn your_welcome_suggests_youre() {
+        let issues = check_homophones("Your welcome to join us.");
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].start, issues[0].end), (0, 4));
+        assert_eq!(issues[0].suggestions, vec!["You're", "Your"]);
+    }
+
+    #[test]
+    fn there_car_suggests_their() {
+        let issues = check_homophones("They parked there car outside.");
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].start, issues[0].end), (12, 17));
+        assert_eq!(issues[0].suggestions[0], "their");
+    }
+
+    #[test]
+    fn correct_homophones_are_not_flagged() {
+        assert!(check_homophones("Your car is over there. Their house is big. There is time.").is_empty());
+        assert!(check_homophones("Put it there, car keys too.").is_empty());
+    }
+
+    #[test]
+    fn idioms_and_questions_are_not_homophone_mixups() {
+        assert!(check_homophones("Is there money left?").is_empty());
+        assert!(check_homophones("Are there jobs available?").is_empty());
+        assert!(check_homophones("Was there car trouble on the way?").is_empty());
+        assert!(check_homophones("It was all your doing.").is_empty());
+        assert!(check_homophones("The team spent its time well.").is_empty());
+    }
+
+    #[test]
+    fn mixed_organize_spellings_flag_the_minority() {
+        let text = "We organize the files. Then we organise the desk. Organize everything.";
+        let issues = check_spelling_consistency(text);
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].start, issues[0].end), (31, 39));
+        assert_eq!(issues[0].suggestions, vec!["organize"]);
+        assert_eq!(issues[0].rule, "Consistency");
+
+        assert!(check_spelling_consistency("Organise and organised are both British.").is_empty());
+        // Both spellings are correct British usage, each in its own sense
+        assert!(check_spelling_consistency("The programme ran a computer program past the parking meter, 100 metres away.").is_empty());
+    }
+
+    #[test]
+    fn inclusive_terms_get_neutral_alternatives() {
+        let issues = check_inclusive_language("Add it to the whitelist. The Chairman agreed.");
+        let fixes: Vec<&str> = issues.iter().map(|i| i.suggestions[0].as_str()).collect();
+        assert_eq!(fixes, vec!["allowlist", "Chair"]);
+        assert_eq!((issues[0].start, issues[0].end), (14, 23));
+        assert!(issues.iter().all(|i| i.rule == "Inclusivity"));
+    }
+
+    #[test]
+    fn lowercase_languages_and_days_are_capitalized() {
+        let issues = check_proper_nouns("I speak english on monday.");
+        let fixes: Vec<(usize, usize, &str)> =
+            issues.iter().map(|i| (i.start, i.end, i.suggestions[0].as_str())).collect();
+        assert_eq!(fixes, vec![(8, 15, "English"), (19, 25, "Monday")]);
+        assert!(issues.iter().all(|i| i.rule == "Capitalization"));
+    }
+
+    #[test]
+    fn proper_nouns_skip_sentence_starts_and_ambiguous_words() {
+        // "monday" opening a sentence belongs to the sentence-start check
+        assert!(check_proper_nouns("We met. monday was busy.").is_empty());
+        assert!(check_proper_nouns("It may march on in august. English is fine.").is_empty());
+    }
+
+    #[test]
+    fn well_known_before_a_noun_is_hyphenated() {
+        let issues = check_compounds("She is a well known author.");
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].start, issues[0].end), (9, 19));
+        assert_eq!(issues[0].suggestions, vec!["well-known"]);
+        assert_eq!(issues[0].rule, "Style");
+    }
+
+    #[test]
+    fn open_modifiers_elsewhere_are_left_alone() {
+        assert!(check_compounds("The author is well known.").is_empty());
+        assert!(check_compounds("She is well known for her novels.").is_empty());
+        assert!(check_compounds("A well-known author. Up to date!").is_empty());
+        assert!(check_compounds("We render in real time today.").is_empty());
+        assert!(check_compounds("We met one time yesterday.").is_empty());
+        assert_eq!(check_compounds("Our real time system and a one time fee.").len(), 2);
+    }
+
+    #[test]
+    fn outdated_closed_compounds_are_flagged_anywhere() {
+        let issues = check_compounds("Send an E-mail to the web site.");
+        let fixes: Vec<&str> = issues.iter().map(|i| i.suggestions[0].as_str()).collect();
+        assert_eq!(fixes, vec!["Email", "website"]);
+        assert!(check_compounds("Send an email.").is_empty());
+    }
+
+    #[test]
+    fn inclusive_terms_match_whole_words_only() {
+        // "manpower" is listed; "manpowered" and "humankind" are not
+        assert!(check_inclusive_language("A manpowered glider for humankind.").is_empty());
+        assert_eq!(check_inclusive_language("We lack manpower.").len(), 1);
+    }
+}
@@ -0,0 +1,222 @@
+//! clear_data: delete Ghostpen's local files by category. Only fixed file names
+//! under the data, config, and log locations are touched, plus the profile files
+//! in profiles/, and symlinks are unlinked rather than followed, so nothing
+//! outside those directories goes.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Categories clear_data accepts, besides "all". "cache" is the in-memory rewrite
+/// cache only; "session" is the remembered mode and dialect history; "custom"
+/// is the user's own modes, abbreviations, and inclusive terms.
+pub const CATEGORIES: &[&str] =
+    &["audit", "feedback", "dictionary", "ignores", "cache", "session", "custom", "profiles", "config"];
+
+/// What clear_data removed
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct ClearReport {
+    /// Categories that were cleared, in CATEGORIES order
+    pub cleared: Vec<String>,
+    /// Files deleted; categories with nothing on disk add nothing here
+    pub removed: Vec<String>,
+}
+
+/// Where each category lives
+pub struct Locations {
+    pub data_dir: PathBuf,
+    pub config_file: PathBuf,
+    pub log_dir: PathBuf,
+}
+
+impl Locations {
+    pub fn current() -> Result<Self, String> {
+        Ok(Self {
+            data_dir: crate::paths::data_dir().ok_or("Could not find the data directory")?,
+            config_file: crate::paths::config_file().ok_or("Could not find config.toml")?,
+            log_dir: crate::paths::log_dir(),
+        })
+    }
+
+    fn files(&self, category: &str) -> Vec<PathBuf> {
+        match category {
+            "audit" => vec![self.log_dir.join("audit.jsonl")],
+            "feedback" => vec![self.data_dir.join("feedback.jsonl")],
+            "dictionary" => vec![self.data_dir.join("dictionary.txt")],
+            "ignores" => vec![self.data_dir.join("ignores.json"), self.data_dir.join("learned_ignores.json")],
+            "cache" => vec![],
+            "session" => vec![self.data_dir.join("session.json")],
+            "custom" => ["modes.toml", "abbreviations.txt", "inclusive_terms.txt"]
+                .iter()
+                .map(|name| self.data_dir.join(name))
+                .collect(),
+            "profiles" => self.profile_files(),
+            "config" => vec![self.config_file.clone()],
+            _ => vec![],
+        }
+    }
+
+    /// Saved profiles and the active-profile marker. A profiles/ that is itself
+    /// a symlink is unlinked as one entry, never listed.
+    fn profile_files(&self) -> Vec<PathBuf> {
+        let dir = self.data_dir.join("profiles");
+        match std::fs::symlink_metadata(&dir) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => return vec![dir],
+            Err(_) => return vec![],
+        }
+        let Ok(entries) = std::fs::read_dir(&dir) else { return vec![] };
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e == "toml") || path.file_name().is_some_and(|n| n == ".active"))
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+/// Resolve the requested names to categories. "all" must be named explicitly;
+/// an empty list or an unknown name is an error, checked before anything is deleted.
+fn select(what: &[String]) -> Result<Vec<&'static str>, String> {
+    if what.is_empty() {
+        return Err(format!("Pick what to clear: {} or \"all\"", CATEGORIES.join(", ")));
+    }
+    let names: Vec<String> = what.iter().map(|w| w.trim().to_lowercase()).collect();
+    if let Some(unknown) = names.iter().find(|n| *n != "all" && !CATEGORIES.contains(&n.as_str())) {
+        return Err(format!("Unknown data category '{}'. Use {} or \"all\".", unknown, CATEGORIES.join(", ")));
+    }
+    let all = names.iter().any(|n| n == "all");
+    Ok(CATEGORIES.iter().copied().filter(|c| all || names.iter().any(|n| n == c)).collect())
+}
+
+/// Delete one file. A symlink is unlinked, never followed; a directory is refused.
+fn remove(path: &Path) -> Result<bool, String> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(format!("Failed to inspect {}: {}", path.display(), e)),
+    };
+    if metadata.is_dir() {
+        return Err(format!("Refusing to delete directory {}", path.display()));
+    }
+    std::fs::remove_file(path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+    Ok(true)
+}
+
+pub fn clear(what: &[String]) -> Result<ClearReport, String> {
+    clear_in(what, &Locations::current()?)
+}
+
+pub fn clear_in(what: &[String], locations: &Locations) -> Result<ClearReport, String> {
+    let mut report = ClearReport::default();
+    for category in select(what)? {
+        if category == "cache" {
+            crate::llm::clear_cache();
+        }
+        for path in locations.files(category) {
+            if remove(&path)? {
+                report.removed.push(path.display().to_string());
+            }
+        }
+        if category == "profiles" {
+            // Only goes if nothing but profiles was in it
+            let _ = std::fs::remove_dir(locations.data_dir.join("profiles"));
+        }
+        report.cleared.push(category.to_string());
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_locations(name: &str) -> Locations {
+        let root = std::env::temp_dir().join(format!("ghostpen-clear-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("logs")).unwrap();
+        Locations {
+            data_dir: root.clone(),
+            config_file: root.join("config.toml"),
+            log_dir: root.join("logs"),
+        }
+    }
+
+    #[test]
+    fn only_selected_categories_are_removed() {
+        let locations = temp_locations("selected");
+        for file in ["feedback.jsonl", "dictionary.txt", "ignores.json", "config.toml", "logs/audit.jsonl"] {
+            std::fs::write(locations.data_dir.join(file), "x").unwrap();
+        }
+
+        let report = clear_in(&["ignores".to_string(), "Feedback".to_string()], &locations).unwrap();
+        assert_eq!(report.cleared, vec!["feedback", "ignores"]);
+        assert_eq!(report.removed.len(), 2);
+        assert!(!locations.data_dir.join("feedback.jsonl").exists());
+        assert!(!locations.data_dir.join("ignores.json").exists());
+        assert!(locations.data_dir.join("dictionary.txt").exists());
+        assert!(locations.config_file.exists());
+
+        let report = clear_in(&["all".to_string()], &locations).unwrap();
+        assert_eq!(report.cleared, CATEGORIES);
+        assert!(!locations.data_dir.join("dictionary.txt").exists());
+        assert!(!locations.log_dir.join("audit.jsonl").exists());
+        assert!(!locations.config_file.exists());
+        let _ = std::fs::remove_dir_all(&locations.data_dir);
+    }
+
+    #[test]
+    fn cache_keeps_preferences_and_all_leaves_nothing() {
+        let locations = temp_locations("everything");
+        std::fs::create_dir_all(locations.data_dir.join("profiles")).unwrap();
+        let files = [
+            "session.json",
+            "modes.toml",
+            "abbreviations.txt",
+            "inclusive_terms.txt",
+            "profiles/Email.toml",
+            "profiles/.active",
+        ];
+        for file in files {
+            std::fs::write(locations.data_dir.join(file), "x").unwrap();
+        }
+
+        let report = clear_in(&["cache".to_string()], &locations).unwrap();
+        assert!(report.removed.is_empty());
+        assert!(locations.data_dir.join("session.json").exists());
+
+        clear_in(&["all".to_string()], &locations).unwrap();
+        for file in files {
+            assert!(!locations.data_dir.join(file).exists(), "{} left behind", file);
+        }
+        assert!(!locations.data_dir.join("profiles").exists());
+        let _ = std::fs::remove_dir_all(&locations.data_dir);
+    }
+
+    #[test]
+    fn empty_or_unknown_selection_deletes_nothing() {
+        let locations = temp_locations("invalid");
+        std::fs::write(locations.data_dir.join("dictionary.txt"), "x").unwrap();
+
+        assert!(clear_in(&[], &locations).is_err());
+        let err = clear_in(&["dictionary".to_string(), "everything".to_string()], &locations).unwrap_err();
+        assert!(err.contains("everything"));
+        assert!(locations.data_dir.join("dictionary.txt").exists());
+        let _ = std::fs::remove_dir_all(&locations.data_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_unlinked_not_followed() {
+        let locations = temp_locations("symlink");
+        let outside = std::env::temp_dir().join(format!("ghostpen-clear-outside-{}.txt", std::process::id()));
+        std::fs::write(&outside, "keep me").unwrap();
+        std::os::unix::fs::symlink(&outside, locations.data_dir.join("dictionary.txt")).unwrap();
+
+        clear_in(&["dictionary".to_string()], &locations).unwrap();
+        assert!(std::fs::symlink_metadata(locations.data_dir.join("dictionary.txt")).is_err());
+        assert_eq!(std::fs::read_to_string(&outside).unwrap(), "keep me");
+        let _ = std::fs::remove_file(&outside);
+        let _ = std::fs::remove_dir_all(&locations.data_dir);
+    }
+}
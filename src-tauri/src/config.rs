@@ -0,0 +1,465 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Loopback address LM Studio / Ollama are probed on. Windows resolves "localhost"
+/// to IPv6 ::1 while both servers bind IPv4 by default, so use the literal.
+const DEFAULT_LLM_HOST: &str = "127.0.0.1";
+
+/// Health-check paths probed during provider detection
+const DEFAULT_LMSTUDIO_PROBE_PATH: &str = "/v1/models";
+const DEFAULT_OLLAMA_PROBE_PATH: &str = "/";
+
+/// Sampling temperature for rewrites — low keeps small models on-task
+const DEFAULT_TEMPERATURE: f32 = 0.3;
+
+/// Average adult silent-reading and speaking speeds
+const DEFAULT_READING_WPM: usize = 200;
+const DEFAULT_SPEAKING_WPM: usize = 130;
+const DEFAULT_CHECK_DEBOUNCE_MS: u64 = 150;
+
+/// rewrite_recommendation suggests a rewrite past either of these
+const DEFAULT_REWRITE_ISSUE_DENSITY: f32 = 5.0;
+const DEFAULT_REWRITE_GRADE_LEVEL: f32 = 12.0;
+
+/// A faithful edit keeps most of the original's content words; under a fifth
+/// in common means the text was replaced rather than rewritten
+const DEFAULT_MIN_FIDELITY: f32 = 0.2;
+
+/// Lint documents over ~50 KB in chunks
+const DEFAULT_CHUNK_SIZE: usize = 50_000;
+
+/// Words used three or more times show up in repetition_report
+const DEFAULT_REPETITION_THRESHOLD: usize = 2;
+
+/// Rough token budget for a 2048-context model once the prompt and reply are accounted for
+const DEFAULT_MAX_INPUT_TOKENS: usize = 1500;
+
+/// A rewrite reply is roughly the text again plus a short explanation, while the
+/// prompt also carries the system prompt and examples
+const DEFAULT_COMPLETION_RATIO: f32 = 0.5;
+
+/// Environment variable that forces offline mode ("1", "true", or "yes")
+pub const OFFLINE_ENV: &str = "GHOSTPEN_OFFLINE";
+
+fn env_flag_set(value: Option<&str>) -> bool {
+    matches!(value.map(|v| v.trim().to_ascii_lowercase()).as_deref(), Some("1" | "true" | "yes"))
+}
+
+/// User settings, read from ~/.ghostpen/config.toml (or $GHOSTPEN_DATA_DIR/config.toml).
+/// Every field is optional in the file — missing keys fall back to defaults.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct Config {
+    /// Root directory for all Ghostpen data (portable installs).
+    /// The GHOSTPEN_DATA_DIR environment variable takes precedence.
+    pub data_dir: Option<String>,
+    /// Host the local LLM servers listen on: an IPv4/IPv6 literal, a LAN address,
+    /// or "localhost" (which probes both 127.0.0.1 and ::1)
+    pub llm_host: String,
+    /// Path probed to detect LM Studio. The API is assumed to be mounted where
+    /// "/v1/models" starts, so "/llm/v1/models" suits a reverse proxy at /llm.
+    pub lmstudio_probe_path: String,
+    /// Path probed to detect Ollama, which is also where its API is mounted
+    pub ollama_probe_path: String,
+    /// Proxy URL for all outbound HTTP, overriding HTTP_PROXY / HTTPS_PROXY.
+    /// Loopback hosts always bypass it.
+    pub proxy: Option<String>,
+    /// Extra comma-separated hosts to reach without the proxy, added to NO_PROXY
+    pub no_proxy: Option<String>,
+    /// Hosts Ghostpen may send requests to besides loopback, e.g. a LAN or cloud
+    /// LLM server. Empty allows any host.
+    pub allowed_hosts: Vec<String>,
+    /// English dialect for grammar checks: american, british, australian, or canadian
+    pub dialect: String,
+    /// Spell-check dictionary: "curated" (Harper's default list) or "full"
+    /// (curated plus a bundled list of rarer words)
+    pub dictionary: String,
+    /// Spelling issues on words shorter than this many characters are dropped,
+    /// to quiet abbreviations like "nd" or "rc". Grammar and style are unaffected.
+    pub min_spell_length: usize,
+    /// Dismissing the same issue on this many documents ignores it everywhere.
+    /// 0 turns learning off.
+    pub learn_ignore_after: usize,
+    /// Only dismissals from the last this-many days count toward learn_ignore_after
+    pub learn_ignore_window_days: u32,
+    /// Documents longer than this many bytes are linted in paragraph-aligned
+    /// chunks of about this size. 0 lints everything in one pass.
+    pub chunk_size: usize,
+    /// Also flag terms some readers find exclusionary (opt-in)
+    pub inclusive_language: bool,
+    /// Serial comma house style: "require" flags "A, B and C", "forbid" flags
+    /// "A, B, and C", "off" checks neither
+    pub oxford_comma: String,
+    /// Maximum estimated input tokens accepted by rewrite before refusing pre-flight
+    pub max_input_tokens: usize,
+    /// Per-model overrides for max_input_tokens, keyed by model id (e.g. "qwen2.5:3b")
+    pub model_max_input_tokens: HashMap<String, usize>,
+    /// Prices of metered models, keyed by model id, for estimate_cost
+    pub model_prices: HashMap<String, ModelPrice>,
+    /// estimate_cost expects a reply this many times the prompt's token count
+    pub completion_ratio: f32,
+    /// Models to try in order, per provider ("ollama", "lmstudio"). When the
+    /// server refuses one (400/404), rewrite moves on to the next. Unset uses
+    /// the model detection picks.
+    pub models: HashMap<String, Vec<String>>,
+    /// Model to use for a rewrite mode (e.g. "formal" = "llama3.1:8b"), tried
+    /// before the provider's models
+    pub mode_models: HashMap<String, String>,
+    /// Disable every network feature (rewrites, LLM detection, launching LM Studio).
+    /// The GHOSTPEN_OFFLINE environment variable turns this on too.
+    pub offline: bool,
+    /// Sampling temperature sent with every rewrite
+    pub temperature: f32,
+    /// Reuse results for identical (text, mode, model, temperature) rewrites.
+    /// Off by default: output isn't deterministic unless temperature is near 0.
+    pub rewrite_cache: bool,
+    /// Strip markdown a model wraps around its rewrite (code fences, bold labels,
+    /// a lone list marker). The explanation is never touched.
+    pub strip_formatting: bool,
+    /// Rewrites allowed to run at once. A local model handles one generation at a
+    /// time well; raise this for backends that serve requests in parallel.
+    pub max_concurrent_rewrites: usize,
+    /// repetition_report lists words used more than this many times
+    pub repetition_threshold: usize,
+    /// Words repetition_report ignores. Replaces the bundled stopword list when set.
+    pub repetition_stopwords: Option<Vec<String>>,
+    /// Words per minute used for TextStats.reading_time_secs
+    pub reading_wpm: usize,
+    /// Words per minute used for TextStats.speaking_time_secs
+    pub speaking_wpm: usize,
+    /// check_grammar calls sharing a debounce_key that arrive within this many
+    /// milliseconds of each other cancel the earlier ones still running
+    pub check_debounce_ms: u64,
+    /// Rules whose findings are dropped, by name (see list_available_rules).
+    /// Case-insensitive.
+    pub disabled_rules: Vec<String>,
+    /// Issues per 100 words at which rewrite_recommendation suggests proofreading
+    pub rewrite_issue_density: f32,
+    /// Flesch-Kincaid grade at which rewrite_recommendation suggests a clarity rewrite
+    pub rewrite_grade_level: f32,
+    /// Rewrites sharing less than this share of content words with the original
+    /// (0–1) are flagged low_fidelity. 0 never flags.
+    pub min_fidelity: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            data_dir: None,
+            llm_host: DEFAULT_LLM_HOST.to_string(),
+            lmstudio_probe_path: DEFAULT_LMSTUDIO_PROBE_PATH.to_string(),
+            ollama_probe_path: DEFAULT_OLLAMA_PROBE_PATH.to_string(),
+            proxy: None,
+            no_proxy: None,
+            allowed_hosts: Vec::new(),
+            dialect: "american".to_string(),
+            dictionary: "curated".to_string(),
+            min_spell_length: 2,
+            learn_ignore_after: 3,
+            learn_ignore_window_days: 30,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            inclusive_language: false,
+            oxford_comma: "off".to_string(),
+            max_input_tokens: DEFAULT_MAX_INPUT_TOKENS,
+            model_max_input_tokens: HashMap::new(),
+            model_prices: HashMap::new(),
+            completion_ratio: DEFAULT_COMPLETION_RATIO,
+            models: HashMap::new(),
+            mode_models: HashMap::new(),
+            offline: false,
+            temperature: DEFAULT_TEMPERATURE,
+            rewrite_cache: false,
+            strip_formatting: false,
+            max_concurrent_rewrites: 1,
+            repetition_threshold: DEFAULT_REPETITION_THRESHOLD,
+            repetition_stopwords: None,
+            reading_wpm: DEFAULT_READING_WPM,
+            speaking_wpm: DEFAULT_SPEAKING_WPM,
+            check_debounce_ms: DEFAULT_CHECK_DEBOUNCE_MS,
+            disabled_rules: Vec::new(),
+            rewrite_issue_density: DEFAULT_REWRITE_ISSUE_DENSITY,
+            rewrite_grade_level: DEFAULT_REWRITE_GRADE_LEVEL,
+            min_fidelity: DEFAULT_MIN_FIDELITY,
+        }
+    }
+}
+
+impl Config {
+    /// True when offline mode is on, via config.toml or GHOSTPEN_OFFLINE
+    pub fn is_offline(&self) -> bool {
+        self.offline || env_flag_set(std::env::var(OFFLINE_ENV).ok().as_deref())
+    }
+
+    /// Input token limit for a model, falling back to the global limit
+    pub fn max_input_tokens_for(&self, model: &str) -> usize {
+        self.model_max_input_tokens
+            .get(model)
+            .copied()
+            .unwrap_or(self.max_input_tokens)
+    }
+}
+
+/// US dollars per million tokens, e.g. `[model_prices."gpt-4o-mini"]`
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+/// A single problem found in config.toml
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ConfigDiagnostic {
+    /// Offending key, or empty for file-level problems (e.g. syntax errors)
+    pub key: String,
+    pub message: String,
+}
+
+/// Result of validate_config. Errors make the whole file fall back to defaults;
+/// warnings are ignored settings or values that may not work as intended.
+#[derive(Serialize, Debug)]
+pub struct ConfigReport {
+    pub path: String,
+    pub exists: bool,
+    pub errors: Vec<ConfigDiagnostic>,
+    pub warnings: Vec<ConfigDiagnostic>,
+}
+
+/// Load ~/.ghostpen/config.toml with the active profile's settings on top.
+/// A missing or unparseable file yields defaults; a profile that doesn't fit is skipped.
+pub fn load() -> Config {
+    let content = read_config_file();
+    match crate::profiles::active_settings() {
+        Some(profile) => with_overrides(&content, profile).unwrap_or_else(|_| parse(&content).unwrap_or_default()),
+        None => parse(&content).unwrap_or_default(),
+    }
+}
+
+/// config.toml alone, ignoring profiles. For finding the data directory, which
+/// is where profiles are kept.
+pub(crate) fn load_file() -> Config {
+    parse(&read_config_file()).unwrap_or_default()
+}
+
+fn read_config_file() -> String {
+    crate::paths::config_file()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .unwrap_or_default()
+}
+
+/// Config text with `overrides` replacing its top-level keys
+pub(crate) fn with_overrides(content: &str, overrides: toml::Table) -> Result<Config, String> {
+    let mut table: toml::Table = toml::from_str(content).map_err(|e| format!("Invalid config.toml: {}", e))?;
+    table.extend(overrides);
+    toml::Value::Table(table).try_into().map_err(|e| format!("Invalid profile settings: {}", e))
+}
+
+pub(crate) fn parse(content: &str) -> Result<Config, String> {
+    toml::from_str(content).map_err(|e| format!("Invalid config.toml: {}", e))
+}
+
+/// Read and check ~/.ghostpen/config.toml without touching the network
+pub fn report() -> ConfigReport {
+    let path = crate::paths::config_file();
+    let display = path.as_ref().map(|p| p.display().to_string()).unwrap_or_default();
+    match path.and_then(|p| std::fs::read_to_string(p).ok()) {
+        Some(content) => {
+            let (errors, warnings) = validate(&content);
+            ConfigReport { path: display, exists: true, errors, warnings }
+        }
+        None => ConfigReport { path: display, exists: false, errors: vec![], warnings: vec![] },
+    }
+}
+
+fn diagnostic(key: &str, message: impl Into<String>) -> ConfigDiagnostic {
+    ConfigDiagnostic { key: key.to_string(), message: message.into() }
+}
+
+/// Check config text for syntax errors, unknown keys, and out-of-range values.
+/// Returns (errors, warnings).
+pub(crate) fn validate(content: &str) -> (Vec<ConfigDiagnostic>, Vec<ConfigDiagnostic>) {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    let table = match toml::from_str::<toml::Table>(content) {
+        Ok(table) => table,
+        Err(e) => {
+            errors.push(diagnostic("", format!("Syntax error: {}", e.message())));
+            return (errors, warnings);
+        }
+    };
+
+    // Known keys are whatever Config serializes — stays in sync as settings are added
+    let known = serde_json::to_value(Config::default()).unwrap_or_default();
+    for key in table.keys() {
+        if known.get(key).is_none() {
+            warnings.push(diagnostic(key, format!("Unknown setting '{}' is ignored. Check the spelling.", key)));
+        }
+    }
+
+    let config = match parse(content) {
+        Ok(config) => config,
+        Err(e) => {
+            errors.push(diagnostic("", e));
+            return (errors, warnings);
+        }
+    };
+
+    if !(0.0..=2.0).contains(&config.temperature) {
+        errors.push(diagnostic("temperature", format!("temperature must be between 0 and 2 (got {})", config.temperature)));
+    }
+    if config.max_input_tokens == 0 {
+        errors.push(diagnostic("max_input_tokens", "max_input_tokens must be greater than 0"));
+    }
+    for (model, price) in &config.model_prices {
+        if price.input_per_million < 0.0 || price.output_per_million < 0.0 {
+            errors.push(diagnostic("model_prices", format!("Prices for '{}' can't be negative", model)));
+        }
+    }
+    if config.completion_ratio < 0.0 {
+        errors.push(diagnostic("completion_ratio", "completion_ratio can't be negative"));
+    }
+    for (model, limit) in &config.model_max_input_tokens {
+        if *limit == 0 {
+            errors.push(diagnostic("model_max_input_tokens", format!("Limit for '{}' must be greater than 0", model)));
+        }
+    }
+    for provider in config.models.keys() {
+        if provider != "ollama" && provider != "lmstudio" {
+            warnings.push(diagnostic("models", format!("Unknown provider '{}' in models. Use ollama or lmstudio.", provider)));
+        }
+    }
+    if config.max_concurrent_rewrites == 0 {
+        errors.push(diagnostic("max_concurrent_rewrites", "max_concurrent_rewrites must be at least 1"));
+    }
+    if config.reading_wpm == 0 || config.speaking_wpm == 0 {
+        errors.push(diagnostic("reading_wpm", "reading_wpm and speaking_wpm must be greater than 0"));
+    }
+    if !(0.0..=1.0).contains(&config.min_fidelity) {
+        errors.push(diagnostic("min_fidelity", format!("min_fidelity must be between 0 and 1 (got {})", config.min_fidelity)));
+    }
+    if config.rewrite_issue_density <= 0.0 || config.rewrite_grade_level <= 0.0 {
+        errors.push(diagnostic("rewrite_issue_density", "rewrite_issue_density and rewrite_grade_level must be greater than 0"));
+    }
+    if crate::parse_dialect(&config.dialect).is_none() {
+        errors.push(diagnostic("dialect", format!("Unknown dialect '{}'. Use american, british, australian, or canadian.", config.dialect)));
+    }
+    if let Some(proxy) = config.proxy.as_deref().filter(|p| !p.trim().is_empty()) {
+        if let Err(e) = reqwest::Proxy::all(proxy) {
+            errors.push(diagnostic("proxy", format!("Invalid proxy URL '{}': {}", proxy, e)));
+        }
+    }
+    for host in crate::llm::candidate_hosts(&config.llm_host) {
+        if !crate::http::host_allowed(&host, &config) {
+            warnings.push(diagnostic("allowed_hosts", format!("llm_host '{}' isn't in allowed_hosts, so every request to it is refused", host)));
+        }
+    }
+    if !crate::checks::SERIAL_COMMA_STYLES.contains(&config.oxford_comma.as_str()) {
+        errors.push(diagnostic("oxford_comma", format!("Unknown oxford_comma '{}'. Use require, forbid, or off.", config.oxford_comma)));
+    }
+    if !crate::wordlist::VARIANTS.contains(&config.dictionary.as_str()) {
+        errors.push(diagnostic("dictionary", format!("Unknown dictionary '{}'. Use curated or full.", config.dictionary)));
+    }
+    for rule in &config.disabled_rules {
+        if !crate::AVAILABLE_RULES.iter().any(|r| r.eq_ignore_ascii_case(rule)) {
+            warnings.push(diagnostic("disabled_rules", format!("Unknown rule '{}' has no effect", rule)));
+        }
+    }
+    let host = config.llm_host.trim();
+    if host.is_empty() {
+        errors.push(diagnostic("llm_host", "llm_host is empty"));
+    } else if host.contains("://") || host.contains('/') {
+        errors.push(diagnostic("llm_host", format!("llm_host should be a bare host like 127.0.0.1, not a URL (got '{}')", host)));
+    } else {
+        // Probe paths are joined onto the host, so check the combined URL
+        let root = crate::llm::base_url(&crate::llm::candidate_hosts(host)[0], 80);
+        for (key, path) in [
+            ("lmstudio_probe_path", &config.lmstudio_probe_path),
+            ("ollama_probe_path", &config.ollama_probe_path),
+        ] {
+            if let Err(e) = crate::llm::probe_urls(&root, path, "") {
+                errors.push(diagnostic(key, e));
+            }
+        }
+    }
+
+    (errors, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_flag_from_env_or_config() {
+        assert!(env_flag_set(Some("1")));
+        assert!(env_flag_set(Some(" TRUE ")));
+        assert!(!env_flag_set(Some("0")));
+        assert!(!env_flag_set(None));
+        assert!(parse("offline = true").unwrap().is_offline());
+    }
+
+    #[test]
+    fn empty_file_uses_defaults() {
+        let config = parse("").unwrap();
+        assert_eq!(config.max_input_tokens, DEFAULT_MAX_INPUT_TOKENS);
+        assert_eq!(config.llm_host, "127.0.0.1");
+    }
+
+    #[test]
+    fn per_model_limit_overrides_global() {
+        let config = parse("max_input_tokens = 1000\n\n[model_max_input_tokens]\n\"llama3.1:8b\" = 6000\n").unwrap();
+        assert_eq!(config.max_input_tokens_for("llama3.1:8b"), 6000);
+        assert_eq!(config.max_input_tokens_for("qwen2.5:3b"), 1000);
+    }
+
+    #[test]
+    fn validate_reports_syntax_error() {
+        let (errors, warnings) = validate("temperature = = 0.5");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.starts_with("Syntax error"));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn validate_reports_unknown_key_and_range() {
+        let (errors, warnings) = validate("temprature = 0.5\ntemperature = 3.5\n");
+        assert_eq!(warnings, vec![diagnostic("temprature", "Unknown setting 'temprature' is ignored. Check the spelling.")]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].key, "temperature");
+    }
+
+    #[test]
+    fn validate_reports_wrong_type() {
+        let (errors, _) = validate("max_input_tokens = \"lots\"");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("Invalid config.toml"));
+    }
+
+    #[test]
+    fn validate_rejects_url_as_host() {
+        let (errors, _) = validate("llm_host = \"http://10.0.0.5:1234\"");
+        assert_eq!(errors[0].key, "llm_host");
+    }
+
+    #[test]
+    fn validate_rejects_unparseable_probe_path() {
+        let (errors, _) = validate("llm_host = \"bad host\"\nollama_probe_path = \"/ollama\"");
+        assert!(errors.iter().any(|e| e.key == "ollama_probe_path"));
+        let (errors, _) = validate("lmstudio_probe_path = \"/llm/v1/models\"");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn validate_warns_on_unknown_disabled_rule() {
+        let (errors, warnings) = validate("disabled_rules = [\"style\", \"Oxford\"]");
+        assert!(errors.is_empty());
+        assert_eq!(warnings, vec![diagnostic("disabled_rules", "Unknown rule 'Oxford' has no effect")]);
+    }
+
+    #[test]
+    fn validate_accepts_clean_file() {
+        let (errors, warnings) = validate("temperature = 0.7\nrewrite_cache = true\n");
+        assert!(errors.is_empty());
+        assert!(warnings.is_empty());
+    }
+}
@@ -0,0 +1,359 @@
+//! Accuracy corpus scoring: how many expected issues are caught (recall) and
+//! how many findings were expected (precision). grammar_corpus.json is scored
+//! against Harper alone; checks_corpus.json against the full check pipeline,
+//! supplemental checks included. Shared by the test_accuracy harness and the
+//! run_corpus command, so both score the same way.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Schema every corpus file follows, the one tests/grammar_corpus.json is checked against
+const SCHEMA: &str = include_str!("../../tests/grammar_corpus.schema.json");
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct CorpusEntry {
+    pub text: String,
+    /// config.toml overrides used when scoring through the full checks, e.g.
+    /// `{"oxford_comma": "forbid"}`. Harper-only scoring ignores them.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub settings: Map<String, Value>,
+    pub expected_issues: Vec<ExpectedIssue>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ExpectedIssue {
+    pub approximate_text: String,
+    #[serde(rename = "type")]
+    pub issue_type: String,
+}
+
+/// One finding, with byte offsets into the entry's text
+#[derive(Serialize, Clone, Debug)]
+pub struct FoundIssue {
+    pub start: usize,
+    pub end: usize,
+    pub message: String,
+    pub matched_text: String,
+}
+
+/// Per issue-type tallies. Unmatched findings have no type, so precision is
+/// only meaningful overall; `matched_findings` shows where hits come from.
+#[derive(Serialize, Default, Debug, PartialEq)]
+pub struct TypeMetrics {
+    pub expected: usize,
+    pub caught: usize,
+    /// Findings that matched an expectation of this type
+    pub matched_findings: usize,
+    pub recall: f64,
+}
+
+/// How one corpus entry fared
+#[derive(Serialize, Debug)]
+pub struct EntryResult {
+    pub text: String,
+    pub expected: usize,
+    pub found: usize,
+    pub caught: usize,
+    pub missed: Vec<ExpectedIssue>,
+    /// Findings that matched nothing expected (potential false positives)
+    pub extra: Vec<FoundIssue>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CorpusMetrics {
+    pub total_expected: usize,
+    pub total_found: usize,
+    /// Expected issues that were caught
+    pub true_positives: usize,
+    /// Findings that match an expected issue
+    pub matched_findings: usize,
+    /// Percentages, 0 when there is nothing to divide by
+    pub recall: f64,
+    pub precision: f64,
+    pub by_type: BTreeMap<String, TypeMetrics>,
+    pub entries: Vec<EntryResult>,
+}
+
+pub fn percent(part: usize, whole: usize) -> f64 {
+    if whole > 0 {
+        part as f64 / whole as f64 * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Validate `value` against the subset of JSON Schema the corpus schema uses:
+/// type, required, properties, additionalProperties, items, enum, minLength.
+/// Errors carry a JSON-pointer-style path, e.g. "/3/expected_issues/0/type".
+pub fn validate_schema(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let type_ok = match schema.get("type").and_then(Value::as_str) {
+        Some("array") => value.is_array(),
+        Some("object") => value.is_object(),
+        Some("string") => value.is_string(),
+        Some(_) | None => true,
+    };
+    if !type_ok {
+        errors.push(format!("{}: expected {}", path, schema["type"]));
+        return;
+    }
+
+    if let (Some(allowed), Some(s)) = (schema.get("enum").and_then(Value::as_array), value.as_str()) {
+        if !allowed.iter().any(|a| a.as_str() == Some(s)) {
+            errors.push(format!("{}: '{}' is not one of {}", path, s, schema["enum"]));
+        }
+    }
+    if let (Some(min), Some(s)) = (schema.get("minLength").and_then(Value::as_u64), value.as_str()) {
+        if (s.chars().count() as u64) < min {
+            errors.push(format!("{}: shorter than {} chars", path, min));
+        }
+    }
+
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            validate_schema(item, items, &format!("{}/{}", path, i), errors);
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for key in schema.get("required").and_then(Value::as_array).into_iter().flatten() {
+            let key = key.as_str().unwrap_or_default();
+            if !object.contains_key(key) {
+                errors.push(format!("{}: missing required '{}'", path, key));
+            }
+        }
+        for (key, child) in object {
+            match properties.and_then(|p| p.get(key)) {
+                Some(child_schema) => validate_schema(child, child_schema, &format!("{}/{}", path, key), errors),
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    errors.push(format!("{}: unknown key '{}'", path, key));
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// Parse corpus JSON, failing with every schema violation listed
+pub fn parse(content: &str) -> Result<Vec<CorpusEntry>, String> {
+    let corpus: Value = serde_json::from_str(content).map_err(|e| format!("Corpus is not valid JSON: {}", e))?;
+    let schema: Value = serde_json::from_str(SCHEMA).map_err(|e| e.to_string())?;
+    let mut errors = Vec::new();
+    validate_schema(&corpus, &schema, "", &mut errors);
+    if !errors.is_empty() {
+        return Err(format!("Corpus failed schema validation:\n  {}", errors.join("\n  ")));
+    }
+    serde_json::from_value(corpus).map_err(|e| e.to_string())
+}
+
+/// Harper's findings for `text` on the curated American rules, as `check_grammar`
+/// sees them before any supplemental checks or filters
+pub fn run_harper(text: &str) -> Vec<FoundIssue> {
+    found_issues(text, crate::harper_issues(text, harper_core::Dialect::American))
+}
+
+/// Every finding a check with `config` reports for `text`: Harper plus the
+/// supplemental checks, after the dictionary and disabled-rule filters
+pub fn run_checks(text: &str, config: &crate::config::Config) -> Vec<FoundIssue> {
+    found_issues(text, crate::collect_issues_with(text, config))
+}
+
+fn found_issues(text: &str, issues: Vec<crate::GrammarIssue>) -> Vec<FoundIssue> {
+    issues
+        .into_iter()
+        .map(|issue| {
+            let start = crate::char_to_byte(text, issue.start);
+            let end = crate::char_to_byte(text, issue.end);
+            FoundIssue { start, end, message: issue.message, matched_text: text[start..end].to_string() }
+        })
+        .collect()
+}
+
+/// Default settings with an entry's overrides applied. Unknown keys are errors,
+/// so a typo can't quietly leave a check off.
+fn entry_config(settings: &Map<String, Value>) -> Result<crate::config::Config, String> {
+    let mut config = serde_json::to_value(crate::config::Config::default()).map_err(|e| e.to_string())?;
+    let fields = config.as_object_mut().ok_or("settings are not an object")?;
+    for (key, value) in settings {
+        if !fields.contains_key(key) {
+            return Err(format!("unknown setting '{}'", key));
+        }
+        fields.insert(key.clone(), value.clone());
+    }
+    serde_json::from_value(config).map_err(|e| e.to_string())
+}
+
+/// Byte ranges of every case-insensitive occurrence of `needle` that sits on
+/// token boundaries, so "the" doesn't match inside "there"
+pub fn occurrences(text: &str, needle: &str) -> Vec<(usize, usize)> {
+    // ASCII lowercasing keeps byte offsets aligned with the original text
+    let haystack = text.to_ascii_lowercase();
+    let needle = needle.to_ascii_lowercase();
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric());
+
+    haystack
+        .match_indices(&needle)
+        .map(|(start, m)| (start, start + m.len()))
+        .filter(|&(start, end)| {
+            let starts_word = is_word(needle.chars().next());
+            let ends_word = is_word(needle.chars().last());
+            (!starts_word || !is_word(text[..start].chars().last()))
+                && (!ends_word || !is_word(text[end..].chars().next()))
+        })
+        .collect()
+}
+
+/// Check if a Harper finding overlaps the expected approximate_text. When the
+/// expected text repeats, the occurrence nearest the finding is the one compared.
+pub fn overlaps(text: &str, found: &FoundIssue, expected: &ExpectedIssue) -> bool {
+    let distance = |&(start, end): &(usize, usize)| {
+        if found.start < end && found.end > start {
+            0
+        } else if found.end <= start {
+            start - found.end
+        } else {
+            found.start - end
+        }
+    };
+    occurrences(text, &expected.approximate_text)
+        .iter()
+        .min_by_key(|occurrence| distance(occurrence))
+        .is_some_and(|occurrence| distance(occurrence) == 0)
+}
+
+/// Run Harper over every entry and score the findings against the expectations
+pub fn evaluate(corpus: &[CorpusEntry]) -> CorpusMetrics {
+    score(corpus, corpus.iter().map(|entry| run_harper(&entry.text)).collect())
+}
+
+/// Run the full checks over every entry, each with its own settings, and score
+/// the findings against the expectations
+pub fn evaluate_checks(corpus: &[CorpusEntry]) -> Result<CorpusMetrics, String> {
+    let findings = corpus
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let config = entry_config(&entry.settings).map_err(|e| format!("/{}/settings: {}", i, e))?;
+            Ok(run_checks(&entry.text, &config))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(score(corpus, findings))
+}
+
+/// Score `findings[i]` against `corpus[i]` for every entry
+fn score(corpus: &[CorpusEntry], findings: Vec<Vec<FoundIssue>>) -> CorpusMetrics {
+    let mut metrics = CorpusMetrics {
+        total_expected: 0,
+        total_found: 0,
+        true_positives: 0,
+        matched_findings: 0,
+        recall: 0.0,
+        precision: 0.0,
+        by_type: BTreeMap::new(),
+        entries: Vec::new(),
+    };
+
+    for (entry, findings) in corpus.iter().zip(findings) {
+        // Which expected issues were caught, which findings matched one, and the
+        // type each finding was first credited to
+        let mut expected_caught = vec![false; entry.expected_issues.len()];
+        let mut finding_matched = vec![false; findings.len()];
+        let mut finding_type: Vec<Option<&str>> = vec![None; findings.len()];
+
+        for (ei, expected) in entry.expected_issues.iter().enumerate() {
+            for (fi, found) in findings.iter().enumerate() {
+                if overlaps(&entry.text, found, expected) {
+                    expected_caught[ei] = true;
+                    finding_matched[fi] = true;
+                    finding_type[fi].get_or_insert(expected.issue_type.as_str());
+                }
+            }
+        }
+
+        for (ei, expected) in entry.expected_issues.iter().enumerate() {
+            let stats = metrics.by_type.entry(expected.issue_type.clone()).or_default();
+            stats.expected += 1;
+            stats.caught += expected_caught[ei] as usize;
+        }
+        for issue_type in finding_type.into_iter().flatten() {
+            metrics.by_type.entry(issue_type.to_string()).or_default().matched_findings += 1;
+        }
+
+        let caught = expected_caught.iter().filter(|x| **x).count();
+        metrics.total_expected += entry.expected_issues.len();
+        metrics.total_found += findings.len();
+        metrics.true_positives += caught;
+        metrics.matched_findings += finding_matched.iter().filter(|x| **x).count();
+        metrics.entries.push(EntryResult {
+            text: entry.text.clone(),
+            expected: entry.expected_issues.len(),
+            found: findings.len(),
+            caught,
+            missed: entry
+                .expected_issues
+                .iter()
+                .zip(&expected_caught)
+                .filter(|(_, caught)| !**caught)
+                .map(|(expected, _)| expected.clone())
+                .collect(),
+            extra: findings
+                .into_iter()
+                .zip(&finding_matched)
+                .filter(|(_, matched)| !**matched)
+                .map(|(found, _)| found)
+                .collect(),
+        });
+    }
+
+    for stats in metrics.by_type.values_mut() {
+        stats.recall = percent(stats.caught, stats.expected);
+    }
+    metrics.recall = percent(metrics.true_positives, metrics.total_expected);
+    metrics.precision = percent(metrics.matched_findings, metrics.total_found);
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_corpus_is_scored() {
+        let corpus = parse(
+            r#"[
+                { "text": "She dont like it.", "expected_issues": [{ "approximate_text": "dont", "type": "grammar" }] },
+                { "text": "We recieve mail daily.", "expected_issues": [{ "approximate_text": "recieve", "type": "spelling" }] },
+                { "text": "This sentence is fine.", "expected_issues": [{ "approximate_text": "sentence", "type": "style" }] }
+            ]"#,
+        )
+        .unwrap();
+        let metrics = evaluate(&corpus);
+
+        assert_eq!(metrics.total_expected, 3);
+        assert_eq!(metrics.entries.len(), 3);
+        assert_eq!(metrics.by_type["spelling"].caught, 1);
+        assert_eq!(metrics.by_type["style"].caught, 0);
+        assert_eq!(metrics.entries[2].missed.len(), 1);
+        assert_eq!(metrics.recall, percent(metrics.true_positives, 3));
+        assert!(metrics.true_positives >= 1 && metrics.true_positives <= 2);
+
+        assert!(parse(r#"[{ "text": "Hi.", "expected_issues": [], "note": "x" }]"#).unwrap_err().contains("unknown key 'note'"));
+    }
+
+    #[test]
+    fn checks_corpus_applies_entry_settings() {
+        let corpus = parse(
+            r#"[
+                { "text": "We bought apples, pears and plums.", "settings": { "oxford_comma": "require" },
+                  "expected_issues": [{ "approximate_text": "pears and", "type": "style" }] }
+            ]"#,
+        )
+        .unwrap();
+        let metrics = evaluate_checks(&corpus).unwrap();
+        assert_eq!(metrics.entries[0].caught, 1);
+
+        let typo = parse(r#"[{ "text": "Hi.", "settings": { "oxford_coma": "require" }, "expected_issues": [] }]"#).unwrap();
+        assert_eq!(evaluate_checks(&typo).unwrap_err(), "/0/settings: unknown setting 'oxford_coma'");
+    }
+}
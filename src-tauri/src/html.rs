@@ -0,0 +1,144 @@
+//! HTML input for check_grammar: strip tags, decode entities, and map issue
+//! offsets from the extracted text back onto the original markup.
+
+/// Tags whose content is never prose
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style"];
+
+/// Tags that end a line of text, so words either side aren't run together
+const BLOCK_TAGS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "br", "dd", "div", "dl", "dt", "figcaption",
+    "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "nav", "ol",
+    "p", "pre", "section", "table", "td", "th", "tr", "ul",
+];
+
+/// Text pulled out of HTML. `sources[i]` is the char range in the HTML that
+/// produced char `i` of `text` — one char for plain text, the whole reference
+/// for a decoded entity, the tag for a line break standing in for a block tag.
+pub struct Extracted {
+    pub text: String,
+    sources: Vec<(usize, usize)>,
+    html_chars: usize,
+}
+
+impl Extracted {
+    /// HTML char range for a char range of the extracted text
+    pub fn source_range(&self, start: usize, end: usize) -> (usize, usize) {
+        let at = |i: usize| self.sources.get(i).map_or(self.html_chars, |s| s.0);
+        if end <= start {
+            return (at(start), at(start));
+        }
+        let last = self.sources.get(end - 1).map_or(self.html_chars, |s| s.1);
+        (at(start), last)
+    }
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '–',
+        "mdash" => '—',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "hellip" => '…',
+        "copy" => '©',
+        _ => return None,
+    })
+}
+
+/// Lowercase tag name of a tag body like "/p" or "a href=...", without the slash
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Strip tags and comments, drop script/style content, and decode entities.
+/// Unknown entities and a stray "<" with no closing ">" are kept as written.
+pub fn extract(html: &str) -> Extracted {
+    let chars: Vec<char> = html.chars().collect();
+    let lower: Vec<char> = chars.iter().map(char::to_ascii_lowercase).collect();
+    let mut text = String::new();
+    let mut sources = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '<' if chars[i..].starts_with(&['<', '!', '-', '-']) => {
+                let close = (i + 4..chars.len()).find(|&j| chars[j..].starts_with(&['-', '-', '>']));
+                i = close.map_or(chars.len(), |j| j + 3);
+            }
+            '<' if chars[i + 1..].contains(&'>') => {
+                let close = i + 1 + chars[i + 1..].iter().position(|&c| c == '>').unwrap_or(0);
+                let body: String = chars[i + 1..close].iter().collect();
+                let name = tag_name(&body);
+                let mut end = close + 1;
+                if !body.starts_with('/') && SKIPPED_ELEMENTS.contains(&name.as_str()) {
+                    let closing: Vec<char> = format!("</{}", name).chars().collect();
+                    end = (end..chars.len())
+                        .find(|&j| lower[j..].starts_with(&closing))
+                        .and_then(|j| chars[j..].iter().position(|&c| c == '>').map(|p| j + p + 1))
+                        .unwrap_or(chars.len());
+                }
+                if BLOCK_TAGS.contains(&name.as_str()) && !text.ends_with('\n') && !text.is_empty() {
+                    text.push('\n');
+                    sources.push((i, end));
+                }
+                i = end;
+            }
+            '&' => {
+                let semi = chars[i + 1..].iter().take(12).position(|&c| c == ';');
+                let decoded = semi.and_then(|n| decode_entity(&chars[i + 1..i + 1 + n].iter().collect::<String>()));
+                match (semi, decoded) {
+                    (Some(n), Some(c)) => {
+                        text.push(c);
+                        sources.push((i, i + n + 2));
+                        i += n + 2;
+                    }
+                    _ => {
+                        text.push('&');
+                        sources.push((i, i + 1));
+                        i += 1;
+                    }
+                }
+            }
+            c => {
+                text.push(c);
+                sources.push((i, i + 1));
+                i += 1;
+            }
+        }
+    }
+    Extracted { text, sources, html_chars: chars.len() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_stripped_and_entities_decoded() {
+        let html = "<p>Salt &amp; pepper</p><script>var x = 1;</script><div>Next&nbsp;line</div><!-- note -->";
+        let extracted = extract(html);
+        assert_eq!(extracted.text, "Salt & pepper\nNext line\n");
+
+        // "&" maps back to the whole "&amp;" reference
+        assert_eq!(extracted.source_range(5, 6), (8, 13));
+        // "pepper" keeps its own position
+        assert_eq!(extracted.source_range(7, 13), (14, 20));
+    }
+}
@@ -0,0 +1,200 @@
+//! Shared HTTP client with proxy support.
+//!
+//! reqwest only picks up proxy environment variables in some builds, so they are
+//! read explicitly here. Loopback addresses always bypass the proxy — the local
+//! LLM server must never be routed through a corporate proxy.
+
+use crate::config::Config;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Hosts that never go through a proxy
+const LOOPBACK_HOSTS: &str = "localhost,127.0.0.1,::1";
+
+/// Idle connections are kept this long so back-to-back rewrites skip the handshake
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Upper bound for establishing a connection; requests set their own overall timeout
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// TCP keepalive probes on open connections. A non-streaming rewrite on slow
+/// hardware sends nothing for minutes, and proxies between us and a remote server
+/// drop connections that look idle. The OpenAI-compatible API has no
+/// application-level keep-alive to ask for, so this is the only lever.
+const TCP_KEEPALIVE: Duration = Duration::from_secs(30);
+
+/// The shared client and the proxy settings it was built with
+static SHARED_CLIENT: Mutex<Option<(ProxySettings, Arc<reqwest::Client>)>> = Mutex::new(None);
+
+/// Proxy settings resolved from config and environment
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct ProxySettings {
+    pub http: Option<String>,
+    pub https: Option<String>,
+    /// Comma-separated hosts to reach directly, always including loopback
+    pub no_proxy: String,
+}
+
+/// Resolve proxies: config `proxy` overrides HTTP_PROXY / HTTPS_PROXY (either case),
+/// and config `no_proxy` adds to NO_PROXY. `env` is injectable for tests.
+pub(crate) fn proxy_settings(config: &Config, env: impl Fn(&str) -> Option<String>) -> ProxySettings {
+    let var = |name: &str| {
+        env(name)
+            .or_else(|| env(&name.to_lowercase()))
+            .filter(|v| !v.trim().is_empty())
+    };
+    let configured = config.proxy.clone().filter(|p| !p.trim().is_empty());
+
+    let no_proxy = [Some(LOOPBACK_HOSTS.to_string()), var("NO_PROXY"), config.no_proxy.clone()]
+        .into_iter()
+        .flatten()
+        .filter(|v| !v.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    ProxySettings {
+        http: configured.clone().or_else(|| var("HTTP_PROXY")),
+        https: configured.or_else(|| var("HTTPS_PROXY")),
+        no_proxy,
+    }
+}
+
+/// Refuse to contact `host` unless it's loopback or listed in config.allowed_hosts
+/// (case-insensitive). An empty list allows every host. Refusals go to the
+/// audit log with the host only, never a URL that could carry a key.
+pub(crate) fn check_host(host: &str, config: &Config) -> Result<(), String> {
+    let host = host.trim().trim_start_matches('[').trim_end_matches(']');
+    if host_allowed(host, config) {
+        return Ok(());
+    }
+    crate::audit::log_event("host_refused", serde_json::json!({ "host": host }));
+    Err(format!("{} isn't in allowed_hosts, so Ghostpen won't contact it. Add it to allowed_hosts in config.toml to allow it.", host))
+}
+
+/// check_host without the audit entry, for config validation
+pub(crate) fn host_allowed(host: &str, config: &Config) -> bool {
+    let loopback = host.eq_ignore_ascii_case("localhost")
+        || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback());
+    loopback || config.allowed_hosts.is_empty() || config.allowed_hosts.iter().any(|h| h.trim().eq_ignore_ascii_case(host))
+}
+
+/// The process-wide client, built on first use and reused so connections are pooled.
+/// Rebuilt only if the proxy settings change. Per-request timeouts still apply via
+/// `RequestBuilder::timeout`.
+pub(crate) fn shared_client(config: &Config) -> Result<Arc<reqwest::Client>, String> {
+    let settings = proxy_settings(config, |name| std::env::var(name).ok());
+    let mut shared = SHARED_CLIENT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((built_with, client)) = shared.as_ref() {
+        if *built_with == settings {
+            return Ok(Arc::clone(client));
+        }
+    }
+    let client = Arc::new(build_client_with(&settings)?);
+    *shared = Some((settings, Arc::clone(&client)));
+    Ok(client)
+}
+
+pub(crate) fn build_client_with(settings: &ProxySettings) -> Result<reqwest::Client, String> {
+    // Proxies are set explicitly, so don't let reqwest layer its own env lookup on top
+    let mut builder = reqwest::Client::builder()
+        .no_proxy()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .tcp_keepalive(TCP_KEEPALIVE);
+    let no_proxy = reqwest::NoProxy::from_string(&settings.no_proxy);
+
+    if let Some(url) = &settings.http {
+        let proxy = reqwest::Proxy::http(url).map_err(|e| format!("Invalid HTTP proxy '{}': {}", url, e))?;
+        builder = builder.proxy(proxy.no_proxy(no_proxy.clone()));
+    }
+    if let Some(url) = &settings.https {
+        let proxy = reqwest::Proxy::https(url).map_err(|e| format!("Invalid HTTPS proxy '{}': {}", url, e))?;
+        builder = builder.proxy(proxy.no_proxy(no_proxy));
+    }
+
+    builder.build().map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_proxy_overrides_environment() {
+        let config = Config {
+            proxy: Some("http://proxy.corp:8080".to_string()),
+            no_proxy: Some("intranet.corp".to_string()),
+            ..Config::default()
+        };
+        let settings = proxy_settings(&config, |name| match name {
+            "https_proxy" => Some("http://env-proxy:3128".to_string()),
+            "NO_PROXY" => Some("example.org".to_string()),
+            _ => None,
+        });
+        assert_eq!(settings.http.as_deref(), Some("http://proxy.corp:8080"));
+        assert_eq!(settings.https.as_deref(), Some("http://proxy.corp:8080"));
+        assert_eq!(settings.no_proxy, "localhost,127.0.0.1,::1,example.org,intranet.corp");
+
+        let from_env = proxy_settings(&Config::default(), |name| {
+            (name == "HTTPS_PROXY").then(|| "http://env-proxy:3128".to_string())
+        });
+        assert_eq!(from_env.http, None);
+        assert_eq!(from_env.https.as_deref(), Some("http://env-proxy:3128"));
+    }
+
+    #[test]
+    fn only_allowed_hosts_are_contacted() {
+        let config = Config { allowed_hosts: vec!["llm.example.com".to_string()], ..Config::default() };
+        assert!(check_host("LLM.example.com", &config).is_ok());
+        assert!(check_host("127.0.0.1", &config).is_ok());
+        assert!(check_host("[::1]", &config).is_ok());
+
+        let err = check_host("exfil.example.net", &config).unwrap_err();
+        assert!(err.contains("exfil.example.net"));
+        let captured = crate::audit::CAPTURED.lock().unwrap();
+        assert!(captured.iter().any(|(event, details)| event == "host_refused" && details["host"] == "exfil.example.net"));
+        drop(captured);
+
+        // No list, no restriction
+        assert!(check_host("exfil.example.net", &Config::default()).is_ok());
+    }
+
+    #[test]
+    fn shared_client_is_reused() {
+        let config = Config::default();
+        let first = shared_client(&config).unwrap();
+        let second = shared_client(&config).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[tokio::test]
+    async fn configured_proxy_receives_remote_requests() {
+        let proxy = crate::test_support::mock_server(|_| (200, "{}".to_string()));
+        let settings = ProxySettings {
+            http: Some(proxy.url.clone()),
+            https: None,
+            no_proxy: LOOPBACK_HOSTS.to_string(),
+        };
+        let client = build_client_with(&settings).unwrap();
+
+        client.get("http://llm.example.invalid/v1/models").send().await.unwrap();
+        assert_eq!(proxy.hits(), 1);
+        assert_eq!(proxy.requests.lock().unwrap()[0].path, "http://llm.example.invalid/v1/models");
+    }
+
+    #[tokio::test]
+    async fn loopback_bypasses_proxy() {
+        let direct = crate::test_support::mock_server(|_| (200, "{}".to_string()));
+        let proxy = crate::test_support::mock_server(|_| (502, String::new()));
+        let settings = ProxySettings {
+            http: Some(proxy.url.clone()),
+            https: None,
+            no_proxy: LOOPBACK_HOSTS.to_string(),
+        };
+        let client = build_client_with(&settings).unwrap();
+
+        let resp = client.get(format!("{}/v1/models", direct.url)).send().await.unwrap();
+        assert!(resp.status().is_success());
+        assert_eq!((direct.hits(), proxy.hits()), (1, 0));
+    }
+}
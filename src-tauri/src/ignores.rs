@@ -0,0 +1,263 @@
+use crate::GrammarIssue;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A dismissed issue. Matched by what was flagged and why, not by offsets,
+/// so edits elsewhere in the document don't bring it back.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Ignore {
+    pub message: String,
+    pub matched_text: String,
+}
+
+impl Ignore {
+    pub fn new(message: &str, matched_text: &str) -> Self {
+        Self {
+            message: normalize(message),
+            matched_text: normalize(matched_text),
+        }
+    }
+}
+
+/// Ignores keyed by document hash, persisted to ~/.ghostpen/ignores.json
+type IgnoreStore = HashMap<String, Vec<Ignore>>;
+
+/// An issue dismissed on enough documents that it's now ignored in every check
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LearnedIgnore {
+    #[serde(flatten)]
+    pub ignore: Ignore,
+    /// Always "learned", to tell these apart from per-document ignores
+    pub reason: String,
+    /// RFC 3339 time the dismissal threshold was reached
+    pub learned_at: String,
+}
+
+/// One dismissal on the way to a learned ignore
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Dismissal {
+    ignore: Ignore,
+    text_hash: String,
+    at: String,
+}
+
+/// Persisted to ~/.ghostpen/learned_ignores.json
+#[derive(Serialize, Deserialize, Default)]
+struct LearnedStore {
+    #[serde(default)]
+    dismissals: Vec<Dismissal>,
+    #[serde(default)]
+    learned: Vec<LearnedIgnore>,
+}
+
+/// Serializes read-modify-write cycles on ignores.json and learned_ignores.json
+static IGNORES_LOCK: Mutex<()> = Mutex::new(());
+
+/// Lowercase and collapse whitespace so trivial differences still match
+fn normalize(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn ignores_path() -> Option<PathBuf> {
+    Some(crate::paths::data_dir()?.join("ignores.json"))
+}
+
+fn learned_path() -> Option<PathBuf> {
+    Some(crate::paths::data_dir()?.join("learned_ignores.json"))
+}
+
+fn load_store(path: &Path) -> IgnoreStore {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Ignores recorded for a document. A missing or corrupt file yields none.
+pub fn for_document(text_hash: &str) -> Vec<Ignore> {
+    ignores_path()
+        .map(|p| load_store(&p).remove(text_hash).unwrap_or_default())
+        .unwrap_or_default()
+}
+
+fn add_at(path: &Path, text_hash: &str, ignore: Ignore) -> Result<(), String> {
+    let _guard = IGNORES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut store = load_store(path);
+    let entries = store.entry(text_hash.to_string()).or_default();
+    if entries.contains(&ignore) {
+        return Ok(());
+    }
+    entries.push(ignore);
+
+    write_json(path, &store)
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    std::fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", name, e))
+}
+
+/// Remember that an issue was dismissed for a document. Enough dismissals of the
+/// same issue across documents (config.learn_ignore_after) make it a learned ignore.
+pub fn add(text_hash: &str, ignore: Ignore) -> Result<(), String> {
+    let path = ignores_path().ok_or_else(|| "Could not determine data directory".to_string())?;
+    add_at(&path, text_hash, ignore.clone())?;
+    let config = crate::config::load();
+    if let Some(learned) = learned_path() {
+        record_dismissal_at(&learned, text_hash, ignore, config.learn_ignore_after, config.learn_ignore_window_days, chrono::Utc::now())?;
+    }
+    Ok(())
+}
+
+fn load_learned(path: &Path) -> LearnedStore {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Count a dismissal toward a learned ignore. Only dismissals on distinct documents
+/// within the last `window_days` count; a `threshold` of 0 turns learning off.
+/// Returns true when this dismissal made the issue a learned ignore.
+fn record_dismissal_at(
+    path: &Path,
+    text_hash: &str,
+    ignore: Ignore,
+    threshold: usize,
+    window_days: u32,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<bool, String> {
+    if threshold == 0 {
+        return Ok(false);
+    }
+    let _guard = IGNORES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut store = load_learned(path);
+    if store.learned.iter().any(|l| l.ignore == ignore) {
+        return Ok(false);
+    }
+
+    let since = now - chrono::Duration::days(window_days as i64);
+    store.dismissals.retain(|d| {
+        chrono::DateTime::parse_from_rfc3339(&d.at).is_ok_and(|at| at >= since)
+    });
+    store.dismissals.push(Dismissal { ignore: ignore.clone(), text_hash: text_hash.to_string(), at: now.to_rfc3339() });
+
+    let mut documents: Vec<&str> = store
+        .dismissals
+        .iter()
+        .filter(|d| d.ignore == ignore)
+        .map(|d| d.text_hash.as_str())
+        .collect();
+    documents.sort_unstable();
+    documents.dedup();
+    let learned = documents.len() >= threshold;
+    if learned {
+        store.dismissals.retain(|d| d.ignore != ignore);
+        store.learned.push(LearnedIgnore { ignore, reason: "learned".to_string(), learned_at: now.to_rfc3339() });
+    }
+    write_json(path, &store)?;
+    Ok(learned)
+}
+
+/// Issues ignored everywhere because they were dismissed on enough documents
+pub fn learned() -> Vec<LearnedIgnore> {
+    learned_path().map(|p| load_learned(&p).learned).unwrap_or_default()
+}
+
+/// Forget learned ignores and the dismissals counting toward new ones.
+/// Returns how many learned ignores were removed.
+pub fn clear_learned() -> Result<usize, String> {
+    let path = learned_path().ok_or_else(|| "Could not determine data directory".to_string())?;
+    let _guard = IGNORES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let cleared = load_learned(&path).learned.len();
+    write_json(&path, &LearnedStore::default())?;
+    Ok(cleared)
+}
+
+/// Drop issues matching an ignore. `text` is the checked text, for reading each span.
+pub fn filter(text: &str, issues: &mut Vec<GrammarIssue>, ignores: &[Ignore]) {
+    if ignores.is_empty() {
+        return;
+    }
+    issues.retain(|issue| {
+        let start = crate::char_to_byte(text, issue.start);
+        let end = crate::char_to_byte(text, issue.end);
+        let candidate = Ignore::new(&issue.message, text.get(start..end).unwrap_or(""));
+        !ignores.contains(&candidate)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Severity;
+
+    #[test]
+    fn ignores_survive_edits_and_round_trip() {
+        let path = std::env::temp_dir().join(format!("ghostpen-ignores-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        add_at(&path, "doc-1", Ignore::new("Did you mean  `Ghostpen`?", "ghostpen")).unwrap();
+        add_at(&path, "doc-1", Ignore::new("did you mean `ghostpen`?", "Ghostpen")).unwrap();
+        let ignores = load_store(&path).remove("doc-1").unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(ignores.len(), 1);
+
+        // Same finding, shifted by an edit earlier in the text
+        let text = "New intro. I use ghostpen daily.";
+        let mut issues = vec![GrammarIssue {
+            start: 17,
+            end: 25,
+            message: "Did you mean `Ghostpen`?".to_string(),
+            suggestions: vec![],
+            severity: Severity::Error,
+            rule: "Spelling".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
+        }];
+        filter(text, &mut issues, &ignores);
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn repeated_dismissals_become_a_learned_ignore() {
+        let path = std::env::temp_dir().join(format!("ghostpen-learned-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let now = chrono::Utc::now();
+        let ignore = || Ignore::new("Did you mean `Ghostpen`?", "ghostpen");
+
+        // Stale, and a repeat on the same document: neither counts
+        record_dismissal_at(&path, "doc-0", ignore(), 3, 30, now - chrono::Duration::days(60)).unwrap();
+        assert!(!record_dismissal_at(&path, "doc-1", ignore(), 3, 30, now).unwrap());
+        assert!(!record_dismissal_at(&path, "doc-1", ignore(), 3, 30, now).unwrap());
+        assert!(!record_dismissal_at(&path, "doc-2", ignore(), 3, 30, now).unwrap());
+        assert!(record_dismissal_at(&path, "doc-3", ignore(), 3, 30, now).unwrap());
+
+        let store = load_learned(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(store.dismissals.is_empty());
+        assert_eq!(store.learned.len(), 1);
+        assert_eq!(store.learned[0].reason, "learned");
+
+        let text = "A brand new document about ghostpen.";
+        let mut issues = vec![GrammarIssue {
+            start: 27,
+            end: 35,
+            message: "Did you mean `Ghostpen`?".to_string(),
+            suggestions: vec![],
+            severity: Severity::Error,
+            rule: "Spelling".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
+        }];
+        let learned: Vec<Ignore> = store.learned.into_iter().map(|l| l.ignore).collect();
+        filter(text, &mut issues, &learned);
+        assert!(issues.is_empty());
+    }
+}
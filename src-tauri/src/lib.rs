@@ -1,60 +1,394 @@
 use serde::{Deserialize, Serialize};
-use harper_core::linting::{LintGroup, Linter};
+use harper_core::linting::Linter;
 use harper_core::spell::FstDictionary;
 use harper_core::{Document, Dialect};
+use std::collections::HashMap;
 use std::io::Write;
-use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use regex::Regex;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 
 mod audit;
+mod checks;
+mod cleanup;
+mod config;
+pub mod corpus;
+mod html;
+mod http;
+mod ignores;
+mod lint_pool;
 mod llm;
+mod lsp;
+mod modes;
+mod paths;
+mod profiles;
+mod recommend;
+mod repetition;
+mod review;
+mod segment;
+mod session;
+mod stream;
 mod t5;
+mod thesaurus;
+mod typography;
+mod wordlist;
+#[cfg(test)]
+mod test_support;
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct GrammarIssue {
     pub start: usize,
     pub end: usize,
     pub message: String,
     pub suggestions: Vec<String>,
-    pub severity: String,
+    /// Stable bucket the editor colors by
+    pub severity: Severity,
+    /// The check that raised the issue: Harper's LintKind name ("Spelling",
+    /// "WordChoice") or one of ours ("Consistency", "Inclusivity")
+    #[serde(default)]
+    pub rule: String,
+    /// The one replacement a "fix" button should apply: the top suggestion, or
+    /// None when there is none or it would leave the text unchanged
+    #[serde(default)]
+    pub best_fix: Option<String>,
+    /// The flagged text itself, so the UI needn't slice by offsets.
+    /// Empty if the offsets don't fit the checked text.
+    #[serde(default)]
+    pub matched_text: String,
 }
 
-#[derive(Serialize)]
+/// Three-level severity for UI colors, independent of upstream lint kind names
+/// Ordered most to least important.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    #[default]
+    Warning,
+    Suggestion,
+}
+
+impl Severity {
+    /// Bucket for a rule name. Kinds not listed here — including any Harper adds
+    /// or renames later — are warnings.
+    pub fn for_rule(rule: &str) -> Self {
+        match rule {
+            "Spelling" | "Typo" => Severity::Error,
+            "Style" | "Readability" | "Enhancement" | "Consistency" | "Inclusivity" => Severity::Suggestion,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+/// Everything check_grammar accepts. Only `text` is required; new options go here
+/// with a serde default so existing callers keep working.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct CheckRequest {
+    pub text: String,
+    /// "strict" or "relaxed"
+    pub profile: String,
+    /// Cap on returned issues; stats still count all of them
+    pub max_issues: Option<usize>,
+    /// Document hash whose ignored issues are filtered out
+    pub text_hash: Option<String>,
+    /// Drop issues inside code blocks and inline code
+    pub skip_code: bool,
+    /// Writing session (from start_session) the check is logged under
+    pub session_id: Option<String>,
+    /// Identifies one editor's stream of checks, e.g. per open document. Rapid
+    /// calls with the same key are debounced, see check_grammar.
+    pub debounce_key: Option<String>,
+    /// Overrides the configured dialect for this check
+    pub dialect: Option<String>,
+    /// "plain" (default) or "html". HTML is checked as its visible text and
+    /// offsets point into the markup; unknown formats are checked as plain.
+    pub format: String,
+}
+
+impl Default for CheckRequest {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            profile: "strict".to_string(),
+            max_issues: None,
+            text_hash: None,
+            skip_code: false,
+            session_id: None,
+            debounce_key: None,
+            dialect: None,
+            format: "plain".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
 pub struct CheckResult {
+    /// Sorted by `start`, then `end`; equal spans keep linter order
     pub issues: Vec<GrammarIssue>,
     pub stats: TextStats,
+    pub sentence_issues: Vec<SentenceSummary>,
+    /// True when a newer check_grammar call for the same session replaced this
+    /// one; everything else is empty and the UI should wait for the newer result
+    pub superseded: bool,
 }
 
+/// Result of check_and_rewrite. `rewrite` is None when the LLM call failed;
+/// `rewrite_error` says why.
 #[derive(Serialize)]
+pub struct CombinedResult {
+    pub check: CheckResult,
+    pub rewrite: Option<RewriteResult>,
+    pub rewrite_error: Option<String>,
+}
+
+/// Issue density for one sentence, for heatmap-style highlighting
+#[derive(Serialize, Debug, PartialEq)]
+pub struct SentenceSummary {
+    /// Char offset of the sentence start, like GrammarIssue
+    pub start: usize,
+    /// Char offset just past the sentence terminator
+    pub end: usize,
+    pub issue_count: usize,
+}
+
+/// One sentence's worth of issues from check_grammar_stream
+#[derive(Serialize, Clone)]
+pub struct GrammarBatch {
+    pub generation: u64,
+    pub sentence_index: usize,
+    pub sentence_count: usize,
+    pub issues: Vec<GrammarIssue>,
+}
+
+#[derive(Serialize, Default, Debug, PartialEq)]
 pub struct TextStats {
     pub word_count: usize,
     pub sentence_count: usize,
+    /// Issues returned in `issues` (after any max_issues cap)
     pub issue_count: usize,
+    /// Issues found before the cap, for "showing 200 of 1500"
+    pub total_issues: usize,
+    /// True when max_issues cut the list short
+    pub truncated_issues: bool,
+    /// Estimated silent reading time at the configured words-per-minute
+    pub reading_time_secs: usize,
+    /// Estimated time to read aloud, for presenters
+    pub speaking_time_secs: usize,
 }
 
 #[derive(Deserialize)]
 pub struct RewriteRequest {
     pub text: String,
     pub mode: String,
+    /// "plain" (default) or "markdown_diff" to also return tracked changes in `marked`
+    #[serde(default = "default_output_format")]
+    pub output_format: String,
+    /// Char range of the editor selection being rewritten, echoed back unchanged
+    #[serde(default)]
+    pub replace_start: Option<usize>,
+    #[serde(default)]
+    pub replace_end: Option<usize>,
+    /// Words or phrases the rewrite must keep verbatim (e.g. SEO keywords)
+    #[serde(default)]
+    pub must_keep: Vec<String>,
+    /// Upper bound on the rewrite's length, in characters
+    #[serde(default)]
+    pub max_chars: Option<usize>,
+    /// Rewrite each blank-line-separated paragraph on its own so the model can't
+    /// merge them. Ignored for streamed rewrites.
+    #[serde(default)]
+    pub split_paragraphs: bool,
+    /// Ask for a numbered explanation and link each point to the change it describes
+    #[serde(default)]
+    pub annotate: bool,
+    /// Also return the model's reply exactly as received, for debugging the parser
+    #[serde(default)]
+    pub include_raw: bool,
+    /// Sampling seed for reproducible output (with temperature 0). Passed to the
+    /// server as `seed`; Ollama and many OpenAI-compatible servers honor it, some ignore it.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Writing session (from start_session) the rewrite is logged under
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
-#[derive(Serialize)]
+fn default_output_format() -> String {
+    "plain".to_string()
+}
+
+impl Default for RewriteRequest {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            mode: String::new(),
+            output_format: default_output_format(),
+            replace_start: None,
+            replace_end: None,
+            must_keep: Vec::new(),
+            max_chars: None,
+            split_paragraphs: false,
+            annotate: false,
+            include_raw: false,
+            seed: None,
+            session_id: None,
+        }
+    }
+}
+
+/// How a rewrite measured up against the request's must_keep / max_chars
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ConstraintReport {
+    pub satisfied: bool,
+    /// must_keep entries absent from the rewrite
+    pub missing: Vec<String>,
+    /// Rewrite length in characters
+    pub chars: usize,
+    pub max_chars: Option<usize>,
+    /// True when the first attempt broke a constraint and the model was asked again
+    pub retried: bool,
+}
+
+#[derive(Serialize, Clone)]
 pub struct RewriteResult {
     pub rewritten: String,
     pub explanation: String,
+    /// `explanation` split into points for list rendering; empty with no explanation
+    pub explanation_points: Vec<String>,
+    /// Rewrite rendered as tracked changes (~~deleted~~ **inserted**), only for "markdown_diff"
+    pub marked: Option<String>,
+    /// True when served from the in-memory rewrite cache without querying the model
+    pub cached: bool,
+    /// Echo of the request's replace span so the editor can apply the result in place
+    pub replace_start: Option<usize>,
+    pub replace_end: Option<usize>,
+    /// Present when the request set must_keep or max_chars
+    pub constraints: Option<ConstraintReport>,
+    /// Explanation points tied to the change they describe; empty unless requested
+    pub annotations: Vec<Annotation>,
+    /// The model's reply before it was split into rewrite and explanation; only
+    /// with include_raw. Never written to the audit log.
+    pub raw: Option<String>,
+    /// Model that produced the rewrite, which may be a fallback from config.models
+    pub model: String,
+    /// Share of content words the rewrite has in common with the original (Jaccard,
+    /// 0–1). Low means the model rewrote more than it was asked to.
+    pub fidelity: f32,
+    /// fidelity fell below config.min_fidelity
+    pub low_fidelity: bool,
+}
+
+/// One numbered explanation point, located in the rewritten text for hover-to-explain
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Annotation {
+    /// Char range of the change in `rewritten`; empty (start == end) for a pure deletion
+    pub start: usize,
+    pub end: usize,
+    /// The point's number as the model wrote it
+    pub point: usize,
+    pub note: String,
+}
+
+#[derive(Serialize)]
+pub struct PromptPreview {
+    pub system_prompt: String,
+    pub user_prompt: String,
+    /// Example user/assistant pairs sent ahead of the request for this mode
+    pub few_shot_examples: usize,
+    pub estimated_tokens: usize,
+    /// Limit check_input_budget will apply, for `model` when one is known
+    pub max_input_tokens: usize,
+    /// Model the rewrite would start with, None when it can't be resolved offline
+    pub model: Option<String>,
 }
 
+/// Result of context_check: whether a rewrite of the text fits the model's window
 #[derive(Serialize)]
+pub struct ContextInfo {
+    /// Estimated tokens in the text alone
+    pub estimated_tokens: usize,
+    /// Text plus prompt scaffolding plus room for a same-length reply
+    pub required_tokens: usize,
+    pub context_window: usize,
+    /// False when the model wasn't in the bundled table and a default window was assumed
+    pub known_model: bool,
+    pub fits: bool,
+}
+
+#[derive(Serialize, Clone)]
 pub struct LlmStatus {
     pub available: bool,
     pub provider: String,
     pub model: String,
+    /// Address that answered the probe (e.g. "127.0.0.1" or "::1"), empty when unavailable
+    pub address: String,
+}
+
+/// Result of benchmark_llm: generation speed of the detected local model
+#[derive(Serialize)]
+pub struct BenchmarkResult {
+    pub provider: String,
+    pub model: String,
+    pub tokens_generated: usize,
+    /// True when the server didn't report usage and tokens were estimated from words
+    pub tokens_estimated: bool,
+    pub total_ms: u64,
+    /// Milliseconds until the first streamed token, if any arrived
+    pub time_to_first_token_ms: Option<u64>,
+    pub tokens_per_sec: f64,
+}
+
+/// Result of estimate_cost: expected tokens and price of a rewrite before it's sent
+#[derive(Serialize, Debug, PartialEq)]
+pub struct CostEstimate {
+    pub model: String,
+    pub input_tokens: usize,
+    /// input_tokens × config.completion_ratio
+    pub output_tokens: usize,
+    /// US dollars, from config.model_prices; None for models without a price (local ones)
+    pub cost_usd: Option<f64>,
+}
+
+/// One probed endpoint in diagnose_llm's report
+#[derive(Serialize, Debug)]
+pub struct EndpointDiagnostic {
+    /// "LM Studio" or "Ollama"
+    pub provider: String,
+    pub url: String,
+    /// True when the server answered at all, whatever the status
+    pub reachable: bool,
+    /// HTTP status of the probe, when one came back
+    pub status: Option<u16>,
+    /// Connection or protocol error, when the probe failed outright
+    pub error: Option<String>,
+    pub latency_ms: Option<u64>,
+    /// Models the server lists; empty when none are loaded or it doesn't say
+    pub models: Vec<String>,
+    /// One line for the user, e.g. "LM Studio reachable but no model loaded"
+    pub summary: String,
+}
+
+/// Result of diagnose_llm: every configured endpoint, probed in detection order
+#[derive(Serialize, Debug)]
+pub struct LlmDiagnostics {
+    /// True when offline mode stopped anything from being probed
+    pub offline: bool,
+    pub endpoints: Vec<EndpointDiagnostic>,
+}
+
+/// A remembered LlmStatus and how old it is, for UIs that poll
+#[derive(Serialize)]
+pub struct CachedStatus {
+    #[serde(flatten)]
+    pub status: LlmStatus,
+    pub status_age_secs: u64,
 }
 
-fn double_space_re() -> &'static Regex {
+fn whitespace_run_re() -> &'static Regex {
     static RE: OnceLock<Regex> = OnceLock::new();
-    RE.get_or_init(|| Regex::new(r"  +").unwrap())
+    RE.get_or_init(|| Regex::new(r"[ \t]+").unwrap())
 }
 
 fn repeated_punct_re() -> &'static Regex {
@@ -68,23 +402,48 @@ fn sentence_line_re() -> &'static Regex {
     RE.get_or_init(|| Regex::new(r#"(?m)^[A-Z][^\n]{10,}[a-z0-9)\]"'\-*`]$"#).unwrap())
 }
 
-/// Check for punctuation issues that Harper doesn't catch
-fn check_punctuation(text: &str) -> Vec<GrammarIssue> {
+/// Check for whitespace Harper ignores: runs of spaces, tabs mixed with spaces,
+/// and trailing spaces at line ends. Leading indentation is left alone.
+fn check_whitespace(text: &str) -> Vec<GrammarIssue> {
     let mut issues = Vec::new();
 
-    // Double spaces
-    for m in double_space_re().find_iter(text) {
-        let start_char = text[..m.start()].chars().count();
-        let end_char = text[..m.end()].chars().count();
+    for m in whitespace_run_re().find_iter(text) {
+        let run = m.as_str();
+        let at_line_start = m.start() == 0 || text[..m.start()].ends_with('\n');
+        let rest = &text[m.end()..];
+        let at_line_end = rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n");
+
+        let (message, suggestion) = if at_line_end {
+            ("Trailing whitespace at end of line.", "")
+        } else if at_line_start {
+            continue; // indentation
+        } else if run.contains('\t') && run.contains(' ') {
+            ("Tabs mixed with spaces. Use a single space.", " ")
+        } else if run.len() >= 2 && !run.contains('\t') {
+            ("Multiple spaces found. Use a single space.", " ")
+        } else {
+            continue;
+        };
+
         issues.push(GrammarIssue {
-            start: start_char,
-            end: end_char,
-            message: "Multiple spaces found. Use a single space.".to_string(),
-            suggestions: vec![" ".to_string()],
-            severity: "Style".to_string(),
+            start: text[..m.start()].chars().count(),
+            end: text[..m.end()].chars().count(),
+            message: message.to_string(),
+            suggestions: vec![suggestion.to_string()],
+            severity: Severity::Suggestion,
+            rule: "Style".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
         });
     }
 
+    issues
+}
+
+/// Check for punctuation issues that Harper doesn't catch
+fn check_punctuation(text: &str) -> Vec<GrammarIssue> {
+    let mut issues = check_whitespace(text);
+
     // Repeated punctuation (!!, ??, ..)
     for m in repeated_punct_re().find_iter(text) {
         let matched = m.as_str();
@@ -100,7 +459,10 @@ fn check_punctuation(text: &str) -> Vec<GrammarIssue> {
             end: end_char,
             message: format!("Repeated punctuation '{}'. Use a single character.", matched),
             suggestions: vec![single],
-            severity: "Style".to_string(),
+            severity: Severity::Suggestion,
+            rule: "Style".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
         });
     }
 
@@ -120,7 +482,10 @@ fn check_punctuation(text: &str) -> Vec<GrammarIssue> {
                 suggestions: vec![
                     format!("{}.", &line[line.len()-1..]),
                 ],
-                severity: "Style".to_string(),
+                severity: Severity::Suggestion,
+                rule: "Style".to_string(),
+                best_fix: None,
+                matched_text: String::new(),
             });
         }
     }
@@ -130,31 +495,150 @@ fn check_punctuation(text: &str) -> Vec<GrammarIssue> {
 
 /// Read custom dictionary words from ~/.ghostpen/dictionary.txt
 fn load_dictionary() -> Vec<String> {
-    let Some(home) = dirs::home_dir() else { return vec![] };
-    let dict_path = home.join(".ghostpen").join("dictionary.txt");
-    match std::fs::read_to_string(&dict_path) {
-        Ok(content) => content
-            .lines()
-            .map(|l| l.trim().to_lowercase())
-            .filter(|l| !l.is_empty())
-            .collect(),
-        Err(_) => vec![],
+    let Some(data_dir) = paths::data_dir() else { return vec![] };
+    load_dictionary_from(&data_dir.join("dictionary.txt"))
+}
+
+/// A dictionary file that exists but can't be used (bad encoding, unreadable)
+/// must not take grammar checking down with it: checks fall back to the curated
+/// dictionary alone and the failure goes to the audit log.
+fn load_dictionary_from(path: &std::path::Path) -> Vec<String> {
+    match read_dictionary(path) {
+        Ok(words) => words,
+        Err(e) => {
+            audit::log_event("dictionary_load_error", serde_json::json!({ "error": e }));
+            vec![]
+        }
+    }
+}
+
+fn read_dictionary(path: &std::path::Path) -> Result<Vec<String>, String> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let content = String::from_utf8(bytes)
+        .map_err(|e| format!("{} is not valid UTF-8: {}", path.display(), e))?;
+    Ok(content
+        .lines()
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty())
+        .collect())
+}
+
+/// Dismiss an issue for one document. `text_hash` identifies the document;
+/// `span` is the issue's [start, end] char range in `text`. Later checks with the
+/// same text_hash drop issues with the same message and flagged text.
+#[tauri::command]
+fn ignore_issue(text_hash: String, span: [usize; 2], message: String, text: String) -> Result<(), String> {
+    let start = char_to_byte(&text, span[0]);
+    let end = char_to_byte(&text, span[1]);
+    let matched = text.get(start..end).ok_or_else(|| "Span is outside the text".to_string())?;
+    ignores::add(&text_hash, ignores::Ignore::new(&message, matched))?;
+
+    audit::log_event("ignore_issue", serde_json::json!({
+        "span_length": span[1].saturating_sub(span[0]),
+    }));
+    Ok(())
+}
+
+/// Rule names accepted by the disabled_rules setting
+#[tauri::command]
+fn list_available_rules() -> Vec<&'static str> {
+    AVAILABLE_RULES.to_vec()
+}
+
+/// Issues ignored everywhere after repeated dismissals
+#[tauri::command]
+fn learned_ignores() -> Vec<ignores::LearnedIgnore> {
+    ignores::learned()
+}
+
+/// Forget every learned ignore; returns how many were removed
+#[tauri::command]
+fn clear_learned_ignores() -> Result<usize, String> {
+    ignores::clear_learned()
+}
+
+/// Report which spell-check dictionary is active and how many words it knows
+#[tauri::command]
+fn dictionary_info() -> wordlist::DictionaryInfo {
+    let config = config::load();
+    wordlist::info(&config.dictionary, &config.dialect)
+}
+
+/// Content words used more than `repetition_threshold` times, most frequent first,
+/// with the byte range of every occurrence
+#[tauri::command]
+fn repetition_report(text: String) -> Vec<repetition::WordCount> {
+    let config = config::load();
+    let stopwords: Option<Vec<String>> = config
+        .repetition_stopwords
+        .map(|words| words.iter().map(|w| w.trim().to_lowercase()).collect());
+    repetition::report(&text, config.repetition_threshold, stopwords.as_deref())
+}
+
+/// Convert quotes, dashes, and ellipses: `to` is "ascii" (straight quotes, "--",
+/// "...") or "smart" (curly quotes, em dash, …). Other characters are untouched.
+#[tauri::command]
+fn normalize_typography(text: String, to: String) -> Result<String, String> {
+    match to.as_str() {
+        "ascii" => Ok(typography::to_ascii(&text)),
+        "smart" => Ok(typography::to_smart(&text)),
+        other => Err(format!("Unknown typography target '{}'. Use ascii or smart.", other)),
     }
 }
 
+/// Look up synonyms for a word in the bundled offline thesaurus
+#[tauri::command]
+fn synonyms(word: String) -> Vec<String> {
+    thesaurus::lookup(&word)
+}
+
+/// Issues that adding `words` to the user dictionary would clear
+#[derive(Serialize)]
+pub struct DictPreview {
+    pub resolved: Vec<GrammarIssue>,
+    /// Issues left after adding the words
+    pub remaining: usize,
+}
+
+/// Lint with and without `words` in the dictionary and report what they'd clear.
+/// Nothing is written to dictionary.txt.
+#[tauri::command]
+fn preview_dictionary(text: String, words: Vec<String>) -> DictPreview {
+    preview_dictionary_with(&text, &words, &config::load())
+}
+
+fn preview_dictionary_with(text: &str, words: &[String], config: &config::Config) -> DictPreview {
+    let before = collect_issues_merged(text, config, &[]);
+    let after = collect_issues_merged(text, config, words);
+    let resolved = before
+        .into_iter()
+        .filter(|issue| {
+            !after.iter().any(|kept| (kept.start, kept.end, &kept.message) == (issue.start, issue.end, &issue.message))
+        })
+        .collect();
+    DictPreview { resolved, remaining: after.len() }
+}
+
 /// Add a word to the custom dictionary
 #[tauri::command]
 fn add_to_dictionary(word: String) -> Result<String, String> {
+    let ghostpen_dir = paths::data_dir()
+        .ok_or_else(|| "Could not determine data directory".to_string())?;
+    add_to_dictionary_in(&ghostpen_dir, &word)
+}
+
+/// add_to_dictionary for the dictionary.txt in `ghostpen_dir`
+fn add_to_dictionary_in(ghostpen_dir: &std::path::Path, word: &str) -> Result<String, String> {
     let trimmed = word.trim();
     if trimmed.is_empty() {
         return Ok("ignored".to_string());
     }
 
-    let ghostpen_dir = dirs::home_dir()
-        .ok_or_else(|| "Could not determine home directory".to_string())?
-        .join(".ghostpen");
-
-    std::fs::create_dir_all(&ghostpen_dir)
+    std::fs::create_dir_all(ghostpen_dir)
         .map_err(|e| format!("Failed to create .ghostpen directory: {}", e))?;
 
     let dict_path = ghostpen_dir.join("dictionary.txt");
@@ -178,31 +662,213 @@ fn add_to_dictionary(word: String) -> Result<String, String> {
     Ok("ok".to_string())
 }
 
-/// Check text for grammar issues using Harper (instant, local, no network)
+/// Convert a char offset (as used by GrammarIssue) to a byte offset into `text`
+pub(crate) fn char_to_byte(text: &str, char_idx: usize) -> usize {
+    text.char_indices()
+        .nth(char_idx)
+        .map(|(b, _)| b)
+        .unwrap_or(text.len())
+}
+
+/// Text between two char offsets, or "" when they're reversed or past the end
+fn matched_text(text: &str, start: usize, end: usize) -> String {
+    if start > end || end > text.chars().count() {
+        return String::new();
+    }
+    text[char_to_byte(text, start)..char_to_byte(text, end)].to_string()
+}
+
+/// Map a config dialect name to Harper's Dialect
+pub(crate) fn parse_dialect(name: &str) -> Option<Dialect> {
+    match name.trim().to_lowercase().as_str() {
+        "american" | "us" | "en-us" => Some(Dialect::American),
+        "british" | "uk" | "en-gb" => Some(Dialect::British),
+        "australian" | "au" | "en-au" => Some(Dialect::Australian),
+        "canadian" | "ca" | "en-ca" => Some(Dialect::Canadian),
+        _ => None,
+    }
+}
+
+/// Run Harper plus the supplemental checks and return every issue found,
+/// ordered left-to-right by position
+fn collect_issues(text: &str) -> Vec<GrammarIssue> {
+    collect_issues_with(text, &config::load())
+}
+
+/// collect_issues with explicit settings. Reads dialect and dictionary from `config`
+/// on every call, so changing either takes effect on the next check.
+fn collect_issues_with(text: &str, config: &config::Config) -> Vec<GrammarIssue> {
+    collect_issues_merged(text, config, &[])
+}
+
+/// collect_issues_with, treating `extra_words` as if they were in the user dictionary
+fn collect_issues_merged(text: &str, config: &config::Config, extra_words: &[String]) -> Vec<GrammarIssue> {
+    collect_issues_until(text, config, extra_words, &AtomicBool::new(false)).unwrap_or_default()
+}
+
+/// collect_issues_merged that gives up once `cancel` is set, checked before
+/// each chunk and before the supplemental checks. None when cancelled.
+fn collect_issues_until(
+    text: &str,
+    config: &config::Config,
+    extra_words: &[String],
+    cancel: &AtomicBool,
+) -> Option<Vec<GrammarIssue>> {
+    let dialect = parse_dialect(&config.dialect).unwrap_or(Dialect::American);
+
+    // Big documents are linted a few paragraphs at a time to bound memory,
+    // yielding between chunks so one huge check doesn't hog the thread
+    let mut issues = Vec::new();
+    let chunks = segment::chunk_spans(text, config.chunk_size);
+    for (i, chunk) in chunks.iter().enumerate() {
+        if i > 0 {
+            std::thread::yield_now();
+        }
+        if cancel.load(Ordering::SeqCst) {
+            return None;
+        }
+        let offset = text[..chunk.start].chars().count();
+        issues.extend(harper_issues(&text[chunk.clone()], dialect).into_iter().map(|mut issue| {
+            issue.start += offset;
+            issue.end += offset;
+            issue
+        }));
+    }
+    if cancel.load(Ordering::SeqCst) {
+        return None;
+    }
+
+    // Merge punctuation issues that Harper doesn't catch
+    let mut punctuation_issues = check_punctuation(text);
+    issues.append(&mut punctuation_issues);
+
+    merge_supplemental(&mut issues, checks::check_sentence_start(text));
+    merge_supplemental(&mut issues, checks::check_proper_nouns(text));
+    merge_supplemental(&mut issues, checks::check_wordy_phrases(text));
+    merge_supplemental(&mut issues, checks::check_compounds(text));
+    merge_supplemental(&mut issues, checks::check_homophones(text));
+    // Never steer toward a spelling the dialect itself rejects ("colour" in an American document)
+    let mut consistency = checks::check_spelling_consistency(text);
+    consistency.retain(|issue| !issue.suggestions.iter().any(|s| misspelled_in(s, dialect)));
+    merge_supplemental(&mut issues, consistency);
+    merge_supplemental(&mut issues, checks::check_serial_comma(text, &config.oxford_comma));
+    if config.inclusive_language {
+        merge_supplemental(&mut issues, checks::check_inclusive_language(text));
+    }
+
+    if !config.disabled_rules.is_empty() {
+        issues.retain(|issue| !config.disabled_rules.iter().any(|r| r.eq_ignore_ascii_case(&issue.rule)));
+    }
+
+    drop_accepted_words(text, &mut issues, config, extra_words);
+
+    // Harper only knows English. Findings touching Hebrew or Arabic script are
+    // noise (every word "misspelled"), and an inverted span is never usable.
+    issues.retain(|issue| {
+        issue.start <= issue.end && !matched_text(text, issue.start, issue.end).chars().any(segment::is_rtl)
+    });
+
+    // Harper and the supplemental checks each emit in their own order — merge by position.
+    // sort_by is stable, so issues on the same span keep their relative order.
+    issues.sort_by(|a, b| a.start.cmp(&b.start).then(a.end.cmp(&b.end)));
+
+    for issue in &mut issues {
+        issue.best_fix = best_fix(text, issue);
+        issue.matched_text = matched_text(text, issue.start, issue.end);
+    }
+
+    Some(issues)
+}
+
+/// Drop findings on words the user accepts: the custom dictionary plus
+/// `extra_words`, the full word list when configured, and spelling flags on
+/// words shorter than min_spell_length
+fn drop_accepted_words(text: &str, issues: &mut Vec<GrammarIssue>, config: &config::Config, extra_words: &[String]) {
+    // Filter out issues for words in the custom dictionary
+    let mut dictionary = load_dictionary();
+    dictionary.extend(extra_words.iter().map(|w| w.trim().to_lowercase()).filter(|w| !w.is_empty()));
+    if !dictionary.is_empty() {
+        issues.retain(|issue| {
+            // Convert char offsets back to byte offsets for slicing
+            let byte_start = char_to_byte(text, issue.start);
+            let byte_end = char_to_byte(text, issue.end);
+            let word = text.get(byte_start..byte_end)
+                .unwrap_or("").trim().to_lowercase();
+            !dictionary.contains(&word)
+        });
+    }
+
+    // The full dictionary accepts rarer words the curated list flags as misspelled
+    if config.dictionary != "curated" {
+        issues.retain(|issue| {
+            if issue.rule != "Spelling" {
+                return true;
+            }
+            let byte_start = char_to_byte(text, issue.start);
+            let byte_end = char_to_byte(text, issue.end);
+            !wordlist::accepts(&config.dictionary, text.get(byte_start..byte_end).unwrap_or(""))
+        });
+    }
+
+    issues.retain(|issue| {
+        issue.rule != "Spelling" || matched_text(text, issue.start, issue.end).chars().count() >= config.min_spell_length
+    });
+}
+
+/// Spelling only, for autocorrect while typing: Harper's spell checker alone,
+/// none of the grammar, style, or supplemental rules. Each issue carries just
+/// its best suggestion. Dictionary settings apply as in a full check.
 #[tauri::command]
-fn check_grammar(text: &str) -> CheckResult {
-    let start_time = std::time::Instant::now();
+fn quick_spellcheck(text: String) -> Vec<GrammarIssue> {
+    quick_spellcheck_with(&text, &config::load())
+}
+
+fn quick_spellcheck_with(text: &str, config: &config::Config) -> Vec<GrammarIssue> {
+    let dialect = parse_dialect(&config.dialect).unwrap_or(Dialect::American);
+    let dict = FstDictionary::curated();
+    let document = Document::new_plain_english(text, &dict);
+    let lints = lint_pool::with_spell_check(dialect, |spell| spell.lint(&document));
+    let mut issues = lint_issues(text, &lints);
+
+    drop_accepted_words(text, &mut issues, config, &[]);
+    issues.retain(|issue| !matched_text(text, issue.start, issue.end).chars().any(segment::is_rtl));
+    for issue in &mut issues {
+        issue.best_fix = best_fix(text, issue);
+        issue.suggestions = issue.best_fix.iter().cloned().collect();
+        issue.matched_text = matched_text(text, issue.start, issue.end);
+    }
+    issues
+}
+
+/// Whether the spell checker for `dialect` flags `word`
+fn misspelled_in(word: &str, dialect: Dialect) -> bool {
+    let dict = FstDictionary::curated();
+    let document = Document::new_plain_english(word, &dict);
+    lint_pool::with_spell_check(dialect, |spell| !spell.lint(&document).is_empty())
+}
 
+/// Harper's lints for `text` as GrammarIssues, with char offsets into `text`
+fn harper_issues(text: &str, dialect: Dialect) -> Vec<GrammarIssue> {
     let dict = FstDictionary::curated();
     let document = Document::new_plain_english(text, &dict);
-    let mut linter = LintGroup::new_curated(Arc::clone(&dict), Dialect::American);
-    let lints = linter.lint(&document);
+    let lints = lint_pool::with_group(dialect, |linter| linter.lint(&document));
+    lint_issues(text, &lints)
+}
+
+/// Harper lints as GrammarIssues, with char offsets into `text`
+fn lint_issues(text: &str, lints: &[harper_core::linting::Lint]) -> Vec<GrammarIssue> {
+    let chars: Vec<char> = text.chars().collect();
 
-    let mut issues: Vec<GrammarIssue> = lints
+    lints
         .iter()
         .map(|lint| {
-            let start = lint.span.start;
-            let end = lint.span.end;
-
-            // Harper returns byte offsets, but JS/CM6 expects character indices.
-            // For multi-byte chars (emoji, accented letters, smart quotes), these differ.
-            let start_char = text[..start.min(text.len())].chars().count();
-            let end_char = text[..end.min(text.len())].chars().count();
+            // Harper spans index the document's chars, which is also what JS/CM6
+            // expects. Clamped so a span can never reach past the text.
+            let end_char = lint.span.end.min(chars.len());
+            let start_char = lint.span.start.min(end_char);
 
             // Pre-expand suggestions so the frontend can treat all as simple replacements
-            // Note: Rust string slicing uses byte offsets, so keep start/end for slicing.
-            // Use .get() to avoid panicking if offsets land mid-character.
-            let original_span = text.get(start..end).unwrap_or("");
+            let original_span: String = chars[start_char..end_char].iter().collect();
             let suggestions: Vec<String> = lint
                 .suggestions
                 .iter()
@@ -219,93 +885,734 @@ fn check_grammar(text: &str) -> CheckResult {
                 })
                 .collect();
 
+            // LintKind's Debug name is the rule; the UI only ever sees the mapped severity
+            let rule = format!("{:?}", lint.lint_kind);
             GrammarIssue {
                 start: start_char,
                 end: end_char,
                 message: lint.message.clone(),
                 suggestions,
-                severity: format!("{:?}", lint.lint_kind),
+                severity: Severity::for_rule(&rule),
+                rule,
+                best_fix: None,
+                matched_text: String::new(),
             }
         })
-        .collect();
+        .collect()
+}
 
-    // Merge punctuation issues that Harper doesn't catch
-    let mut punctuation_issues = check_punctuation(text);
-    issues.append(&mut punctuation_issues);
+/// First suggestion that actually changes the flagged span. Suggestions arrive
+/// ranked, so that is the top one unless it merely echoes the original
+/// (homophone checks list the original second as an opt-out).
+fn best_fix(text: &str, issue: &GrammarIssue) -> Option<String> {
+    let original = text.get(char_to_byte(text, issue.start)..char_to_byte(text, issue.end))?;
+    issue.suggestions.first().filter(|s| s.as_str() != original).cloned()
+}
 
-    // Filter out issues for words in the custom dictionary
-    let dictionary = load_dictionary();
-    if !dictionary.is_empty() {
-        issues.retain(|issue| {
-            // Convert char offsets back to byte offsets for slicing
-            let byte_start: usize = text.chars().take(issue.start).map(|c| c.len_utf8()).sum();
-            let byte_end: usize = text.chars().take(issue.end).map(|c| c.len_utf8()).sum();
-            let word = text.get(byte_start..byte_end)
-                .unwrap_or("").trim().to_lowercase();
-            !dictionary.contains(&word)
+/// Add supplemental issues, skipping any that overlap an existing issue of the
+/// same kind — Harper sometimes catches the same problem with a wider span.
+fn merge_supplemental(issues: &mut Vec<GrammarIssue>, extra: Vec<GrammarIssue>) {
+    for issue in extra {
+        let duplicate = issues.iter().any(|existing| {
+            existing.rule == issue.rule
+                && existing.start < issue.end.max(issue.start + 1)
+                && issue.start < existing.end.max(existing.start + 1)
         });
+        if !duplicate {
+            issues.push(issue);
+        }
     }
+}
 
-    let word_count = text.split_whitespace().count();
-    let sentence_count = text.chars()
-        .filter(|c| *c == '.' || *c == '!' || *c == '?')
-        .count()
-        .max(1);
-
-    let duration_ms = start_time.elapsed().as_millis();
-    let issue_count = issues.len();
+/// Bucket issues into sentences. An issue spanning a boundary counts toward the
+/// sentence containing its start; issues in inter-sentence whitespace go to the
+/// preceding sentence.
+fn summarize_sentences(text: &str, issues: &[GrammarIssue]) -> Vec<SentenceSummary> {
+    let mut summaries: Vec<SentenceSummary> = segment::sentence_spans(text)
+        .into_iter()
+        .map(|span| SentenceSummary {
+            start: text[..span.start].chars().count(),
+            end: text[..span.end].chars().count(),
+            issue_count: 0,
+        })
+        .collect();
 
-    audit::log_event("grammar_check", serde_json::json!({
-        "word_count": word_count,
-        "issue_count": issue_count,
-        "duration_ms": duration_ms,
-    }));
+    if summaries.is_empty() {
+        return summaries;
+    }
 
-    CheckResult {
-        stats: TextStats {
-            word_count,
-            sentence_count,
-            issue_count,
-        },
-        issues,
+    for issue in issues {
+        let idx = summaries
+            .iter()
+            .rposition(|s| s.start <= issue.start)
+            .unwrap_or(0);
+        summaries[idx].issue_count += 1;
     }
+
+    summaries
 }
 
-/// Rewrite text using local LLM (Ollama or LM Studio)
-/// When called via rewrite_text_stream, emits "rewrite-stream" events with progressive text
-#[tauri::command]
-async fn rewrite_text(request: RewriteRequest) -> Result<RewriteResult, String> {
-    let text_length = request.text.len();
-    let mode = request.mode.clone();
+/// Seconds needed to get through `words` at `wpm`, rounded to the nearest second
+fn duration_secs(words: usize, wpm: usize) -> usize {
+    if wpm == 0 {
+        return 0;
+    }
+    (words * 60 + wpm / 2) / wpm
+}
 
-    let result = llm::rewrite(&request.text, &request.mode, None)
-        .await
-        .map_err(|e| e.to_string());
+/// Rule names config.disabled_rules can switch off: Harper's LintKind names plus
+/// the categories our own checks report under
+pub(crate) const AVAILABLE_RULES: &[&str] = &[
+    "Agreement",
+    "BoundaryError",
+    "Capitalization",
+    "Consistency",
+    "Eggcorn",
+    "Enhancement",
+    "Formatting",
+    "Grammar",
+    "Inclusivity",
+    "Malapropism",
+    "Miscellaneous",
+    "Nonstandard",
+    "Punctuation",
+    "Readability",
+    "Redundancy",
+    "Regionalism",
+    "Repetition",
+    "Spelling",
+    "Style",
+    "Typo",
+    "Usage",
+    "WordChoice",
+];
+
+/// Lint kinds kept by the "relaxed" profile — spelling and clear-cut grammar errors.
+/// Matched against the Debug name of harper's LintKind (plus our own categories).
+const RELAXED_KINDS: &[&str] = &[
+    "Spelling",
+    "Typo",
+    "Grammar",
+    "Agreement",
+    "Repetition",
+    "Malapropism",
+    "Eggcorn",
+    "BoundaryError",
+];
+
+/// Post-lint filter for a linting profile. "strict" (the default) keeps everything;
+/// "relaxed" drops style and low-severity suggestions. Unknown profiles act as strict.
+fn apply_profile(issues: Vec<GrammarIssue>, profile: &str) -> Vec<GrammarIssue> {
+    match profile {
+        "relaxed" => issues
+            .into_iter()
+            .filter(|issue| RELAXED_KINDS.contains(&issue.rule.as_str()))
+            .collect(),
+        _ => issues,
+    }
+}
 
-    let (success, provider) = match &result {
-        Ok(_) => (true, "detected".to_string()),
-        Err(e) => (false, e.clone()),
-    };
+/// Splice the first suggestion of each issue into `text`.
+/// Applied right-to-left so earlier offsets stay valid; overlapping spans are skipped.
+/// Returns the new text and how many fixes were applied.
+fn apply_suggestions(text: &str, issues: &[&GrammarIssue]) -> (String, usize) {
+    let mut ordered: Vec<&GrammarIssue> = issues
+        .iter()
+        .copied()
+        .filter(|issue| !issue.suggestions.is_empty())
+        .collect();
+    ordered.sort_by(|a, b| b.start.cmp(&a.start).then(b.end.cmp(&a.end)));
+
+    let mut result = text.to_string();
+    let mut applied = 0;
+    // Start of the leftmost span applied so far — anything reaching past it overlaps
+    let mut boundary = usize::MAX;
+    for issue in ordered {
+        if issue.end > boundary || issue.start > issue.end {
+            continue;
+        }
+        let byte_start = char_to_byte(text, issue.start);
+        let byte_end = char_to_byte(text, issue.end);
+        result.replace_range(byte_start..byte_end, &issue.suggestions[0]);
+        boundary = issue.start;
+        applied += 1;
+    }
+    (result, applied)
+}
 
-    audit::log_event("rewrite", serde_json::json!({
-        "mode": mode,
-        "text_length": text_length,
-        "success": success,
-        "provider": provider,
+/// Apply the first suggestion of each accepted issue. `accepted` indexes the
+/// issues check_grammar returned for the same `request`, so pass it unchanged.
+#[tauri::command]
+fn apply_fixes(request: CheckRequest, accepted: Vec<usize>) -> Result<String, String> {
+    let issues = check_request(&request, &request_config(&request)).issues;
+    let mut selected = Vec::with_capacity(accepted.len());
+    for idx in accepted {
+        let issue = issues
+            .get(idx)
+            .ok_or_else(|| format!("Issue index {} out of range ({} issues)", idx, issues.len()))?;
+        selected.push(issue);
+    }
+    let (fixed, applied) = apply_suggestions(&request.text, &selected);
+    audit::log_event("apply_fixes", serde_json::json!({
+        "applied": applied,
+        "session_id": request.session_id,
     }));
-
-    result
+    Ok(fixed)
+}
+
+/// Focus mode: the first issue check_grammar would show for `request` that
+/// starts at or after char `after_offset`, the most severe one when several
+/// start at the same place. None past the last issue.
+#[tauri::command]
+fn next_issue(request: CheckRequest, after_offset: usize) -> Option<GrammarIssue> {
+    next_issue_with(&request, after_offset, &request_config(&request))
+}
+
+fn next_issue_with(request: &CheckRequest, after_offset: usize, config: &config::Config) -> Option<GrammarIssue> {
+    check_request(request, config)
+        .issues
+        .into_iter()
+        .filter(|issue| issue.start >= after_offset)
+        .min_by(|a, b| a.start.cmp(&b.start).then(a.severity.cmp(&b.severity)))
+}
+
+/// Replace the char range `span` with `replacement`, but only if it still reads
+/// `expected_original`. The document may have changed since the check that
+/// produced the suggestion; applying it then would corrupt the text.
+#[tauri::command]
+fn safe_apply(text: String, span: [usize; 2], expected_original: String, replacement: String) -> Result<String, String> {
+    let [start, end] = span;
+    if start > end || end > text.chars().count() {
+        return Err("stale suggestion: span is outside the text".to_string());
+    }
+    let (byte_start, byte_end) = (char_to_byte(&text, start), char_to_byte(&text, end));
+    if text[byte_start..byte_end] != expected_original {
+        return Err(format!("stale suggestion: expected '{}' but found '{}'", expected_original, &text[byte_start..byte_end]));
+    }
+    Ok(format!("{}{}{}", &text[..byte_start], replacement, &text[byte_end..]))
+}
+
+/// Apply the first suggestion of every issue check_grammar reports for `request`
+/// and save the result. `format` is "text" or "markdown"; a path without an
+/// extension gets ".txt" or ".md" to match. An existing file is only replaced
+/// when `overwrite` is set. Returns how many fixes were applied.
+#[tauri::command]
+fn export_corrected(request: CheckRequest, path: String, format: String, overwrite: bool) -> Result<usize, String> {
+    let extension = match format.as_str() {
+        "text" => "txt",
+        "markdown" => "md",
+        other => return Err(format!("Unknown export format '{}'. Use \"text\" or \"markdown\".", other)),
+    };
+    let mut path = std::path::PathBuf::from(path);
+    if path.extension().is_none() {
+        path.set_extension(extension);
+    }
+
+    let issues = check_request(&request, &request_config(&request)).issues;
+    let all: Vec<&GrammarIssue> = issues.iter().collect();
+    let (fixed, applied) = apply_suggestions(&request.text, &all);
+
+    // create_new makes the existence check and the create one step
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    let mut file = options.open(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => format!("{} already exists. Pass overwrite to replace it.", path.display()),
+        _ => format!("Failed to open {}: {}", path.display(), e),
+    })?;
+    file.write_all(fixed.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    audit::log_event("export_corrected", serde_json::json!({
+        "format": format,
+        "fixes_applied": applied,
+        "text_length": request.text.len(),
+    }));
+
+    Ok(applied)
+}
+
+/// Drop issues whose span falls entirely inside fenced, indented, or inline code
+fn drop_code_issues(text: &str, issues: &mut Vec<GrammarIssue>) {
+    let regions = segment::code_regions(text);
+    if regions.is_empty() {
+        return;
+    }
+    issues.retain(|issue| {
+        let start = char_to_byte(text, issue.start);
+        let end = char_to_byte(text, issue.end);
+        !regions.iter().any(|r| r.start <= start && end <= r.end)
+    });
+}
+
+/// An issue from check_grammar_multi and the dialects that raised it
+#[derive(Serialize)]
+pub struct DialectIssue {
+    #[serde(flatten)]
+    pub issue: GrammarIssue,
+    /// Dialects that flag this issue, in request order
+    pub dialects: Vec<String>,
+    /// True when every requested dialect flags it — not a dialect question
+    pub universal: bool,
+}
+
+#[derive(Serialize)]
+pub struct MultiCheckResult {
+    /// Canonical names of the dialects checked ("american", "british", ...)
+    pub dialects: Vec<String>,
+    /// Each distinct issue once, sorted by position
+    pub issues: Vec<DialectIssue>,
+}
+
+fn check_multi(text: &str, dialects: &[String], config: &config::Config) -> Result<MultiCheckResult, String> {
+    let mut names: Vec<String> = Vec::new();
+    for name in dialects {
+        let dialect = parse_dialect(name).ok_or_else(|| format!("Unknown dialect '{}'", name))?;
+        let canonical = format!("{:?}", dialect).to_lowercase();
+        if !names.contains(&canonical) {
+            names.push(canonical);
+        }
+    }
+    if names.is_empty() {
+        return Err("Pick at least one dialect".to_string());
+    }
+
+    let mut merged: Vec<DialectIssue> = Vec::new();
+    for name in &names {
+        let dialect_config = config::Config { dialect: name.clone(), ..config.clone() };
+        for issue in collect_issues_with(text, &dialect_config) {
+            let same = |d: &&mut DialectIssue| {
+                d.issue.start == issue.start
+                    && d.issue.end == issue.end
+                    && d.issue.rule == issue.rule
+                    && d.issue.message == issue.message
+            };
+            match merged.iter_mut().find(same) {
+                Some(existing) => existing.dialects.push(name.clone()),
+                None => merged.push(DialectIssue { issue, dialects: vec![name.clone()], universal: false }),
+            }
+        }
+    }
+    for entry in &mut merged {
+        entry.universal = entry.dialects.len() == names.len();
+    }
+    merged.sort_by(|a, b| a.issue.start.cmp(&b.issue.start).then(a.issue.end.cmp(&b.issue.end)));
+    Ok(MultiCheckResult { dialects: names, issues: merged })
+}
+
+/// Check under several dialects at once and label each issue with the dialects
+/// that flag it, so dialect-specific flags stand apart from universal ones
+#[tauri::command]
+fn check_grammar_multi(text: String, dialects: Vec<String>) -> Result<MultiCheckResult, String> {
+    check_multi(&text, &dialects, &config::load())
+}
+
+/// Lint text and compute stats. Shared by check_grammar and check_and_rewrite,
+/// which each log their own audit event.
+fn build_check(
+    text: &str,
+    profile: &str,
+    max_issues: Option<usize>,
+    ignored: &[ignores::Ignore],
+    skip_code: bool,
+    config: &config::Config,
+) -> CheckResult {
+    build_check_until(text, profile, max_issues, ignored, skip_code, config, &AtomicBool::new(false)).unwrap_or_default()
+}
+
+/// build_check that stops linting once `cancel` is set. None when cancelled.
+fn build_check_until(
+    text: &str,
+    profile: &str,
+    max_issues: Option<usize>,
+    ignored: &[ignores::Ignore],
+    skip_code: bool,
+    config: &config::Config,
+    cancel: &AtomicBool,
+) -> Option<CheckResult> {
+    // Nothing to lint — skip the dictionary and linters entirely
+    if text.trim().is_empty() {
+        return Some(CheckResult::default());
+    }
+    let mut issues = apply_profile(collect_issues_until(text, config, &[], cancel)?, profile);
+    ignores::filter(text, &mut issues, ignored);
+    if skip_code {
+        drop_code_issues(text, &mut issues);
+    }
+
+    let word_count = segment::word_count(text);
+    let sentence_count = segment::sentence_spans(text).len().max(1);
+    // Sentence density uses every issue; only the returned list is capped
+    let sentence_issues = summarize_sentences(text, &issues);
+    let total_issues = issues.len();
+    if let Some(max) = max_issues {
+        issues.truncate(max);
+    }
+    let issue_count = issues.len();
+
+    Some(CheckResult {
+        stats: TextStats {
+            word_count,
+            sentence_count,
+            issue_count,
+            total_issues,
+            truncated_issues: issue_count < total_issues,
+            reading_time_secs: duration_secs(word_count, config.reading_wpm),
+            speaking_time_secs: duration_secs(word_count, config.speaking_wpm),
+        },
+        issues,
+        sentence_issues,
+        superseded: false,
+    })
+}
+
+/// Running check_grammar calls by debounce key, plus the check_grammar_stream
+/// run (STREAM_CHECK_KEY), held in Tauri's managed state.
+/// A key has a slot only while a check for it runs, so idle keys cost nothing.
+#[derive(Default)]
+pub struct CheckCancellation {
+    slots: Mutex<HashMap<String, DebounceSlot>>,
+    next_generation: AtomicU64,
+}
+
+/// The newest running check for one key
+struct DebounceSlot {
+    generation: u64,
+    at: std::time::Instant,
+    cancel: Arc<AtomicBool>,
+}
+
+/// A running check's hold on its key's slot. Dropping it frees the slot unless
+/// a newer check has taken it over.
+struct CheckTicket<'a> {
+    checks: &'a CheckCancellation,
+    key: String,
+    generation: u64,
+    cancel: Arc<AtomicBool>,
+}
+
+impl CheckCancellation {
+    /// Register a check for `key`. Arriving within `window_ms` of the previous
+    /// call for the key cancels that call if it is still running.
+    fn begin(&self, key: &str, window_ms: u64) -> CheckTicket<'_> {
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        let now = std::time::Instant::now();
+        if let Some(previous) = slots.get(key) {
+            if now.duration_since(previous.at) < std::time::Duration::from_millis(window_ms) {
+                previous.cancel.store(true, Ordering::SeqCst);
+            }
+        }
+        let generation = self.next_generation.fetch_add(1, Ordering::SeqCst);
+        let cancel = Arc::new(AtomicBool::new(false));
+        slots.insert(key.to_string(), DebounceSlot { generation, at: now, cancel: Arc::clone(&cancel) });
+        CheckTicket { checks: self, key: key.to_string(), generation, cancel }
+    }
+
+    /// Keys with a check running
+    #[cfg(test)]
+    fn running(&self) -> usize {
+        self.slots.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+impl Drop for CheckTicket<'_> {
+    fn drop(&mut self) {
+        let mut slots = self.checks.slots.lock().unwrap_or_else(|e| e.into_inner());
+        if slots.get(&self.key).is_some_and(|slot| slot.generation == self.generation) {
+            slots.remove(&self.key);
+        }
+    }
+}
+
+/// Check text for grammar issues using Harper (instant, local, no network).
+/// Runs off the main thread. With a `debounce_key`, rapid calls are debounced: a
+/// newer call cancels the one still linting, which stops between chunks and
+/// passes and comes back `superseded`, so only the newest in a burst computes.
+#[tauri::command(async)]
+fn check_grammar(app: tauri::AppHandle, request: CheckRequest) -> CheckResult {
+    check_grammar_in(&app.state::<CheckCancellation>(), request)
+}
+
+fn check_grammar_in(checks: &CheckCancellation, request: CheckRequest) -> CheckResult {
+    if request.text.trim().is_empty() {
+        return CheckResult::default();
+    }
+    let start_time = std::time::Instant::now();
+    let config = request_config(&request);
+    let ticket = request.debounce_key.as_deref().map(|key| checks.begin(key, config.check_debounce_ms));
+
+    let never = AtomicBool::new(false);
+    let cancel = ticket.as_ref().map_or(&never, |ticket| ticket.cancel.as_ref());
+    // Cancelled mid-lint, or just after the last checkpoint
+    let result = match check_request_until(&request, &config, cancel) {
+        Some(result) if !cancel.load(Ordering::SeqCst) => result,
+        _ => return CheckResult { superseded: true, ..CheckResult::default() },
+    };
+
+    audit::log_event("grammar_check", serde_json::json!({
+        "word_count": result.stats.word_count,
+        "issue_count": result.stats.total_issues,
+        "duration_ms": start_time.elapsed().as_millis(),
+        "profile": request.profile,
+        "max_issues": request.max_issues,
+        "session_id": request.session_id,
+    }));
+    session::record_check(&config.dialect);
+
+    result
+}
+
+/// Config for a check: config.toml with the request's dialect override
+fn request_config(request: &CheckRequest) -> config::Config {
+    let mut config = config::load();
+    if let Some(dialect) = &request.dialect {
+        config.dialect = dialect.clone();
+    }
+    config
+}
+
+/// The result check_grammar returns for `request`, without debouncing or
+/// logging: per-document and learned ignores applied, HTML mapped back onto
+/// the markup. Commands that act on issues the user saw by index go through
+/// here so the indices mean the same thing.
+fn check_request(request: &CheckRequest, config: &config::Config) -> CheckResult {
+    check_request_until(request, config, &AtomicBool::new(false)).unwrap_or_default()
+}
+
+/// check_request that gives up once `cancel` is set. None when cancelled.
+fn check_request_until(request: &CheckRequest, config: &config::Config, cancel: &AtomicBool) -> Option<CheckResult> {
+    let mut ignored = request.text_hash.as_deref().map(ignores::for_document).unwrap_or_default();
+    ignored.extend(ignores::learned().into_iter().map(|learned| learned.ignore));
+
+    let html = (request.format == "html").then(|| html::extract(&request.text));
+    let checked = html.as_ref().map_or(request.text.as_str(), |h| h.text.as_str());
+    let result = build_check_until(checked, &request.profile, request.max_issues, &ignored, request.skip_code, config, cancel)?;
+    match &html {
+        Some(extracted) => Some(map_to_html(extracted, result)),
+        None => Some(result),
+    }
+}
+
+/// Move a check of extracted HTML text back onto the markup: issue and sentence
+/// char offsets end up pointing into the original HTML
+fn map_to_html(extracted: &html::Extracted, mut result: CheckResult) -> CheckResult {
+    for issue in &mut result.issues {
+        (issue.start, issue.end) = extracted.source_range(issue.start, issue.end);
+    }
+    for sentence in &mut result.sentence_issues {
+        (sentence.start, sentence.end) = extracted.source_range(sentence.start, sentence.end);
+    }
+    result
+}
+
+/// check_grammar's stats alone, for a document health badge. The issues are still
+/// found (they're what's counted) but never serialized back to the UI.
+#[tauri::command]
+fn grammar_stats(text: &str, profile: Option<String>) -> TextStats {
+    let profile = profile.unwrap_or_else(|| "strict".to_string());
+    build_check(text, &profile, None, &[], false, &config::load()).stats
+}
+
+/// Nudge toward a rewrite when the text is error-dense or hard to read. Lint-only, no LLM.
+#[tauri::command]
+fn rewrite_recommendation(text: String) -> recommend::Recommendation {
+    recommendation_with(&text, &config::load())
+}
+
+fn recommendation_with(text: &str, config: &config::Config) -> recommend::Recommendation {
+    let check = build_check(text, "strict", None, &[], false, config);
+    recommend::recommend(text, &check.stats, config)
+}
+
+/// check_grammar's findings as LSP diagnostics (line/UTF-16 column ranges,
+/// numeric severity, suggestions in `data`) for language-server integrations
+#[tauri::command]
+fn check_grammar_lsp(text: String) -> Vec<lsp::Diagnostic> {
+    let check = build_check(&text, "strict", None, &[], false, &config::load());
+    lsp::diagnostics(&text, &check.issues)
+}
+
+/// Run the local grammar check, then the rewrite. The check result is kept even
+/// when the rewrite fails.
+async fn check_then_rewrite<F>(text: &str, rewrite: F) -> CombinedResult
+where
+    F: std::future::Future<Output = Result<RewriteResult, String>>,
+{
+    let check = build_check(text, "strict", None, &[], false, &config::load());
+    let (rewrite, rewrite_error) = match rewrite.await {
+        Ok(result) => (Some(result), None),
+        Err(e) => (None, Some(e)),
+    };
+    CombinedResult { check, rewrite, rewrite_error }
+}
+
+/// Grammar check plus rewrite in one round trip
+#[tauri::command]
+async fn check_and_rewrite(text: String, mode: String) -> CombinedResult {
+    let start_time = std::time::Instant::now();
+
+    let request = RewriteRequest { text: text.clone(), mode: mode.clone(), ..RewriteRequest::default() };
+    let rewrite = async { llm::rewrite(&request, None).await.map_err(|e| e.to_string()) };
+    let result = check_then_rewrite(&text, rewrite).await;
+    if result.rewrite.is_some() {
+        session::record_rewrite(&mode);
+    }
+
+    audit::log_event("check_and_rewrite", serde_json::json!({
+        "mode": mode,
+        "word_count": result.check.stats.word_count,
+        "issue_count": result.check.stats.issue_count,
+        "rewrite_success": result.rewrite.is_some(),
+        "error": result.rewrite_error,
+        "duration_ms": start_time.elapsed().as_millis(),
+    }));
+
+    result
+}
+
+/// Grammar check plus the model's "explain" critique, merged. Falls back to the
+/// Harper findings alone when the model call fails.
+async fn review_with<F>(text: &str, explain: F) -> review::DeepReview
+where
+    F: std::future::Future<Output = Result<RewriteResult, String>>,
+{
+    let check = build_check(text, "strict", None, &[], false, &config::load());
+    let (explanation, llm_error) = match explain.await {
+        Ok(result) => ([result.rewritten, result.explanation].join("\n"), None),
+        Err(e) => (String::new(), Some(e)),
+    };
+    review::DeepReview {
+        items: review::merge(text, &check.issues, &explanation),
+        llm_available: llm_error.is_none(),
+        llm_error,
+    }
+}
+
+/// Harper and the local model together: Harper's findings, the model's critique,
+/// and the overlap between them, each item marked by source
+#[tauri::command]
+async fn deep_review(text: String) -> review::DeepReview {
+    let start_time = std::time::Instant::now();
+    let request = RewriteRequest { text: text.clone(), mode: "explain".to_string(), ..RewriteRequest::default() };
+    let explain = async { llm::rewrite(&request, None).await.map_err(|e| e.to_string()) };
+    let result = review_with(&text, explain).await;
+
+    audit::log_event("deep_review", serde_json::json!({
+        "item_count": result.items.len(),
+        "llm_available": result.llm_available,
+        "error": result.llm_error,
+        "duration_ms": start_time.elapsed().as_millis(),
+    }));
+
+    result
+}
+
+/// CheckCancellation key for check_grammar_stream. Every new stream supersedes
+/// the one in flight, however long ago it started.
+const STREAM_CHECK_KEY: &str = "check_grammar_stream";
+
+/// Lint sentence-by-sentence, handing each batch to `emit` as it finishes.
+/// Offsets are document-relative. Stops early once `ticket` is cancelled.
+/// Returns the number of batches emitted.
+fn check_progressive<F>(text: &str, ticket: &CheckTicket, config: &config::Config, mut emit: F) -> usize
+where
+    F: FnMut(GrammarBatch),
+{
+    let spans = segment::sentence_spans(text);
+    let sentence_count = spans.len();
+    let mut emitted = 0;
+
+    for (sentence_index, span) in spans.into_iter().enumerate() {
+        if ticket.cancel.load(Ordering::SeqCst) {
+            break;
+        }
+        let char_offset = text[..span.start].chars().count();
+        let mut issues = collect_issues_with(&text[span], config);
+        for issue in &mut issues {
+            issue.start += char_offset;
+            issue.end += char_offset;
+        }
+        emit(GrammarBatch {
+            generation: ticket.generation,
+            sentence_index,
+            sentence_count,
+            issues,
+        });
+        emitted += 1;
+    }
+
+    emitted
+}
+
+/// Progressive grammar check — emits a "grammar-batch" event per sentence.
+/// Calling it again cancels any run still in flight; stale batches carry an old generation.
+#[tauri::command]
+async fn check_grammar_stream(app: tauri::AppHandle, text: String) -> Result<usize, String> {
+    tokio::task::spawn_blocking(move || {
+        let checks = app.state::<CheckCancellation>();
+        let ticket = checks.begin(STREAM_CHECK_KEY, u64::MAX);
+        check_progressive(&text, &ticket, &config::load(), |batch| {
+            let _ = app.emit("grammar-batch", &batch);
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))
+}
+
+/// Rewrite text using local LLM (Ollama or LM Studio)
+/// When called via rewrite_text_stream, emits "rewrite-stream" events with progressive text
+#[tauri::command]
+async fn rewrite_text(request: RewriteRequest) -> Result<RewriteResult, String> {
+    let start_time = std::time::Instant::now();
+    let text_length = request.text.len();
+    let mode = request.mode.clone();
+
+    let result = llm::rewrite(&request, None)
+        .await
+        .map_err(|e| e.to_string());
+    // Only a finished rewrite becomes the last-used mode
+    if result.is_ok() {
+        session::record_rewrite(&mode);
+    }
+
+    let (success, provider, cached) = match &result {
+        Ok(r) => (true, "detected".to_string(), r.cached),
+        Err(e) => (false, e.clone(), false),
+    };
+
+    audit::log_event("rewrite", serde_json::json!({
+        "mode": mode,
+        "text_length": text_length,
+        "success": success,
+        "provider": provider,
+        "cached": cached,
+        "duration_ms": start_time.elapsed().as_millis(),
+        "session_id": request.session_id,
+    }));
+
+    result
 }
 
 /// Streaming rewrite — emits "rewrite-stream" events as tokens arrive
 #[tauri::command]
 async fn rewrite_text_stream(app: tauri::AppHandle, request: RewriteRequest) -> Result<RewriteResult, String> {
+    let start_time = std::time::Instant::now();
     let text_length = request.text.len();
     let mode = request.mode.clone();
 
-    let result = llm::rewrite(&request.text, &request.mode, Some(&app))
+    let emit_partial = move |partial: &str| {
+        let _ = app.emit("rewrite-stream", partial);
+    };
+    let result = llm::rewrite(&request, Some(&emit_partial))
         .await
         .map_err(|e| e.to_string());
+    if result.is_ok() {
+        session::record_rewrite(&mode);
+    }
 
     let (success, provider) = match &result {
         Ok(_) => (true, "detected".to_string()),
@@ -317,11 +1624,157 @@ async fn rewrite_text_stream(app: tauri::AppHandle, request: RewriteRequest) ->
         "text_length": text_length,
         "success": success,
         "provider": provider,
+        "duration_ms": start_time.elapsed().as_millis(),
+        "session_id": request.session_id,
     }));
 
     result
 }
 
+/// One "explain-stream" event: the critique so far, and whether it's finished
+#[derive(Serialize, Clone)]
+struct ExplainChunk {
+    text: String,
+    done: bool,
+}
+
+/// Streaming "explain" critique — emits "explain-stream" events as tokens arrive.
+/// The last event has `done: true` and carries the complete critique.
+#[tauri::command]
+async fn explain_text_stream(app: tauri::AppHandle, text: String) -> Result<String, String> {
+    let start_time = std::time::Instant::now();
+    let emitter = app.clone();
+    let emit_partial = move |partial: &str| {
+        let _ = emitter.emit("explain-stream", ExplainChunk { text: partial.to_string(), done: false });
+    };
+    let result = llm::explain_stream(&text, &emit_partial)
+        .await
+        .map_err(|e| e.to_string());
+
+    if let Ok(critique) = &result {
+        let _ = app.emit("explain-stream", ExplainChunk { text: critique.clone(), done: true });
+    }
+
+    audit::log_event("explain_stream", serde_json::json!({
+        "text_length": text.len(),
+        "success": result.is_ok(),
+        "error": result.as_ref().err(),
+        "duration_ms": start_time.elapsed().as_millis(),
+    }));
+
+    result
+}
+
+/// Last-used rewrite mode, dialect, and recent custom modes, for restoring UI state
+#[tauri::command]
+fn get_session_state() -> session::SessionState {
+    session::load()
+}
+
+/// Ask the local model to explain one grammar finding in teaching terms.
+/// Returns the linter's own message when no model is available.
+#[tauri::command]
+async fn explain_issue(text: String, issue: GrammarIssue) -> String {
+    let explanation = llm::explain_issue(&text, &issue).await;
+
+    audit::log_event("explain_issue", serde_json::json!({
+        "rule": issue.rule,
+        "enriched": explanation != issue.message,
+    }));
+
+    explanation
+}
+
+/// Begin a writing session. Pass the returned id as `session_id` to check_grammar,
+/// rewrite_text, apply_fixes, and save_feedback, then read it back with session_report.
+#[tauri::command]
+fn start_session() -> String {
+    let session_id = session::new_session_id();
+    audit::log_event("session_start", serde_json::json!({ "session_id": session_id }));
+    session_id
+}
+
+/// Checks, fixes, rewrites, and feedback logged under one writing session
+#[tauri::command]
+fn session_report(session_id: String) -> audit::SessionReport {
+    audit::session_report(&session_id)
+}
+
+/// Summarize local usage (checks, rewrites, durations, modes) over the last `days` days
+#[tauri::command]
+fn usage_metrics(days: u32) -> audit::UsageMetrics {
+    audit::usage_metrics(days)
+}
+
+/// Show the prompt a rewrite would send, with its estimated token count
+#[tauri::command]
+async fn preview_prompt(request: RewriteRequest) -> PromptPreview {
+    llm::preview_prompt(&request.text, &request.mode).await
+}
+
+/// Estimated tokens and cost of a rewrite on a metered model, without sending it
+#[tauri::command]
+async fn estimate_cost(request: RewriteRequest) -> Result<CostEstimate, String> {
+    llm::estimate_cost(&request).await.map_err(|e| e.to_string())
+}
+
+/// Estimate whether rewriting `text` fits the model's context window
+#[tauri::command]
+fn context_check(text: String, model: String) -> ContextInfo {
+    llm::context_check(&text, &model)
+}
+
+/// Score Harper against a corpus file in grammar_corpus.json's format: recall and
+/// precision overall and per issue type, plus what each entry missed. Reading
+/// and linting block, so they run off the async runtime.
+#[tauri::command]
+async fn run_corpus(path: String) -> Result<corpus::CorpusMetrics, String> {
+    tokio::task::spawn_blocking(move || -> Result<corpus::CorpusMetrics, String> {
+        let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let entries = corpus::parse(&content)?;
+        Ok(corpus::evaluate(&entries))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Check ~/.ghostpen/config.toml for typos and bad values.
+/// With `probe`, also warns when no LLM server answers on the configured host.
+#[tauri::command]
+async fn validate_config(app: tauri::AppHandle, probe: Option<bool>) -> config::ConfigReport {
+    let mut report = config::report();
+    if probe.unwrap_or(false)
+        && report.errors.is_empty()
+        && !llm::check_status(&app.state::<llm::StatusCache>()).await.map(|s| s.available).unwrap_or(false)
+    {
+        report.warnings.push(config::ConfigDiagnostic {
+            key: "llm_host".to_string(),
+            message: format!("No LLM server answered on {}", config::load().llm_host),
+        });
+    }
+    report
+}
+
+/// Saved settings profiles, with the active one marked
+#[tauri::command]
+fn list_profiles() -> Vec<profiles::ProfileInfo> {
+    profiles::list()
+}
+
+/// Save `settings` (config keys and values) as a named profile
+#[tauri::command]
+fn save_profile(name: String, settings: serde_json::Value) -> Result<(), String> {
+    profiles::save(&name, &settings)
+}
+
+/// Switch profiles. Takes effect on the next check or rewrite; "" turns profiles off.
+#[tauri::command]
+fn activate_profile(name: String) -> Result<(), String> {
+    profiles::activate(&name)?;
+    audit::log_event("profile_activated", serde_json::json!({ "name": name }));
+    Ok(())
+}
+
 /// Cancel an in-flight rewrite request
 #[tauri::command]
 fn cancel_rewrite() {
@@ -330,19 +1783,107 @@ fn cancel_rewrite() {
 
 /// Check if a local LLM server is running
 #[tauri::command]
-async fn check_llm_status() -> Result<LlmStatus, String> {
-    let result = llm::check_status().await.map_err(|e| e.to_string());
+async fn check_llm_status(app: tauri::AppHandle) -> Result<LlmStatus, String> {
+    let result = llm::check_status(&app.state::<llm::StatusCache>()).await.map_err(|e| e.to_string());
 
     if let Ok(ref status) = result {
         audit::log_event("llm_status_check", serde_json::json!({
             "available": status.available,
             "provider": status.provider,
+            "address": status.address,
+        }));
+    }
+
+    result
+}
+
+/// Most recent check_llm_status result, without probing the network
+#[tauri::command]
+fn last_known_status(status: tauri::State<'_, llm::StatusCache>) -> Option<CachedStatus> {
+    status.last()
+}
+
+/// Delete local data by category: any of cleanup::CATEGORIES ("audit",
+/// "dictionary", "profiles", ...), or "all" (which must be passed explicitly)
+#[tauri::command]
+fn clear_data(what: Vec<String>) -> Result<cleanup::ClearReport, String> {
+    let result = cleanup::clear(&what);
+
+    // Logging now would recreate the audit log that was just removed
+    if let Ok(report) = &result {
+        if !report.cleared.iter().any(|c| c == "audit") {
+            audit::log_event("clear_data", serde_json::json!({
+                "cleared": report.cleared,
+                "removed_count": report.removed.len(),
+            }));
+        }
+    }
+
+    result
+}
+
+/// Probe every configured LLM endpoint and report why each is or isn't usable
+#[tauri::command]
+async fn diagnose_llm() -> Result<LlmDiagnostics, String> {
+    let result = llm::diagnose().await.map_err(|e| e.to_string());
+
+    if let Ok(ref report) = result {
+        audit::log_event("llm_diagnose", serde_json::json!({
+            "offline": report.offline,
+            "reachable": report.endpoints.iter().filter(|e| e.reachable).count(),
+            "endpoints": report.endpoints.len(),
         }));
     }
 
     result
 }
 
+/// Measure the local model's generation speed with a fixed ~100 token prompt
+#[tauri::command]
+async fn benchmark_llm() -> Result<BenchmarkResult, String> {
+    let result = llm::benchmark().await.map_err(|e| e.to_string());
+
+    match &result {
+        Ok(r) => audit::log_event("llm_benchmark", serde_json::json!({
+            "provider": r.provider,
+            "model": r.model,
+            "tokens_generated": r.tokens_generated,
+            "tokens_estimated": r.tokens_estimated,
+            "total_ms": r.total_ms,
+            "time_to_first_token_ms": r.time_to_first_token_ms,
+            "tokens_per_sec": r.tokens_per_sec,
+        })),
+        Err(e) => audit::log_event("llm_benchmark", serde_json::json!({
+            "success": false,
+            "error": e,
+        })),
+    }
+
+    result
+}
+
+/// Download an Ollama model (e.g. "qwen2.5:3b"), emitting `model-pull-progress`
+/// events as it downloads. Succeeds at once if the model is already installed.
+#[tauri::command]
+async fn pull_ollama_model(app: tauri::AppHandle, name: String) -> Result<String, String> {
+    let start_time = std::time::Instant::now();
+    let emit_progress = move |progress: &llm::PullProgress| {
+        let _ = app.emit("model-pull-progress", progress);
+    };
+    let result = llm::pull_ollama_model(&name, &emit_progress)
+        .await
+        .map_err(|e| e.to_string());
+
+    audit::log_event("model_pull", serde_json::json!({
+        "model": name,
+        "success": result.is_ok(),
+        "error": result.as_ref().err(),
+        "duration_ms": start_time.elapsed().as_millis(),
+    }));
+
+    result
+}
+
 /// Launch LM Studio in the background
 #[tauri::command]
 fn launch_llm() -> Result<String, String> {
@@ -368,6 +1909,9 @@ pub struct FeedbackRequest {
     pub original_text: String,
     pub rewritten_text: String,
     pub mode: String,
+    /// Writing session (from start_session) the feedback is logged under
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
 /// Correct grammar using local T5 ONNX model
@@ -384,17 +1928,60 @@ async fn correct_grammar_ai(text: String, app: tauri::AppHandle) -> Result<t5::A
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
+/// Ratings accepted by save_feedback
+const FEEDBACK_RATINGS: &[&str] = &["up", "down"];
+
+/// Serializes feedback.jsonl appends so concurrent saves can't interleave lines
+static FEEDBACK_LOCK: Mutex<()> = Mutex::new(());
+
+/// Error returned by save_feedback. `kind` is "invalid_rating" or "io".
+#[derive(Serialize, Debug)]
+pub struct FeedbackError {
+    pub kind: String,
+    pub message: String,
+}
+
+impl FeedbackError {
+    fn io(context: &str, e: std::io::Error) -> Self {
+        Self { kind: "io".to_string(), message: format!("{}: {}", context, e) }
+    }
+}
+
+/// Append one JSON line and fsync it before releasing the lock.
+/// The whole line goes out in a single write so a crash can't leave half an entry mid-file.
+fn append_feedback(path: &std::path::Path, entry: &serde_json::Value) -> Result<(), FeedbackError> {
+    let _guard = FEEDBACK_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| FeedbackError::io("Failed to open feedback file", e))?;
+
+    let line = format!("{}\n", entry);
+    file.write_all(line.as_bytes())
+        .map_err(|e| FeedbackError::io("Failed to write feedback", e))?;
+    file.sync_data()
+        .map_err(|e| FeedbackError::io("Failed to flush feedback", e))
+}
+
 /// Save user feedback on a rewrite to ~/.ghostpen/feedback.jsonl
 #[tauri::command]
-fn save_feedback(feedback: FeedbackRequest) -> Result<String, String> {
-    let ghostpen_dir = dirs::home_dir()
-        .ok_or_else(|| "Could not determine home directory".to_string())?
-        .join(".ghostpen");
+fn save_feedback(feedback: FeedbackRequest) -> Result<String, FeedbackError> {
+    if !FEEDBACK_RATINGS.contains(&feedback.rating.as_str()) {
+        return Err(FeedbackError {
+            kind: "invalid_rating".to_string(),
+            message: format!("Rating must be one of {:?} (got '{}')", FEEDBACK_RATINGS, feedback.rating),
+        });
+    }
 
-    std::fs::create_dir_all(&ghostpen_dir)
-        .map_err(|e| format!("Failed to create .ghostpen directory: {}", e))?;
+    let ghostpen_dir = paths::data_dir().ok_or_else(|| FeedbackError {
+        kind: "io".to_string(),
+        message: "Could not determine data directory".to_string(),
+    })?;
 
-    let feedback_path = ghostpen_dir.join("feedback.jsonl");
+    std::fs::create_dir_all(&ghostpen_dir)
+        .map_err(|e| FeedbackError::io("Failed to create .ghostpen directory", e))?;
 
     let entry = serde_json::json!({
         "timestamp": chrono::Utc::now().to_rfc3339(),
@@ -403,19 +1990,12 @@ fn save_feedback(feedback: FeedbackRequest) -> Result<String, String> {
         "rewritten_text": feedback.rewritten_text,
         "mode": feedback.mode,
     });
-
-    let mut file = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&feedback_path)
-        .map_err(|e| format!("Failed to open feedback file: {}", e))?;
-
-    writeln!(file, "{}", entry.to_string())
-        .map_err(|e| format!("Failed to write feedback: {}", e))?;
+    append_feedback(&ghostpen_dir.join("feedback.jsonl"), &entry)?;
 
     audit::log_event("feedback", serde_json::json!({
         "rating": feedback.rating,
         "mode": feedback.mode,
+        "session_id": feedback.session_id,
     }));
 
     Ok("ok".to_string())
@@ -428,14 +2008,65 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_clipboard_manager::init())
+        .setup(|app| {
+            modes::watch(app.handle().clone());
+            let handle = app.handle().clone();
+            std::thread::spawn(move || {
+                lint_pool::warm_up();
+                let _ = handle.emit("grammar_ready", ());
+            });
+            Ok(())
+        })
+        .manage(CheckCancellation::default())
+        .manage(llm::StatusCache::default())
         .invoke_handler(tauri::generate_handler![
             check_grammar,
+            check_grammar_stream,
+            check_grammar_lsp,
+            grammar_stats,
+            rewrite_recommendation,
+            check_grammar_multi,
+            apply_fixes,
+            safe_apply,
+            export_corrected,
+            repetition_report,
             add_to_dictionary,
+            preview_dictionary,
+            quick_spellcheck,
+            next_issue,
+            synonyms,
+            normalize_typography,
+            dictionary_info,
+            list_available_rules,
+            ignore_issue,
+            learned_ignores,
+            clear_learned_ignores,
             rewrite_text,
             rewrite_text_stream,
+            explain_text_stream,
+            check_and_rewrite,
+            deep_review,
+            preview_prompt,
+            estimate_cost,
+            context_check,
+            explain_issue,
+            validate_config,
+            run_corpus,
+            list_profiles,
+            save_profile,
+            activate_profile,
+            usage_metrics,
+            start_session,
+            session_report,
+            get_session_state,
             cancel_rewrite,
             check_llm_status,
+            last_known_status,
+            diagnose_llm,
+            clear_data,
+            benchmark_llm,
             launch_llm,
+            pull_ollama_model,
             save_feedback,
             correct_grammar_ai,
         ])
@@ -447,6 +2078,12 @@ pub fn run() {
 mod tests {
     use super::*;
 
+    /// check_grammar without a running app, sharing one registry across tests
+    fn check_grammar(request: CheckRequest) -> CheckResult {
+        static CHECKS: OnceLock<CheckCancellation> = OnceLock::new();
+        check_grammar_in(CHECKS.get_or_init(CheckCancellation::default), request)
+    }
+
     #[test]
     fn punctuation_missing_period() {
         // The regex requires: starts with capital, 10+ chars, ends with [a-z0-9)\]"'\-*`] at end of line
@@ -478,6 +2115,31 @@ mod tests {
         assert!(issues.iter().any(|i| i.message.contains("Multiple spaces")));
     }
 
+    #[test]
+    fn whitespace_double_space_span() {
+        let issues = check_whitespace("One  two.");
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].start, issues[0].end), (3, 5));
+        assert_eq!(issues[0].suggestions, vec![" "]);
+    }
+
+    #[test]
+    fn whitespace_trailing_space_removed() {
+        let text = "Line one.  \nLine two. \r\n    indented stays.";
+        let issues = check_whitespace(text);
+        assert_eq!(issues.len(), 2);
+        assert!(issues.iter().all(|i| i.message.contains("Trailing") && i.suggestions == vec![""]));
+        assert_eq!((issues[0].start, issues[0].end), (9, 11));
+        assert_eq!((issues[1].start, issues[1].end), (21, 22));
+    }
+
+    #[test]
+    fn whitespace_mixed_tabs() {
+        let issues = check_whitespace("Mixed \tgap here.");
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.contains("Tabs mixed"));
+    }
+
     #[test]
     fn punctuation_widened_chars() {
         // Lines ending in hyphens, asterisks, backticks should be caught
@@ -485,4 +2147,692 @@ mod tests {
         // The hyphen at end of line should trigger missing punctuation
         assert!(issues.iter().any(|i| i.message.contains("missing ending punctuation")));
     }
+
+    #[test]
+    fn apply_two_non_overlapping_fixes() {
+        let text = "Wait  what?? Really.";
+        let issues = check_punctuation(text);
+        let refs: Vec<&GrammarIssue> = issues.iter().collect();
+        let (fixed, applied) = apply_suggestions(text, &refs);
+        assert_eq!(applied, 2);
+        assert_eq!(fixed, "Wait what? Really.");
+    }
+
+    #[test]
+    fn apply_skips_overlapping_fixes() {
+        let issue = |start, end, fix: &str| GrammarIssue {
+            start,
+            end,
+            message: String::new(),
+            suggestions: vec![fix.to_string()],
+            severity: Severity::Suggestion,
+            rule: "Style".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
+        };
+        let a = issue(0, 5, "Hi");
+        let b = issue(3, 8, "X");
+        let (fixed, applied) = apply_suggestions("Hello there", &[&a, &b]);
+        assert_eq!(applied, 1);
+        assert_eq!(fixed, "HelXere");
+    }
+
+    #[test]
+    fn progressive_check_emits_one_batch_per_sentence() {
+        let text = "This is fine. Wait  here. And  there!";
+        let checks = CheckCancellation::default();
+        let config = config::Config::default();
+        let ticket = checks.begin(STREAM_CHECK_KEY, u64::MAX);
+        let mut batches = Vec::new();
+        let emitted = check_progressive(text, &ticket, &config, |batch| batches.push(batch));
+        assert_eq!(emitted, 3);
+        assert_eq!(batches.len(), 3);
+        // Double-space issues keep document-relative char offsets
+        let spaces: Vec<usize> = batches
+            .iter()
+            .flat_map(|b| b.issues.iter())
+            .filter(|i| i.message.contains("Multiple spaces"))
+            .map(|i| i.start)
+            .collect();
+        assert_eq!(spaces, vec![18, 29]);
+
+        // A newer stream cancels this one before its next sentence
+        let newer = checks.begin(STREAM_CHECK_KEY, u64::MAX);
+        assert_eq!(check_progressive(text, &ticket, &config, |_| {}), 0);
+        assert!(!newer.cancel.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn sentence_summary_buckets_issues() {
+        let text = "This one is fine. This  one is not.";
+        let issues = check_punctuation(text);
+        let summaries = summarize_sentences(text, &issues);
+        assert_eq!(summaries, vec![
+            SentenceSummary { start: 0, end: 17, issue_count: 0 },
+            SentenceSummary { start: 18, end: 35, issue_count: 1 },
+        ]);
+
+        // Char offsets, like the issues: "é" is two bytes but one char
+        let text = "Café is fine. Café  is not.";
+        let summaries = summarize_sentences(text, &check_punctuation(text));
+        assert_eq!((summaries[1].start, summaries[1].end), (14, 27));
+        assert_eq!(summaries[1].issue_count, 1);
+    }
+
+    #[test]
+    fn relaxed_profile_drops_style_issues() {
+        let text = "Teh  cat sat!!";
+        let mut issues = check_punctuation(text);
+        issues.push(GrammarIssue {
+            start: 0,
+            end: 3,
+            message: "Did you mean \"The\"?".to_string(),
+            suggestions: vec!["The".to_string()],
+            severity: Severity::Error,
+            rule: "Spelling".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
+        });
+        let relaxed = apply_profile(issues, "relaxed");
+        assert_eq!(relaxed.len(), 1);
+        assert_eq!(relaxed[0].rule, "Spelling");
+    }
+
+    #[test]
+    fn relaxed_profile_is_subset_of_strict() {
+        let text = "The recieve button dont work  properly!!";
+        let strict = apply_profile(collect_issues(text), "strict");
+        let relaxed = apply_profile(collect_issues(text), "relaxed");
+        assert!(relaxed.len() < strict.len());
+        for issue in &relaxed {
+            assert!(strict.iter().any(|s| s.start == issue.start && s.end == issue.end && s.message == issue.message));
+        }
+    }
+
+    #[test]
+    fn reading_time_for_400_words() {
+        assert_eq!(duration_secs(400, 200), 120);
+        assert_eq!(duration_secs(400, 130), 185);
+        assert_eq!(duration_secs(0, 200), 0);
+        assert_eq!(duration_secs(100, 0), 0);
+    }
+
+    #[test]
+    fn issues_are_sorted_by_position() {
+        // Punctuation issues are appended after Harper's, so this mixes both sources
+        let text = "Their  going to the store!! She dont like teh new design.";
+        let issues = collect_issues(text);
+        assert!(issues.len() >= 3);
+        assert!(issues.windows(2).all(|w| (w[0].start, w[0].end) <= (w[1].start, w[1].end)));
+    }
+
+    #[test]
+    fn data_dir_env_roots_user_files() {
+        // The root is passed in rather than set in the environment, which every
+        // other test running in parallel would see
+        let root = std::env::temp_dir().join(format!("ghostpen-data-{}", std::process::id()));
+        let data_dir = paths::data_dir_in(Some(root.clone())).unwrap();
+
+        let added = add_to_dictionary_in(&data_dir, "portablewordtest");
+        let dict = std::fs::read_to_string(root.join("dictionary.txt"));
+        let config_file = paths::config_file_in(Some(root.clone()));
+        let log_dir = paths::log_dir_in(Some(root.clone()));
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(added, Ok("ok".to_string()));
+        assert!(dict.unwrap().contains("portablewordtest"));
+        assert_eq!(config_file, Some(root.join("config.toml")));
+        assert_eq!(log_dir, root.join("logs"));
+    }
+
+    #[test]
+    fn apply_fixes_rejects_out_of_range_index() {
+        let result = apply_fixes(CheckRequest { text: "Fine.".to_string(), ..CheckRequest::default() }, vec![99]);
+        assert!(result.unwrap_err().contains("out of range"));
+    }
+
+    #[test]
+    fn apply_fixes_indexes_the_issues_check_grammar_returned() {
+        // skip_code hides the flag on the identifier, so index 0 is an issue after it
+        let request = || CheckRequest {
+            text: "Call `recieve` first. Then teh mail comes.".to_string(),
+            skip_code: true,
+            ..CheckRequest::default()
+        };
+        let shown = check_grammar(request());
+        assert!(!shown.issues.is_empty());
+
+        let fixed = apply_fixes(request(), vec![0]).unwrap();
+        assert_eq!(fixed, apply_suggestions(&request().text, &[&shown.issues[0]]).0);
+        assert!(fixed.contains("`recieve`"));
+    }
+
+    #[test]
+    fn next_issue_walks_the_document_in_order() {
+        let text = "I recieve mail. She teh best. They definately agree.";
+        let request = CheckRequest { text: text.to_string(), ..CheckRequest::default() };
+        let config = config::Config::default();
+        let all = collect_issues_with(text, &config);
+        assert!(all.len() >= 3);
+
+        let mut walked = Vec::new();
+        let mut offset = 0;
+        while let Some(issue) = next_issue_with(&request, offset, &config) {
+            offset = issue.start + 1;
+            walked.push(issue.start);
+        }
+        let mut starts: Vec<usize> = all.iter().map(|i| i.start).collect();
+        starts.dedup();
+        assert_eq!(walked, starts);
+        assert!(next_issue_with(&request, text.chars().count(), &config).is_none());
+    }
+
+    #[test]
+    fn next_issue_skips_what_check_grammar_hides() {
+        let text = "Call `recieve` first. Then teh mail comes.";
+        let config = config::Config::default();
+        let request = |skip_code| CheckRequest { text: text.to_string(), skip_code, ..CheckRequest::default() };
+        let code_end = text.find("` first").unwrap();
+
+        assert!(next_issue_with(&request(false), 0, &config).is_some_and(|i| i.end <= code_end));
+        assert!(next_issue_with(&request(true), 0, &config).is_some_and(|i| i.start > code_end));
+    }
+
+    #[test]
+    fn safe_apply_replaces_matching_span() {
+        let fixed = safe_apply("Café: I recieve mail.".to_string(), [8, 15], "recieve".to_string(), "receive".to_string());
+        assert_eq!(fixed.unwrap(), "Café: I receive mail.");
+    }
+
+    #[test]
+    fn safe_apply_rejects_stale_span() {
+        // The user typed ahead of the flagged word since the check ran
+        let edited = "Café: I now recieve mail.".to_string();
+        let err = safe_apply(edited.clone(), [8, 15], "recieve".to_string(), "receive".to_string()).unwrap_err();
+        assert!(err.starts_with("stale suggestion"));
+
+        let err = safe_apply(edited, [20, 40], "mail".to_string(), "post".to_string()).unwrap_err();
+        assert!(err.starts_with("stale suggestion"));
+    }
+
+    #[test]
+    fn export_corrected_writes_fixed_text() {
+        let text = "She dont like the the new design.";
+        let request = || CheckRequest { text: text.to_string(), ..CheckRequest::default() };
+        let base = std::env::temp_dir().join(format!("ghostpen-export-{}", std::process::id()));
+        let _ = std::fs::remove_file(base.with_extension("md"));
+
+        let applied = export_corrected(request(), base.display().to_string(), "markdown".to_string(), false).unwrap();
+        let written = std::fs::read_to_string(base.with_extension("md")).unwrap();
+        let issues = check_grammar(request()).issues;
+        let (expected, expected_applied) = apply_suggestions(text, &issues.iter().collect::<Vec<_>>());
+        assert!(applied > 0);
+        assert_eq!(applied, expected_applied);
+        assert_eq!(written, expected);
+
+        let again = export_corrected(request(), base.display().to_string(), "markdown".to_string(), false);
+        assert!(again.unwrap_err().contains("already exists"));
+        assert!(export_corrected(request(), base.display().to_string(), "markdown".to_string(), true).is_ok());
+        let _ = std::fs::remove_file(base.with_extension("md"));
+    }
+
+    #[test]
+    fn concurrent_feedback_lines_all_parse() {
+        let path = std::env::temp_dir().join(format!("ghostpen-feedback-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    for i in 0..25 {
+                        let entry = serde_json::json!({ "rating": "up", "original_text": "x".repeat(2000), "n": t * 100 + i });
+                        append_feedback(&path, &entry).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 200);
+        assert!(lines.iter().all(|l| serde_json::from_str::<serde_json::Value>(l).is_ok()));
+    }
+
+    #[test]
+    fn feedback_rejects_unknown_rating() {
+        let err = save_feedback(FeedbackRequest {
+            rating: "meh".to_string(),
+            original_text: String::new(),
+            rewritten_text: String::new(),
+            mode: "clarity".to_string(),
+            session_id: None,
+        })
+        .unwrap_err();
+        assert_eq!(err.kind, "invalid_rating");
+    }
+
+    #[tokio::test]
+    async fn check_and_rewrite_returns_both_parts() {
+        let server = test_support::chat_server("She doesn't like it.\nEXPLANATION: Fixed the verb.");
+        let text = "She dont like it.";
+        let request = RewriteRequest { text: text.to_string(), mode: "clarity".to_string(), ..RewriteRequest::default() };
+
+        let combined = check_then_rewrite(text, llm::rewrite_at(&server.url, &request)).await;
+        assert!(!combined.check.issues.is_empty());
+        assert_eq!(combined.rewrite.unwrap().rewritten, "She doesn't like it.");
+        assert!(combined.rewrite_error.is_none());
+    }
+
+    #[tokio::test]
+    async fn deep_review_merges_model_points() {
+        let text = "The recieve button works.";
+        let server = test_support::chat_server("- \"recieve\" should be \"receive\".\n- The sentence is clear and short.");
+        let request = RewriteRequest { text: text.to_string(), mode: "explain".to_string(), ..RewriteRequest::default() };
+
+        let review = review_with(text, llm::rewrite_at(&server.url, &request)).await;
+        assert!(review.llm_available);
+        let flagged = review.items.iter().find(|i| i.start == Some(4)).expect("misspelling reviewed");
+        assert_eq!(flagged.source, review::Source::Both);
+        assert!(flagged.model_note.as_deref().unwrap().contains("receive"));
+        assert!(review.items.iter().any(|i| i.source == review::Source::Llm && i.start.is_none()));
+
+        let offline = review_with(text, async { Err("No LLM server found.".to_string()) }).await;
+        assert!(!offline.llm_available);
+        assert!(offline.items.iter().all(|i| i.source == review::Source::Harper));
+    }
+
+    #[tokio::test]
+    async fn check_survives_failed_rewrite() {
+        let combined = check_then_rewrite("She dont like it.", async { Err("No LLM server found.".to_string()) }).await;
+        assert!(!combined.check.issues.is_empty());
+        assert!(combined.rewrite.is_none());
+        assert_eq!(combined.rewrite_error.as_deref(), Some("No LLM server found."));
+    }
+
+    #[test]
+    fn max_issues_caps_returned_list() {
+        let text = "Teh cat sat. Teh dog ran. Teh bird flew. Teh fish swam. Teh cow ate. Teh pig slept. Teh hen laid.";
+        let config = config::Config::default();
+
+        let full = build_check(text, "strict", None, &[], false, &config);
+        assert!(full.stats.total_issues > 5);
+        assert!(!full.stats.truncated_issues);
+
+        let capped = build_check(text, "strict", Some(5), &[], false, &config);
+        assert_eq!(capped.issues.len(), 5);
+        assert_eq!(capped.stats.issue_count, 5);
+        assert_eq!(capped.stats.total_issues, full.stats.total_issues);
+        assert!(capped.stats.truncated_issues);
+        let starts: Vec<usize> = capped.issues.iter().map(|i| i.start).collect();
+        assert_eq!(starts, full.issues[..5].iter().map(|i| i.start).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn multi_dialect_labels_dialect_specific_issues() {
+        let text = "The colour of the the sky.";
+        let dialects = vec!["american".to_string(), "en-gb".to_string()];
+        let result = check_multi(text, &dialects, &config::Config::default()).unwrap();
+        assert_eq!(result.dialects, vec!["american", "british"]);
+
+        let colour = result.issues.iter().find(|i| i.issue.start == 4).expect("colour flagged");
+        assert_eq!(colour.dialects, vec!["american"]);
+        assert!(!colour.universal);
+
+        let repeated = result.issues.iter().find(|i| i.dialects.len() == 2).expect("shared issue");
+        assert!(repeated.universal);
+
+        assert!(check_multi(text, &["klingon".to_string()], &config::Config::default()).is_err());
+    }
+
+    #[test]
+    fn corrupt_dictionary_falls_back_to_curated() {
+        let path = std::env::temp_dir().join(format!("ghostpen-corrupt-dict-{}.txt", std::process::id()));
+        std::fs::write(&path, [b'o', b'k', b'\n', 0xff, 0xfe, 0x00]).unwrap();
+
+        assert!(read_dictionary(&path).unwrap_err().contains("not valid UTF-8"));
+        assert!(load_dictionary_from(&path).is_empty());
+        let logged = audit::CAPTURED.lock().unwrap();
+        assert!(logged.iter().any(|(event, details)| {
+            event == "dictionary_load_error" && details["error"].as_str().unwrap_or("").contains("ghostpen-corrupt-dict")
+        }));
+        drop(logged);
+
+        // Checking still works on the built-in dictionary
+        assert!(!collect_issues("The recieve button works.").is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn newer_check_cancels_the_running_one() {
+        let checks = CheckCancellation::default();
+        let request = CheckRequest { text: "The recieve button dont work.".to_string(), ..CheckRequest::default() };
+        let config = config::Config::default();
+
+        // The first call is cancelled before it lints anything; only the newest computes
+        let first = checks.begin("editor", 1_000);
+        let second = checks.begin("editor", 1_000);
+        assert!(check_request_until(&request, &config, &first.cancel).is_none());
+        assert!(!check_request_until(&request, &config, &second.cancel).unwrap().issues.is_empty());
+
+        // Finished checks free their slot
+        drop((first, second));
+        assert_eq!(checks.running(), 0);
+
+        // Outside the window, calls don't cancel each other, and the older one
+        // finishing leaves the newer one's slot alone
+        let slow = checks.begin("slow", 0);
+        let _newer = checks.begin("slow", 0);
+        assert!(!slow.cancel.load(Ordering::SeqCst));
+        drop(slow);
+        assert_eq!(checks.running(), 1);
+
+        // A writing session spans documents; only the debounce key links calls
+        let editor = checks.begin("session-1", 1_000);
+        let other_doc = CheckRequest { session_id: Some("session-1".to_string()), debounce_key: Some("doc-b".to_string()), ..request };
+        assert!(!check_grammar_in(&checks, other_doc).superseded);
+        assert!(!editor.cancel.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn disabled_rule_findings_disappear() {
+        let text = "I recieve mail.  It comes daily.";
+        let all = collect_issues_with(text, &config::Config::default());
+        assert!(all.iter().any(|i| i.rule == "Spelling"));
+        assert!(all.iter().any(|i| i.rule == "Style"));
+
+        let config = config::Config { disabled_rules: vec!["spelling".to_string()], ..Default::default() };
+        let filtered = collect_issues_with(text, &config);
+        assert!(filtered.iter().all(|i| i.rule != "Spelling"));
+        assert!(filtered.iter().any(|i| i.rule == "Style"));
+    }
+
+    #[test]
+    fn rewrite_recommended_only_for_rough_text() {
+        let config = config::Config::default();
+        let clean = recommendation_with("The cat sat on the mat.", &config);
+        assert_eq!(clean.mode, None);
+        assert_eq!(clean.confidence, 0.0);
+
+        let rough = recommendation_with("i recieve teh mesage yesterday and it dont make sence .", &config);
+        assert_eq!(rough.mode.as_deref(), Some("proofread"));
+        assert!(rough.issues_per_100_words >= config.rewrite_issue_density);
+        assert!(rough.confidence >= 0.5);
+    }
+
+    #[test]
+    fn html_issues_point_into_the_markup() {
+        let result = check_grammar(CheckRequest {
+            text: "<p>recieve this</p>".to_string(),
+            format: "html".to_string(),
+            ..CheckRequest::default()
+        });
+        assert!(result.issues.iter().any(|i| i.rule == "Spelling" && i.matched_text == "recieve"));
+        assert!(result.issues.iter().all(|i| (i.start, i.end) == (3, 10)), "{:?}", result.issues);
+    }
+
+    #[test]
+    fn short_tokens_skip_spelling_below_threshold() {
+        let text = "Then xq appeared.";
+        let flagged = |min_spell_length| {
+            let config = config::Config { min_spell_length, ..Default::default() };
+            collect_issues_with(text, &config).iter().any(|i| i.rule == "Spelling" && i.matched_text == "xq")
+        };
+        assert!(flagged(2));
+        assert!(!flagged(3));
+    }
+
+    #[test]
+    fn session_report_ties_commands_together() {
+        let session_id = start_session();
+        let request = || CheckRequest {
+            text: "She dont like the the new design.".to_string(),
+            session_id: Some(session_id.clone()),
+            ..CheckRequest::default()
+        };
+        let first = check_grammar(request());
+        apply_fixes(request(), vec![0]).unwrap();
+        check_grammar(request());
+        check_grammar(CheckRequest { session_id: Some("someone-else".to_string()), ..request() });
+
+        let report = audit::summarize_session(&audit::captured_log(), &session_id);
+        assert_eq!(report.checks_run, 2);
+        assert_eq!(report.issues_fixed, 1);
+        assert_eq!(report.open_issues, Some(first.stats.total_issues));
+        assert_eq!(report.rewrites, 0);
+        assert!(report.started_at.is_some());
+    }
+
+    #[test]
+    fn chunked_check_matches_whole_document() {
+        let text = "She dont like the the new design.\n\nCafé owners recieve mail  daily.\n\n\nI has a apple. Their going home\n\nThis is fine.";
+        let whole = collect_issues_with(text, &config::Config { chunk_size: 0, ..Default::default() });
+        let chunked = collect_issues_with(text, &config::Config { chunk_size: 40, ..Default::default() });
+        assert!(segment::chunk_spans(text, 40).len() > 2);
+        assert!(!whole.is_empty());
+        assert_eq!(
+            serde_json::to_value(&chunked).unwrap(),
+            serde_json::to_value(&whole).unwrap()
+        );
+    }
+
+    #[test]
+    fn quick_spellcheck_reports_spelling_only() {
+        let text = "In order to win, we must recieve the the ball.";
+        let config = config::Config::default();
+        let full = collect_issues_with(text, &config);
+        assert!(full.iter().any(|i| i.rule != "Spelling"), "sample should have non-spelling issues");
+
+        let quick = quick_spellcheck_with(text, &config);
+        assert_eq!(quick.len(), 1);
+        assert_eq!(quick[0].rule, "Spelling");
+        assert_eq!(quick[0].matched_text, "recieve");
+        assert_eq!(quick[0].suggestions, vec!["receive"]);
+    }
+
+    #[test]
+    fn regional_spellings_follow_the_dialect() {
+        let text = "We realise the centre needs a new colour.";
+        let flagged = |config: &config::Config, word: &str| {
+            collect_issues_with(text, config).iter().any(|i| i.matched_text == word)
+                || quick_spellcheck_with(text, config).iter().any(|i| i.matched_text == word)
+        };
+
+        let british = config::Config { dialect: "british".to_string(), ..config::Config::default() };
+        for word in ["realise", "centre", "colour"] {
+            assert!(!flagged(&british, word), "{} flagged for British", word);
+        }
+        let canadian = config::Config { dialect: "canadian".to_string(), ..config::Config::default() };
+        for word in ["centre", "colour"] {
+            assert!(!flagged(&canadian, word), "{} flagged for Canadian", word);
+        }
+        assert!(flagged(&config::Config::default(), "colour"));
+
+        // The consistency check would suggest "colour" here; American rejects it
+        let mixed = "The colour faded, then the color returned.";
+        let issues = collect_issues_with(mixed, &config::Config::default());
+        assert!(!issues.iter().any(|i| i.suggestions.iter().any(|s| s == "colour")));
+        assert_eq!(wordlist::info("curated", "british").dialect, "british");
+    }
+
+    #[test]
+    fn dictionary_preview_resolves_only_the_new_word() {
+        let text = "Our kubelet config has a typo: recieve.";
+        let config = config::Config::default();
+        let preview = preview_dictionary_with(text, &["Kubelet".to_string()], &config);
+        assert_eq!(preview.resolved.len(), 1);
+        assert_eq!(preview.resolved[0].matched_text, "kubelet");
+        assert_eq!(preview.resolved[0].rule, "Spelling");
+        assert_eq!(preview.remaining, collect_issues_with(text, &config).len() - 1);
+    }
+
+    #[test]
+    fn rtl_text_yields_valid_spans() {
+        let samples = [
+            "שלום עולם, מה שלומך היום?",
+            "مرحبا بالعالم. كيف حالك اليوم؟",
+            "The word שלום means peace, and I recieve it gladly.",
+            "He said \u{202B}مرحبا بك\u{202C} and then we recieve guests.",
+            "\u{200F}אני אוהב קפה.\u{200F} I like coffee too.",
+        ];
+        for text in samples {
+            let char_count = text.chars().count();
+            for issue in collect_issues(text) {
+                assert!(issue.start <= issue.end && issue.end <= char_count, "bad span in {:?}", text);
+                let expected: String = text.chars().skip(issue.start).take(issue.end - issue.start).collect();
+                assert_eq!(issue.matched_text, expected);
+                assert!(!issue.matched_text.chars().any(segment::is_rtl), "RTL text flagged in {:?}", text);
+            }
+        }
+
+        // No English to check, so nothing to report
+        assert!(collect_issues(samples[0]).is_empty());
+        assert!(collect_issues(samples[1]).is_empty());
+        // English around RTL words is still checked, at the right place
+        assert!(collect_issues(samples[2]).iter().any(|i| i.matched_text == "recieve"));
+        assert!(collect_issues(samples[3]).iter().any(|i| i.matched_text == "recieve"));
+    }
+
+    #[test]
+    fn matched_text_is_the_flagged_word() {
+        let text = "Café owners recieve mail.";
+        let issues = collect_issues(text);
+        let spelling = issues.iter().find(|i| i.rule == "Spelling").expect("misspelling flagged");
+        assert_eq!(spelling.matched_text, "recieve");
+
+        assert_eq!(matched_text(text, 12, 19), "recieve");
+        assert_eq!(matched_text(text, 20, 99), "");
+        assert_eq!(matched_text(text, 5, 2), "");
+    }
+
+    #[test]
+    fn partial_check_request_gets_defaults() {
+        let request: CheckRequest = serde_json::from_str(r#"{"text": "Hello there.", "max_issues": 5}"#).unwrap();
+        assert_eq!(request, CheckRequest {
+            text: "Hello there.".to_string(),
+            max_issues: Some(5),
+            ..CheckRequest::default()
+        });
+        assert_eq!(request.profile, "strict");
+        assert!(!request.skip_code);
+        assert!(request.dialect.is_none());
+        assert!(request.debounce_key.is_none());
+    }
+
+    #[test]
+    fn grammar_stats_match_full_check() {
+        let text = "The recieve button dont work. It was fixed yesterday, mostly.";
+        let full = check_grammar(CheckRequest { text: text.to_string(), ..CheckRequest::default() });
+        assert!(full.stats.issue_count > 0);
+        assert_eq!(grammar_stats(text, None), full.stats);
+        assert_eq!(grammar_stats("  ", None), TextStats::default());
+    }
+
+    #[test]
+    fn blank_input_returns_empty_check() {
+        for text in ["", "    ", " \n\t "] {
+            let result = check_grammar(CheckRequest { text: text.to_string(), ..CheckRequest::default() });
+            assert!(result.issues.is_empty());
+            assert!(result.sentence_issues.is_empty());
+            assert_eq!(result.stats.word_count, 0);
+            assert_eq!(result.stats.sentence_count, 0);
+        }
+    }
+
+    #[test]
+    fn spelling_maps_to_error_and_style_to_suggestion() {
+        let issues = collect_issues_with("The recieve button  works.", &config::Config::default());
+        let spelling = issues.iter().find(|i| i.rule == "Spelling").expect("misspelling flagged");
+        assert_eq!(spelling.severity, Severity::Error);
+        assert_eq!(serde_json::to_value(spelling).unwrap()["severity"], "error");
+
+        let style = issues.iter().find(|i| i.rule == "Style").expect("double space flagged");
+        assert_eq!(style.severity, Severity::Suggestion);
+
+        // Kinds we haven't mapped still get a stable bucket
+        assert_eq!(Severity::for_rule("SomeFutureKind"), Severity::Warning);
+    }
+
+    #[test]
+    fn best_fix_is_top_suggestion_or_none() {
+        let text = "The recieve button works.";
+        let issues = collect_issues(text);
+        let spelling = issues.iter().find(|i| i.rule == "Spelling").expect("misspelling flagged");
+        assert!(!spelling.suggestions.is_empty());
+        assert_eq!(spelling.best_fix.as_ref(), spelling.suggestions.first());
+
+        let bare = GrammarIssue {
+            start: 4,
+            end: 11,
+            message: "Consider rephrasing.".to_string(),
+            suggestions: vec![],
+            severity: Severity::Suggestion,
+            rule: "Style".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
+        };
+        assert_eq!(best_fix(text, &bare), None);
+    }
+
+    #[test]
+    fn full_dictionary_accepts_rare_word() {
+        let text = "Her sesquipedalian prose tired the readers.";
+        let spelling_spans = |config: &config::Config| {
+            collect_issues_with(text, config)
+                .into_iter()
+                .filter(|i| i.rule == "Spelling")
+                .map(|i| (i.start, i.end))
+                .collect::<Vec<_>>()
+        };
+
+        let curated = config::Config::default();
+        assert_eq!(spelling_spans(&curated), vec![(4, 18)]);
+
+        let full = config::Config { dictionary: "full".to_string(), ..config::Config::default() };
+        assert!(spelling_spans(&full).is_empty());
+    }
+
+    #[test]
+    fn ignored_issue_is_filtered_on_next_check() {
+        let text = "The recieve button works.";
+        let config = config::Config::default();
+        let first = build_check(text, "strict", None, &[], false, &config);
+        let issue = first.issues.iter().find(|i| i.rule == "Spelling").unwrap();
+
+        let matched = &text[char_to_byte(text, issue.start)..char_to_byte(text, issue.end)];
+        let ignored = vec![ignores::Ignore::new(&issue.message, matched)];
+        let second = build_check(&format!("Edited. {}", text), "strict", None, &ignored, false, &config);
+        assert!(second.issues.iter().all(|i| i.message != issue.message));
+    }
+
+    #[test]
+    fn skip_code_drops_issues_inside_code() {
+        // A misspelled identifier, so there is something in the code to skip
+        let text = "Call the `recieve` function before you start.";
+        let config = config::Config::default();
+        let code_start = text.find('`').unwrap();
+        let in_code = |result: &CheckResult| {
+            result.issues.iter().any(|i| i.start >= code_start && i.end <= code_start + 9)
+        };
+
+        assert!(in_code(&build_check(text, "strict", None, &[], false, &config)));
+        assert!(!in_code(&build_check(text, "strict", None, &[], true, &config)));
+    }
+
+    #[test]
+    fn spans_are_char_offsets_in_accented_text() {
+        // Every accented letter is two bytes, so byte offsets would land past the typo
+        for text in ["Café owners recieve mail.", "Naïve façades and déjà vu recieve praise."] {
+            let start = text[..text.find("recieve").unwrap()].chars().count();
+            let issue = collect_issues(text)
+                .into_iter()
+                .find(|i| i.suggestions.iter().any(|s| s == "receive"))
+                .expect("typo flagged");
+            assert_eq!((issue.start, issue.end), (start, start + 7), "in {:?}", text);
+        }
+    }
 }
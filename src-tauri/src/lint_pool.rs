@@ -0,0 +1,112 @@
+//! Reusable Harper linters. Building a curated LintGroup loads every rule, so
+//! groups are kept and reused per configuration instead of rebuilt per check.
+//!
+//! LintGroup isn't guaranteed to be Send, so the pool is per thread. Tauri runs
+//! commands on a small fixed set of worker threads, so each configuration is
+//! built a handful of times at most.
+//!
+//! `warm_up` runs once at startup so the first keystroke doesn't pay for loading
+//! the curated dictionary, the one piece shared across threads. Groups can't be
+//! built ahead for the command threads, so each still builds its own on first use.
+
+use harper_core::linting::{LintGroup, SpellCheck};
+use harper_core::spell::FstDictionary;
+use harper_core::{Dialect, Document};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Once};
+
+/// Settings a LintGroup is built from. Profiles filter results after linting,
+/// so they share a group with every other profile on the same dialect.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct PoolKey {
+    dialect: String,
+}
+
+thread_local! {
+    static POOL: RefCell<HashMap<PoolKey, LintGroup>> = RefCell::new(HashMap::new());
+    /// Lone spell checkers for quick_spellcheck, which skips every other rule
+    static SPELLERS: RefCell<HashMap<PoolKey, SpellCheck<Arc<FstDictionary>>>> = RefCell::new(HashMap::new());
+    /// Groups built on this thread, for tests
+    static BUILT: Cell<usize> = const { Cell::new(0) };
+}
+
+static WARM_UP: Once = Once::new();
+/// Times warm_up actually did its work, for tests
+static PRIMED: AtomicUsize = AtomicUsize::new(0);
+
+/// Load the curated dictionary and parse a throwaway sentence against it. Only
+/// the first call does anything; later calls return immediately.
+pub fn warm_up() {
+    WARM_UP.call_once(|| {
+        PRIMED.fetch_add(1, Ordering::Relaxed);
+        let dict = FstDictionary::curated();
+        let _ = Document::new_plain_english("Ghostpen is warming up.", &dict);
+    });
+}
+
+/// Run `f` with the pooled LintGroup for `dialect`, building it on first use
+pub fn with_group<R>(dialect: Dialect, f: impl FnOnce(&mut LintGroup) -> R) -> R {
+    let key = PoolKey { dialect: format!("{:?}", dialect) };
+    POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let group = pool.entry(key).or_insert_with(|| {
+            BUILT.with(|built| built.set(built.get() + 1));
+            LintGroup::new_curated(Arc::clone(&FstDictionary::curated()), dialect)
+        });
+        f(group)
+    })
+}
+
+/// Run `f` with the pooled spell checker for `dialect`, building it on first use
+pub fn with_spell_check<R>(dialect: Dialect, f: impl FnOnce(&mut SpellCheck<Arc<FstDictionary>>) -> R) -> R {
+    let key = PoolKey { dialect: format!("{:?}", dialect) };
+    SPELLERS.with(|spellers| {
+        let mut spellers = spellers.borrow_mut();
+        let spell = spellers
+            .entry(key)
+            .or_insert_with(|| SpellCheck::new(Arc::clone(&FstDictionary::curated()), dialect));
+        f(spell)
+    })
+}
+
+/// How many groups this thread has built
+#[cfg(test)]
+pub fn built_on_this_thread() -> usize {
+    BUILT.with(Cell::get)
+}
+
+/// How many times warm_up has primed the caches
+#[cfg(test)]
+pub fn primed() -> usize {
+    PRIMED.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_config_reuses_the_group() {
+        let before = built_on_this_thread();
+        with_group(Dialect::British, |_| ());
+        with_group(Dialect::British, |_| ());
+        assert_eq!(built_on_this_thread(), before + 1);
+
+        with_group(Dialect::American, |_| ());
+        assert_eq!(built_on_this_thread(), before + 2);
+    }
+
+    #[test]
+    fn warm_up_primes_once() {
+        let handles: Vec<_> = (0..4)
+            .map(|_| std::thread::spawn(warm_up))
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        warm_up();
+        assert_eq!(primed(), 1);
+    }
+}
@@ -1,7 +1,11 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicU64, Ordering};
-use tauri::Emitter;
-use crate::{RewriteResult, LlmStatus};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::Semaphore;
+use crate::{Annotation, BenchmarkResult, ConstraintReport, CachedStatus, ContextInfo, CostEstimate, EndpointDiagnostic, LlmDiagnostics, GrammarIssue, RewriteRequest, RewriteResult, LlmStatus, PromptPreview};
+use crate::config::{self, Config};
+use crate::stream;
 
 /// Generation counter for cancel safety — each rewrite gets a unique ID.
 /// cancel stores the ID to cancel; the streaming loop checks its own ID.
@@ -13,25 +17,49 @@ pub fn request_cancel() {
     CANCEL_GENERATION.store(REWRITE_GENERATION.load(Ordering::SeqCst), Ordering::SeqCst);
 }
 
-// Both Ollama and LM Studio serve OpenAI-compatible API on these ports
-// Use 127.0.0.1 instead of localhost — Windows can resolve localhost to IPv6 ::1
-// while LM Studio / Ollama only bind to IPv4
-const LMSTUDIO_URL: &str = "http://127.0.0.1:1234";
-const OLLAMA_LOCAL_URL: &str = "http://127.0.0.1:11434";
+/// Returned by every network feature while offline mode is on
+pub const OFFLINE_ERROR: &str = "Offline mode is on (GHOSTPEN_OFFLINE or `offline` in config.toml). AI features are disabled; grammar checking still works.";
+
+// Both Ollama and LM Studio serve OpenAI-compatible API on these ports.
+// The host comes from config (llm_host) — see config.rs for why it defaults to 127.0.0.1
+const LMSTUDIO_PORT: u16 = 1234;
+const OLLAMA_PORT: u16 = 11434;
 
 // Default models (user can change later)
 const OLLAMA_MODEL: &str = "qwen2.5:3b";
 const LMSTUDIO_MODEL: &str = "default"; // LM Studio uses whatever model is loaded
 
+/// Modes with a dedicated prompt in build_prompt; anything else is a custom mode
+pub const BUILTIN_MODES: &[&str] = &["clarity", "concise", "formal", "casual", "explain", "proofread"];
+
+/// Number of recent rewrites kept by the in-memory cache
+const REWRITE_CACHE_CAPACITY: usize = 32;
+
+const SYSTEM_PROMPT: &str = "You are a writing assistant. You help improve text while preserving the writer's voice. Always explain WHY you made changes so the writer learns. Be concise.";
+
+/// Average tokens per word for English BPE tokenizers, in tenths (1.3 tokens/word)
+const TOKENS_PER_WORD_X10: usize = 13;
+
 #[derive(Serialize)]
 struct ChatRequest {
     model: String,
     messages: Vec<ChatMessage>,
     stream: bool,
     temperature: f32,
+    /// Left out when unset; servers that don't know the field may reject it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
 }
 
+/// Asks OpenAI-compatible servers to append token usage to the final SSE chunk
 #[derive(Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+#[derive(Serialize, Clone)]
 struct ChatMessage {
     role: String,
     content: String,
@@ -52,11 +80,140 @@ struct ChatResponseMessage {
     content: String,
 }
 
+/// Request body for Ollama's native /api/chat endpoint
+#[derive(Serialize)]
+struct OllamaChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    stream: bool,
+    options: OllamaOptions,
+}
+
+#[derive(Serialize)]
+struct OllamaOptions {
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+}
+
+/// Sampling settings sent with a completion
+#[derive(Clone, Copy)]
+struct Sampling {
+    temperature: f32,
+    seed: Option<u64>,
+}
+
+impl Sampling {
+    fn unseeded(config: &Config) -> Self {
+        Self { temperature: config.temperature, seed: None }
+    }
+}
+
+/// Non-streaming /api/chat response: a single message, not a choices array
+#[derive(Deserialize)]
+struct OllamaChatResponse {
+    message: ChatResponseMessage,
+}
+
+#[derive(Clone)]
 enum Provider {
     Ollama,
     LmStudio,
 }
 
+impl Provider {
+    fn label(&self) -> &'static str {
+        match self {
+            Provider::Ollama => "Ollama",
+            Provider::LmStudio => "LM Studio",
+        }
+    }
+
+    /// Key for this provider in config tables such as `[models]`
+    fn config_key(&self) -> &'static str {
+        match self {
+            Provider::Ollama => "ollama",
+            Provider::LmStudio => "lmstudio",
+        }
+    }
+}
+
+/// The server turned down the model itself (400 or 404), so the next model in
+/// the fallback chain may still work. Anything else ends the rewrite.
+#[derive(Debug)]
+struct ModelUnavailable {
+    model: String,
+    status: u16,
+    detail: String,
+}
+
+impl std::fmt::Display for ModelUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} was refused ({})", self.model, self.status)?;
+        if !self.detail.trim().is_empty() {
+            write!(f, ": {}", self.detail.trim())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ModelUnavailable {}
+
+/// The connection closed after the request went out and before the reply was
+/// complete — usually a proxy giving up on an idle connection. Kept apart from
+/// timeouts, which mean our own deadline passed.
+#[derive(Debug)]
+struct ConnectionDropped {
+    after_secs: u64,
+    detail: String,
+}
+
+impl std::fmt::Display for ConnectionDropped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "connection dropped after {} seconds: {}", self.after_secs, self.detail)
+    }
+}
+
+impl std::error::Error for ConnectionDropped {}
+
+/// Wrap a reqwest error as ConnectionDropped when the connection died mid-request.
+/// Timeouts, refused connections, and bad replies pass through unchanged.
+fn dropped_or(e: reqwest::Error, sent: std::time::Instant) -> Box<dyn std::error::Error + Send + Sync> {
+    if e.is_timeout() || e.is_connect() || !(e.is_body() || e.is_request()) {
+        return e.into();
+    }
+    Box::new(ConnectionDropped { after_secs: sent.elapsed().as_secs(), detail: e.to_string() })
+}
+
+/// Turn a 400/404 reply into ModelUnavailable; any other response passes through
+async fn reject_unavailable_model(
+    resp: reqwest::Response,
+    model: &str,
+) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>> {
+    let status = resp.status();
+    if status == reqwest::StatusCode::BAD_REQUEST || status == reqwest::StatusCode::NOT_FOUND {
+        return Err(ModelUnavailable {
+            model: model.to_string(),
+            status: status.as_u16(),
+            detail: resp.text().await.unwrap_or_default(),
+        }
+        .into());
+    }
+    Ok(resp)
+}
+
+/// A reachable LLM server found by detect_provider
+#[derive(Clone)]
+struct Detected {
+    /// Client used for detection, reused for the requests that follow
+    client: reqwest::Client,
+    provider: Provider,
+    /// Address that answered the probe, e.g. "127.0.0.1" or "::1"
+    address: String,
+    base_url: String,
+    model: String,
+}
+
 /// Response from /v1/models endpoint
 #[derive(Deserialize)]
 struct ModelsResponse {
@@ -68,41 +225,242 @@ struct ModelEntry {
     id: String,
 }
 
-async fn detect_provider() -> Result<(Provider, String, String), Box<dyn std::error::Error + Send + Sync>> {
-    let client = reqwest::Client::new();
-    let timeout = std::time::Duration::from_secs(2);
+/// Addresses to probe for a configured host. "localhost" is ambiguous across
+/// IPv4/IPv6 stacks, so try both loopbacks rather than trusting the resolver.
+pub(crate) fn candidate_hosts(host: &str) -> Vec<String> {
+    let host = host.trim();
+    match host {
+        "" => vec!["127.0.0.1".to_string()],
+        "localhost" => vec!["127.0.0.1".to_string(), "::1".to_string()],
+        other => vec![other.trim_start_matches('[').trim_end_matches(']').to_string()],
+    }
+}
+
+/// candidate_hosts, refused outright if config.allowed_hosts rules the host out
+fn permitted_hosts(config: &Config) -> Result<Vec<String>, String> {
+    let hosts = candidate_hosts(&config.llm_host);
+    for host in &hosts {
+        crate::http::check_host(host, config)?;
+    }
+    Ok(hosts)
+}
 
-    // Try LM Studio first (most common for desktop users)
-    if let Ok(resp) = client
-        .get(format!("{}/v1/models", LMSTUDIO_URL))
-        .timeout(timeout)
+/// Build a base URL, bracketing IPv6 literals
+pub(crate) fn base_url(address: &str, port: u16) -> String {
+    if address.contains(':') {
+        format!("http://[{}]:{}", address, port)
+    } else {
+        format!("http://{}:{}", address, port)
+    }
+}
+
+/// Full probe URL plus the base URL the API is mounted under. The mount is the
+/// probe path minus `api_suffix`, so a probe path of "/llm/v1/models" puts the
+/// OpenAI-compatible API at "<root>/llm". Errors if the combined URL doesn't parse.
+pub(crate) fn probe_urls(root: &str, probe_path: &str, api_suffix: &str) -> Result<(String, String), String> {
+    let path = probe_path.trim();
+    let path = if path.starts_with('/') { path.to_string() } else { format!("/{}", path) };
+    let probe = format!("{}{}", root, path);
+    reqwest::Url::parse(&probe).map_err(|e| format!("Invalid probe URL '{}': {}", probe, e))?;
+
+    let mount = path.strip_suffix(api_suffix).unwrap_or("").trim_end_matches('/');
+    Ok((probe, format!("{}{}", root, mount)))
+}
+
+/// Probe an LM Studio-style /v1/models endpoint and pick a chat model from it
+async fn probe_lm_studio(
+    client: &reqwest::Client,
+    address: &str,
+    root: &str,
+    probe_path: &str,
+) -> Option<Detected> {
+    let (probe, base) = probe_urls(root, probe_path, "/v1/models").ok()?;
+    let resp = client
+        .get(&probe)
+        .timeout(std::time::Duration::from_secs(2))
         .send()
         .await
-    {
-        if resp.status().is_success() {
-            // Parse the actual model name — skip embedding models, pick first chat-capable one
-            let model_name = if let Ok(models) = resp.json::<ModelsResponse>().await {
-                models.data.iter()
-                    .find(|m| !m.id.contains("embed"))
-                    .or_else(|| models.data.first())
-                    .map(|m| m.id.clone())
-                    .unwrap_or_else(|| LMSTUDIO_MODEL.to_string())
-            } else {
-                LMSTUDIO_MODEL.to_string()
-            };
-            return Ok((Provider::LmStudio, LMSTUDIO_URL.to_string(), model_name));
+        .ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+
+    // Parse the actual model name — skip embedding models, pick first chat-capable one
+    let model_name = if let Ok(models) = resp.json::<ModelsResponse>().await {
+        models.data.iter()
+            .find(|m| !m.id.contains("embed"))
+            .or_else(|| models.data.first())
+            .map(|m| m.id.clone())
+            .unwrap_or_else(|| LMSTUDIO_MODEL.to_string())
+    } else {
+        LMSTUDIO_MODEL.to_string()
+    };
+    Some(Detected {
+        client: client.clone(),
+        provider: Provider::LmStudio,
+        address: address.to_string(),
+        base_url: base,
+        model: model_name,
+    })
+}
+
+/// Probe an Ollama server; the probe path is also its mount point
+async fn probe_ollama(
+    client: &reqwest::Client,
+    address: &str,
+    root: &str,
+    probe_path: &str,
+) -> Option<Detected> {
+    let (probe, base) = probe_urls(root, probe_path, "").ok()?;
+    let resp = client
+        .get(&probe)
+        .timeout(std::time::Duration::from_secs(2))
+        .send()
+        .await
+        .ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    Some(Detected {
+        client: client.clone(),
+        provider: Provider::Ollama,
+        address: address.to_string(),
+        base_url: base,
+        model: OLLAMA_MODEL.to_string(),
+    })
+}
+
+/// One progress line from Ollama's /api/pull, forwarded as `model-pull-progress`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PullProgress {
+    /// "pulling manifest", "downloading", "verifying sha256 digest", "success", ...
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// Bytes of the current layer, when downloading
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct OllamaTags {
+    #[serde(default)]
+    models: Vec<OllamaTag>,
+}
+
+#[derive(Deserialize)]
+struct OllamaTag {
+    name: String,
+}
+
+/// Download a model into the local Ollama, reporting progress as it streams in.
+/// Returns immediately if the model is already installed.
+pub async fn pull_ollama_model(
+    name: &str,
+    on_progress: &(dyn Fn(&PullProgress) + Send + Sync),
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let config = config::load();
+    if config.is_offline() {
+        return Err(OFFLINE_ERROR.into());
+    }
+    let client = crate::http::shared_client(&config)?;
+    let root = base_url(&permitted_hosts(&config)?[0], OLLAMA_PORT);
+    let (_, base) = probe_urls(&root, &config.ollama_probe_path, "")?;
+    pull_model_at(&client, &base, name, on_progress).await
+}
+
+async fn pull_model_at(
+    client: &reqwest::Client,
+    base: &str,
+    name: &str,
+    on_progress: &(dyn Fn(&PullProgress) + Send + Sync),
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    use futures_util::StreamExt;
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Model name is empty".into());
+    }
+    let not_running = |_| format!("Ollama isn't running at {}. Start it and try again.", base);
+
+    let tags = client
+        .get(format!("{}/api/tags", base))
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await
+        .map_err(not_running)?;
+    if let Ok(tags) = tags.json::<OllamaTags>().await {
+        // "qwen2.5:3b" and a bare "mistral" (meaning "mistral:latest") both count
+        let installed = tags.models.iter().any(|m| {
+            m.name == name || (!name.contains(':') && m.name == format!("{}:latest", name))
+        });
+        if installed {
+            on_progress(&PullProgress { status: "already exists".to_string(), digest: None, total: None, completed: None });
+            return Ok(format!("{} is already installed", name));
         }
     }
 
-    // Try local Ollama
-    if let Ok(resp) = client
-        .get(OLLAMA_LOCAL_URL)
-        .timeout(timeout)
+    let resp = client
+        .post(format!("{}/api/pull", base))
+        .json(&serde_json::json!({ "model": name, "stream": true }))
         .send()
         .await
-    {
-        if resp.status().is_success() {
-            return Ok((Provider::Ollama, OLLAMA_LOCAL_URL.to_string(), OLLAMA_MODEL.to_string()));
+        .map_err(not_running)?;
+    if !resp.status().is_success() {
+        return Err(format!("Ollama refused the pull ({}): {}", resp.status(), resp.text().await.unwrap_or_default()).into());
+    }
+
+    let mut lines = std::pin::pin!(stream::json_values::<serde_json::Value, _, _, _>(resp.bytes_stream(), stream::Format::Jsonl));
+    let mut finished = false;
+    while let Some(line) = lines.next().await {
+        finished |= handle_pull_line(line?, on_progress)?;
+    }
+
+    if !finished {
+        return Err(format!("Pull of {} ended before Ollama reported success", name).into());
+    }
+    Ok(format!("Pulled {}", name))
+}
+
+/// Forward one progress line. True once Ollama reports success; an `error` line fails the pull.
+fn handle_pull_line(
+    value: serde_json::Value,
+    on_progress: &(dyn Fn(&PullProgress) + Send + Sync),
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+        return Err(format!("Ollama couldn't pull the model: {}", error).into());
+    }
+    let progress: PullProgress = serde_json::from_value(value)?;
+    on_progress(&progress);
+    Ok(progress.status == "success")
+}
+
+async fn detect_provider() -> Result<Detected, Box<dyn std::error::Error + Send + Sync>> {
+    detect_provider_with(&config::load()).await
+}
+
+async fn detect_provider_with(config: &Config) -> Result<Detected, Box<dyn std::error::Error + Send + Sync>> {
+    if config.is_offline() {
+        return Err(OFFLINE_ERROR.into());
+    }
+    let client = crate::http::shared_client(config)?;
+    let hosts = permitted_hosts(config)?;
+
+    // Try LM Studio first (most common for desktop users)
+    for address in &hosts {
+        let root = base_url(address, LMSTUDIO_PORT);
+        if let Some(detected) = probe_lm_studio(&client, address, &root, &config.lmstudio_probe_path).await {
+            return Ok(detected);
+        }
+    }
+
+    // Try local Ollama
+    for address in &hosts {
+        let root = base_url(address, OLLAMA_PORT);
+        if let Some(detected) = probe_ollama(&client, address, &root, &config.ollama_probe_path).await {
+            return Ok(detected);
         }
     }
 
@@ -111,6 +469,13 @@ async fn detect_provider() -> Result<(Provider, String, String), Box<dyn std::er
 
 /// Attempt to launch LM Studio in the background
 pub fn launch_lm_studio() -> Result<String, String> {
+    launch_lm_studio_with(&config::load())
+}
+
+fn launch_lm_studio_with(config: &Config) -> Result<String, String> {
+    if config.is_offline() {
+        return Err(OFFLINE_ERROR.to_string());
+    }
     // Try common LM Studio paths on Windows
     let paths = [
         dirs::home_dir().map(|h| h.join(".lmstudio/bin/lms.exe")),
@@ -142,30 +507,177 @@ pub fn launch_lm_studio() -> Result<String, String> {
     Err("LM Studio not found. Install from https://lmstudio.ai".to_string())
 }
 
-pub async fn check_status() -> Result<LlmStatus, Box<dyn std::error::Error + Send + Sync>> {
-    match detect_provider().await {
-        Ok((Provider::Ollama, _, model)) => Ok(LlmStatus {
-            available: true,
-            provider: "Ollama".to_string(),
-            model,
-        }),
-        Ok((Provider::LmStudio, _, model)) => Ok(LlmStatus {
+/// Probe every endpoint detection would try and report what each one said,
+/// instead of stopping at the first that answers
+pub async fn diagnose() -> Result<LlmDiagnostics, Box<dyn std::error::Error + Send + Sync>> {
+    diagnose_with(&config::load()).await
+}
+
+async fn diagnose_with(config: &Config) -> Result<LlmDiagnostics, Box<dyn std::error::Error + Send + Sync>> {
+    if config.is_offline() {
+        return Ok(LlmDiagnostics { offline: true, endpoints: Vec::new() });
+    }
+    let client = crate::http::shared_client(config)?;
+    let hosts = permitted_hosts(config)?;
+
+    let mut endpoints = Vec::new();
+    for address in &hosts {
+        let root = base_url(address, LMSTUDIO_PORT);
+        endpoints.push(diagnose_endpoint(&client, Provider::LmStudio, &root, &config.lmstudio_probe_path).await);
+    }
+    for address in &hosts {
+        let root = base_url(address, OLLAMA_PORT);
+        endpoints.push(diagnose_endpoint(&client, Provider::Ollama, &root, &config.ollama_probe_path).await);
+    }
+    Ok(LlmDiagnostics { offline: false, endpoints })
+}
+
+/// Probe one endpoint the way detection does, keeping the status, timing, and model list
+async fn diagnose_endpoint(client: &reqwest::Client, provider: Provider, root: &str, probe_path: &str) -> EndpointDiagnostic {
+    let label = provider.label();
+    let api_suffix = match provider {
+        Provider::LmStudio => "/v1/models",
+        Provider::Ollama => "",
+    };
+    let mut report = EndpointDiagnostic {
+        provider: label.to_string(),
+        url: format!("{}{}", root, probe_path),
+        reachable: false,
+        status: None,
+        error: None,
+        latency_ms: None,
+        models: Vec::new(),
+        summary: String::new(),
+    };
+    let (probe, base) = match probe_urls(root, probe_path, api_suffix) {
+        Ok(urls) => urls,
+        Err(e) => {
+            report.summary = format!("{} probe URL is invalid — check the probe path in config.toml", label);
+            report.error = Some(e);
+            return report;
+        }
+    };
+    report.url = probe.clone();
+
+    let started = std::time::Instant::now();
+    let resp = client.get(&probe).timeout(std::time::Duration::from_secs(2)).send().await;
+    report.latency_ms = Some(started.elapsed().as_millis() as u64);
+    let resp = match resp {
+        Ok(resp) => resp,
+        Err(e) => {
+            report.summary = if e.is_timeout() {
+                format!("{} didn't answer within 2s", label)
+            } else {
+                format!("{} isn't running (nothing listening at {})", label, root)
+            };
+            report.error = Some(e.to_string());
+            return report;
+        }
+    };
+    report.reachable = true;
+    report.status = Some(resp.status().as_u16());
+    if !resp.status().is_success() {
+        report.summary = format!("{} reachable but the probe returned HTTP {}", label, resp.status().as_u16());
+        return report;
+    }
+
+    report.models = match provider {
+        Provider::LmStudio => resp
+            .json::<ModelsResponse>()
+            .await
+            .map(|models| models.data.into_iter().map(|m| m.id).collect())
+            .unwrap_or_default(),
+        // Ollama's probe path is a health check; models come from /api/tags
+        Provider::Ollama => match client.get(format!("{}/api/tags", base)).timeout(std::time::Duration::from_secs(2)).send().await {
+            Ok(tags) => tags
+                .json::<OllamaTags>()
+                .await
+                .map(|tags| tags.models.into_iter().map(|m| m.name).collect())
+                .unwrap_or_default(),
+            Err(_) => Vec::new(),
+        },
+    };
+    report.summary = match (&provider, report.models.len()) {
+        (Provider::LmStudio, 0) => format!("{} reachable but no model loaded", label),
+        (Provider::Ollama, 0) => format!("{} reachable but no models installed — try pulling one", label),
+        (_, n) => format!("{} reachable with {} model{}", label, n, if n == 1 { "" } else { "s" }),
+    };
+    report
+}
+
+/// Most recent probe result and when it was taken, for last_known_status.
+/// Held in Tauri's managed state; every probe through check_status updates it.
+#[derive(Default)]
+pub struct StatusCache(Mutex<Option<(LlmStatus, std::time::Instant)>>);
+
+impl StatusCache {
+    fn record(&self, status: &LlmStatus) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some((status.clone(), std::time::Instant::now()));
+    }
+
+    /// The last probe result without touching the network; None until the first probe
+    pub fn last(&self) -> Option<CachedStatus> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|(status, at)| CachedStatus {
+                status: status.clone(),
+                status_age_secs: at.elapsed().as_secs(),
+            })
+    }
+}
+
+pub async fn check_status(cache: &StatusCache) -> Result<LlmStatus, Box<dyn std::error::Error + Send + Sync>> {
+    check_status_with(&config::load(), cache).await
+}
+
+async fn check_status_with(config: &Config, cache: &StatusCache) -> Result<LlmStatus, Box<dyn std::error::Error + Send + Sync>> {
+    // Nothing was probed, so the cached status is left alone
+    if config.is_offline() {
+        return Ok(LlmStatus {
+            available: false,
+            provider: "offline".to_string(),
+            model: String::new(),
+            address: String::new(),
+        });
+    }
+    Ok(record_probe(detect_provider_with(config).await, cache))
+}
+
+/// Turn a detection result into a status and remember it in `cache`
+fn record_probe<E>(detected: Result<Detected, E>, cache: &StatusCache) -> LlmStatus {
+    let status = match detected {
+        Ok(detected) => LlmStatus {
             available: true,
-            provider: "LM Studio".to_string(),
-            model,
-        }),
-        Err(_) => Ok(LlmStatus {
+            provider: detected.provider.label().to_string(),
+            model: detected.model,
+            address: detected.address,
+        },
+        Err(_) => LlmStatus {
             available: false,
             provider: "none".to_string(),
             model: String::new(),
-        }),
-    }
+            address: String::new(),
+        },
+    };
+    cache.record(&status);
+    status
 }
 
 /// Streaming chunk from OpenAI-compatible SSE
 #[derive(Deserialize)]
 struct StreamChunk {
+    #[serde(default)]
     choices: Vec<StreamChoice>,
+    /// Present on the final chunk when stream_options.include_usage is set
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    completion_tokens: usize,
 }
 
 #[derive(Deserialize)]
@@ -179,146 +691,1177 @@ struct StreamDelta {
     content: Option<String>,
 }
 
-pub async fn rewrite(text: &str, mode: &str, app_handle: Option<&tauri::AppHandle>) -> Result<RewriteResult, Box<dyn std::error::Error + Send + Sync>> {
-    // Assign a unique generation ID to this rewrite call
-    let my_generation = REWRITE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
-
-    let (_provider, base_url, model) = detect_provider().await?;
-
-    let system_prompt = "You are a writing assistant. You help improve text while preserving the writer's voice. Always explain WHY you made changes so the writer learns. Be concise.";
-    let user_prompt = build_prompt(text, mode);
+/// Everything that determines a rewrite's output, for cache lookups
+#[derive(PartialEq)]
+struct CacheKey {
+    text: String,
+    mode: String,
+    model: String,
+    temperature_bits: u32,
+    output_format: String,
+    annotate: bool,
+    seed: Option<u64>,
+}
 
-    let api_url = format!("{}/v1/chat/completions", base_url);
+/// Most-recently-used first
+static REWRITE_CACHE: Mutex<VecDeque<(CacheKey, RewriteResult)>> = Mutex::new(VecDeque::new());
 
-    let use_stream = app_handle.is_some();
-    let client = reqwest::Client::new();
-    let resp = client
-        .post(&api_url)
-        .json(&ChatRequest {
-            model,
-            messages: vec![
-                ChatMessage {
-                    role: "system".to_string(),
-                    content: system_prompt.to_string(),
-                },
-                ChatMessage {
-                    role: "user".to_string(),
-                    content: user_prompt,
-                },
-            ],
-            stream: use_stream,
-            temperature: 0.3,
-        })
-        .timeout(std::time::Duration::from_secs(180))
-        .send()
-        .await?;
+fn cache_get(key: &CacheKey) -> Option<RewriteResult> {
+    let mut cache = REWRITE_CACHE.lock().ok()?;
+    let idx = cache.iter().position(|(k, _)| k == key)?;
+    let entry = cache.remove(idx)?;
+    let result = entry.1.clone();
+    cache.push_front(entry);
+    Some(result)
+}
 
-    let full = if use_stream {
-        // Stream tokens and emit events to the frontend
-        use futures_util::StreamExt;
-        let app = app_handle.unwrap();
-        let mut accumulated = String::new();
-        let mut stream = resp.bytes_stream();
+fn cache_put(key: CacheKey, result: RewriteResult) {
+    if let Ok(mut cache) = REWRITE_CACHE.lock() {
+        cache.retain(|(k, _)| k != &key);
+        cache.push_front((key, result));
+        cache.truncate(REWRITE_CACHE_CAPACITY);
+    }
+}
 
-        // SSE buffer — responses come as "data: {...}\n\n" lines
-        let mut buffer = String::new();
+/// Drop every cached rewrite
+pub fn clear_cache() {
+    if let Ok(mut cache) = REWRITE_CACHE.lock() {
+        cache.clear();
+    }
+}
 
-        while let Some(chunk_result) = stream.next().await {
-            if CANCEL_GENERATION.load(Ordering::SeqCst) == my_generation {
-                return Err("Rewrite cancelled by user".into());
-            }
+/// Receives the accumulated rewrite text each time a streamed chunk arrives
+pub type OnChunk = dyn Fn(&str) + Send + Sync;
 
-            let chunk = chunk_result?;
-            buffer.push_str(&String::from_utf8_lossy(&chunk));
-            if buffer.len() > 1_048_576 {
-                return Err("SSE buffer overflow — malformed LLM response".into());
-            }
+/// Chunk count and time-to-first-token for one streamed rewrite
+#[derive(Default)]
+struct StreamStats {
+    chunks: usize,
+    first_token_ms: Option<u64>,
+}
 
-            // Process complete SSE lines
-            while let Some(line_end) = buffer.find('\n') {
-                let line = buffer[..line_end].trim().to_string();
-                buffer = buffer[line_end + 1..].to_string();
+/// Semaphore bounding concurrent rewrites, with the limit it was built for
+static REWRITE_PERMITS: Mutex<Option<(usize, Arc<Semaphore>)>> = Mutex::new(None);
 
-                if line.starts_with("data: ") {
-                    let json_str = &line[6..];
-                    if json_str.trim() == "[DONE]" {
-                        continue;
-                    }
-                    if let Ok(chunk) = serde_json::from_str::<StreamChunk>(json_str) {
-                        if let Some(choice) = chunk.choices.first() {
-                            if let Some(content) = &choice.delta.content {
-                                accumulated.push_str(content);
-                                let _ = app.emit("rewrite-stream", &accumulated);
-                            }
-                        }
-                    }
-                }
-            }
+/// The shared rewrite semaphore. Rebuilt if the configured limit changes;
+/// rewrites already holding a permit on the old one finish undisturbed.
+fn rewrite_permits(limit: usize) -> Arc<Semaphore> {
+    let limit = limit.max(1);
+    let mut permits = REWRITE_PERMITS.lock().unwrap_or_else(|e| e.into_inner());
+    match permits.as_ref() {
+        Some((built_for, semaphore)) if *built_for == limit => Arc::clone(semaphore),
+        _ => {
+            let semaphore = Arc::new(Semaphore::new(limit));
+            *permits = Some((limit, Arc::clone(&semaphore)));
+            semaphore
         }
+    }
+}
 
-        accumulated.trim().to_string()
-    } else {
-        // Non-streaming fallback
-        let chat_resp = resp.json::<ChatResponse>().await?;
-        chat_resp
-            .choices
-            .first()
-            .map(|c| c.message.content.trim().to_string())
-            .unwrap_or_default()
-    };
+/// Empty or whitespace-only text has nothing for the model to work on
+fn reject_blank(request: &RewriteRequest) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if request.text.trim().is_empty() {
+        return Err("Nothing to rewrite — the text is empty.".into());
+    }
+    Ok(())
+}
 
-    // Validate response before parsing
-    validate_response(&full, text).map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { e.into() })?;
+/// Rewrite text. With `on_chunk`, the response is streamed and each chunk is
+/// reported as it arrives; without it, the full response is returned at once.
+pub async fn rewrite(request: &RewriteRequest, on_chunk: Option<&OnChunk>) -> Result<RewriteResult, Box<dyn std::error::Error + Send + Sync>> {
+    // Checked before detection so blank input never touches the network
+    reject_blank(request)?;
+    let config = config::load();
+    let detected = detect_provider_with(&config).await?;
+    rewrite_with(&detected, request, on_chunk, &config).await
+}
 
-    // Parse response — try to split rewrite from explanation
-    let (rewritten, explanation) = parse_response(&full);
+/// Stream the "explain" critique through `on_chunk` as it's generated.
+/// Returns the complete critique; cancel_rewrite stops it like any rewrite.
+pub async fn explain_stream(text: &str, on_chunk: &OnChunk) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let config = config::load();
+    let detected = detect_provider_with(&config).await?;
+    explain_stream_with(&detected, text, on_chunk, &config).await
+}
 
-    Ok(RewriteResult {
-        rewritten,
-        explanation,
-    })
+async fn explain_stream_with(
+    detected: &Detected,
+    text: &str,
+    on_chunk: &OnChunk,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let request = RewriteRequest { text: text.to_string(), mode: "explain".to_string(), ..RewriteRequest::default() };
+    let result = rewrite_with(detected, &request, Some(on_chunk), config).await?;
+    // A critique has no rewrite/explanation split, but the model may still write one
+    Ok([result.rewritten, result.explanation]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n"))
 }
 
-/// Validate LLM response for common failure modes
-pub(crate) fn validate_response(response: &str, original_text: &str) -> Result<(), String> {
-    let trimmed = response.trim();
+/// True for "markdown_diff" output, false for "plain"; errors on anything else
+fn wants_marked(output_format: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    match output_format {
+        "plain" => Ok(false),
+        "markdown_diff" => Ok(true),
+        other => Err(format!("Unknown output format '{}'. Use \"plain\" or \"markdown_diff\".", other).into()),
+    }
+}
 
-    // Empty response
-    if trimmed.is_empty() {
-        return Err("LLM returned an empty response. Try again or use a different model.".to_string());
+/// Models to try in order: the mode's pinned model from config.mode_models, then
+/// the provider's list from config.models, or else the one detection picked
+fn model_chain(detected: &Detected, config: &Config, mode: &str) -> Vec<String> {
+    let mut chain = match config.models.get(detected.provider.config_key()) {
+        Some(models) if !models.is_empty() => models.clone(),
+        _ => vec![detected.model.clone()],
+    };
+    if let Some(pinned) = config.mode_models.get(mode).filter(|m| !m.trim().is_empty()) {
+        chain.retain(|m| m != pinned);
+        chain.insert(0, pinned.clone());
     }
+    chain
+}
 
-    // Repetition loops: any substring of 20+ chars repeated 3+ times consecutively
-    let bytes = trimmed.as_bytes();
-    for window_size in 20..=trimmed.len() / 3 {
-        let mut i = 0;
-        while i + window_size * 3 <= bytes.len() {
-            let pattern = &bytes[i..i + window_size];
-            if &bytes[i + window_size..i + window_size * 2] == pattern
-                && &bytes[i + window_size * 2..i + window_size * 3] == pattern
-            {
-                return Err("LLM response contains repetitive loops. Try again with a different model.".to_string());
-            }
-            i += 1;
+/// Run a rewrite against an already-detected server, moving down the model
+/// chain while the server refuses the model. Other errors end it right away.
+async fn rewrite_with(
+    detected: &Detected,
+    request: &RewriteRequest,
+    on_chunk: Option<&OnChunk>,
+    config: &Config,
+) -> Result<RewriteResult, Box<dyn std::error::Error + Send + Sync>> {
+    let mut refused = Vec::new();
+    for model in model_chain(detected, config, &request.mode) {
+        let attempt = Detected { model, ..detected.clone() };
+        match rewrite_on_model(&attempt, request, on_chunk, config).await {
+            Err(e) if e.downcast_ref::<ModelUnavailable>().is_some() => refused.push(e.to_string()),
+            outcome => return outcome,
         }
-        // Only check a few window sizes to avoid O(n^3)
-        if window_size > 60 { break; }
     }
+    Err(format!("No model could take the rewrite: {}", refused.join("; ")).into())
+}
 
-    // Length explosion: response > 5x input
-    if !original_text.is_empty() && trimmed.len() > original_text.len() * 5 {
-        return Err("LLM response is suspiciously long (>5x input). Discarding.".to_string());
+/// One model's attempt, paragraph by paragraph when the request asks for it
+async fn rewrite_on_model(
+    detected: &Detected,
+    request: &RewriteRequest,
+    on_chunk: Option<&OnChunk>,
+    config: &Config,
+) -> Result<RewriteResult, Box<dyn std::error::Error + Send + Sync>> {
+    if request.split_paragraphs && on_chunk.is_none() {
+        let spans = crate::segment::paragraph_spans(&request.text);
+        if spans.len() > 1 {
+            return rewrite_paragraphs(detected, request, &spans, config).await;
+        }
     }
+    rewrite_single(detected, request, on_chunk, config).await
+}
 
-    // Non-printable chars (allow newlines, tabs, carriage returns)
-    if trimmed.chars().any(|c| c.is_control() && c != '\n' && c != '\r' && c != '\t') {
-        return Err("LLM response contains non-printable characters. Try again.".to_string());
-    }
+/// Rewrite each paragraph separately and rejoin them with the original breaks.
+/// Paragraphs run concurrently, bounded by the shared rewrite permits. Each one
+/// carries its share of the constraints (see paragraph_constraints) and gets its
+/// own retry; the joined text is checked against the full constraints at the end.
+async fn rewrite_paragraphs(
+    detected: &Detected,
+    request: &RewriteRequest,
+    spans: &[std::ops::Range<usize>],
+    config: &Config,
+) -> Result<RewriteResult, Box<dyn std::error::Error + Send + Sync>> {
+    let text = request.text.as_str();
+    let want_marked = wants_marked(&request.output_format)?;
+    validate_replace_span(request)?;
 
-    Ok(())
-}
+    let requests: Vec<RewriteRequest> = spans
+        .iter()
+        .zip(paragraph_constraints(request, spans))
+        .map(|(span, (must_keep, max_chars))| RewriteRequest {
+            text: text[span.clone()].to_string(),
+            mode: request.mode.clone(),
+            must_keep,
+            max_chars,
+            annotate: request.annotate,
+            include_raw: request.include_raw,
+            seed: request.seed,
+            session_id: request.session_id.clone(),
+            ..RewriteRequest::default()
+        })
+        .collect();
+    let results = futures_util::future::try_join_all(
+        requests.iter().map(|paragraph| rewrite_single(detected, paragraph, None, config)),
+    )
+    .await?;
 
-pub(crate) fn parse_response(full: &str) -> (String, String) {
+    // Keep whatever surrounded each paragraph: leading indent, blank lines, trailing newline
+    let mut rewritten = text[..spans[0].start].to_string();
+    let mut annotations = Vec::new();
+    for (i, result) in results.iter().enumerate() {
+        let offset = rewritten.chars().count();
+        annotations.extend(result.annotations.iter().map(|a| Annotation { start: a.start + offset, end: a.end + offset, ..a.clone() }));
+        rewritten.push_str(&result.rewritten);
+        let gap_end = spans.get(i + 1).map_or(text.len(), |next| next.start);
+        rewritten.push_str(&text[spans[i].end..gap_end]);
+    }
+    let explanation = results
+        .iter()
+        .map(|r| r.explanation.trim())
+        .filter(|e| !e.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let constrained = !request.must_keep.is_empty() || request.max_chars.is_some();
+    let similarity = fidelity(text, &rewritten);
+    let raw = request.include_raw.then(|| {
+        results.iter().filter_map(|r| r.raw.as_deref()).collect::<Vec<_>>().join("\n\n")
+    });
+
+    Ok(RewriteResult {
+        marked: want_marked.then(|| render_markdown_diff(text, &rewritten)),
+        constraints: constrained.then(|| ConstraintReport {
+            retried: results.iter().any(|r| r.constraints.as_ref().is_some_and(|c| c.retried)),
+            ..check_constraints(&rewritten, &request.must_keep, request.max_chars)
+        }),
+        cached: results.iter().all(|r| r.cached),
+        replace_start: request.replace_start,
+        replace_end: request.replace_end,
+        rewritten,
+        explanation_points: explanation_points(&explanation),
+        explanation,
+        annotations,
+        raw,
+        model: detected.model.clone(),
+        fidelity: similarity,
+        low_fidelity: similarity < config.min_fidelity,
+    })
+}
+
+/// Split a request's constraints across its paragraphs. A must_keep phrase goes to
+/// every paragraph that contains it, or to the first when none does. max_chars,
+/// less the breaks kept between paragraphs, is shared in proportion to length.
+fn paragraph_constraints(request: &RewriteRequest, spans: &[std::ops::Range<usize>]) -> Vec<(Vec<String>, Option<usize>)> {
+    let text = request.text.as_str();
+    let paragraphs: Vec<String> = spans.iter().map(|span| text[span.clone()].to_lowercase()).collect();
+    let mut must_keep = vec![Vec::new(); spans.len()];
+    for keep in request.must_keep.iter().filter(|k| !k.trim().is_empty()) {
+        let needle = keep.trim().to_lowercase();
+        let holders: Vec<usize> = (0..spans.len()).filter(|&i| paragraphs[i].contains(&needle)).collect();
+        let holders = if holders.is_empty() { vec![0] } else { holders };
+        for i in holders {
+            must_keep[i].push(keep.clone());
+        }
+    }
+
+    let lengths: Vec<usize> = spans.iter().map(|span| text[span.clone()].chars().count()).collect();
+    let total: usize = lengths.iter().sum();
+    let budget = request.max_chars.map(|max| max.saturating_sub(text.chars().count() - total));
+    must_keep
+        .into_iter()
+        .zip(&lengths)
+        .map(|(keep, &len)| (keep, budget.map(|budget| (budget * len / total.max(1)).max(1))))
+        .collect()
+}
+
+/// One rewrite request, one model call (plus at most one constraint retry)
+async fn rewrite_single(
+    detected: &Detected,
+    request: &RewriteRequest,
+    on_chunk: Option<&OnChunk>,
+    config: &Config,
+) -> Result<RewriteResult, Box<dyn std::error::Error + Send + Sync>> {
+    reject_blank(request)?;
+    let text = request.text.as_str();
+    let mode = request.mode.as_str();
+    let base_url = detected.base_url.as_str();
+    let model = detected.model.as_str();
+
+    let want_marked = wants_marked(&request.output_format)?;
+
+    validate_replace_span(request)?;
+
+    // Refuse oversized input before sending it — small models silently truncate
+    check_input_budget(text, model, config)?;
+
+    // Streaming results are never cached — the UI expects progressive events
+    let use_stream = on_chunk.is_some();
+    let constrained = !request.must_keep.is_empty() || request.max_chars.is_some();
+    let cache_key = (config.rewrite_cache && !use_stream && !constrained).then(|| CacheKey {
+        text: text.to_string(),
+        mode: mode.to_string(),
+        model: model.to_string(),
+        temperature_bits: config.temperature.to_bits(),
+        output_format: request.output_format.clone(),
+        annotate: request.annotate,
+        seed: request.seed,
+    });
+    if let Some(hit) = cache_key.as_ref().and_then(cache_get) {
+        return Ok(RewriteResult {
+            cached: true,
+            replace_start: request.replace_start,
+            replace_end: request.replace_end,
+            raw: hit.raw.filter(|_| request.include_raw),
+            ..hit
+        });
+    }
+
+    // Wait for a free slot so simultaneous rewrites don't all hit the model at once
+    let _permit = rewrite_permits(config.max_concurrent_rewrites).acquire_owned().await?;
+
+    // Assign a unique generation ID to this rewrite call
+    let my_generation = REWRITE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    // Streamed rewrites log start and end separately — never per chunk
+    let started = std::time::Instant::now();
+    if use_stream {
+        crate::audit::log_event("rewrite_start", serde_json::json!({
+            "mode": mode,
+            "text_length": text.len(),
+            "model": model,
+        }));
+    }
+
+    let mut messages = request_messages(request);
+
+    let sampling = Sampling { temperature: config.temperature, seed: request.seed };
+    let mut stats = StreamStats::default();
+    let outcome = request_completion(detected, messages.clone(), on_chunk, my_generation, sampling, &mut stats, started).await;
+
+    if use_stream {
+        crate::audit::log_event("rewrite_end", serde_json::json!({
+            "mode": mode,
+            "success": outcome.is_ok(),
+            "chunk_count": stats.chunks,
+            "time_to_first_token_ms": stats.first_token_ms,
+            "duration_ms": started.elapsed().as_millis() as u64,
+        }));
+    }
+    let mut full = outcome?;
+    let (mut rewritten, mut explanation) = finish_response(&full, text, mode, config)?;
+
+    let mut constraints = constrained.then(|| check_constraints(&rewritten, &request.must_keep, request.max_chars));
+    // One retry for a broken constraint, showing the model what it missed.
+    // Streamed rewrites are already on screen, so they aren't redone.
+    if let Some(report) = constraints.as_ref().filter(|r| !r.satisfied && !use_stream) {
+        messages.push(ChatMessage { role: "assistant".to_string(), content: full.clone() });
+        messages.push(ChatMessage { role: "user".to_string(), content: constraint_retry_prompt(report) });
+        full = request_completion(detected, messages, None, my_generation, sampling, &mut stats, started).await?;
+        (rewritten, explanation) = finish_response(&full, text, mode, config)?;
+        let mut report = check_constraints(&rewritten, &request.must_keep, request.max_chars);
+        report.retried = true;
+        constraints = Some(report);
+    }
+
+    let marked = want_marked.then(|| render_markdown_diff(text, &rewritten));
+    let annotations = if request.annotate { annotate(text, &rewritten, &explanation) } else { Vec::new() };
+    let similarity = fidelity(text, &rewritten);
+
+    // The cache keeps the raw reply so a later include_raw hit can still return it
+    let mut result = RewriteResult {
+        rewritten,
+        explanation_points: explanation_points(&explanation),
+        explanation,
+        marked,
+        cached: false,
+        replace_start: request.replace_start,
+        replace_end: request.replace_end,
+        constraints,
+        annotations,
+        raw: Some(full),
+        model: model.to_string(),
+        fidelity: similarity,
+        low_fidelity: similarity < config.min_fidelity,
+    };
+    if let Some(key) = cache_key {
+        cache_put(key, result.clone());
+    }
+    if !request.include_raw {
+        result.raw = None;
+    }
+
+    Ok(result)
+}
+
+/// Everything sent for a rewrite request: system prompt, few-shot examples, and
+/// the prompt with any constraint or annotation instructions in front
+fn request_messages(request: &RewriteRequest) -> Vec<ChatMessage> {
+    let mut messages = build_messages(&request.text, &request.mode);
+    let constrained = !request.must_keep.is_empty() || request.max_chars.is_some();
+    if constrained {
+        if let Some(last) = messages.last_mut() {
+            last.content = format!("{}{}", constraint_instructions(&request.must_keep, request.max_chars), last.content);
+        }
+    }
+    if request.annotate {
+        if let Some(last) = messages.last_mut() {
+            last.content = format!("{}{}", ANNOTATE_INSTRUCTION, last.content);
+        }
+    }
+    messages
+}
+
+/// Price a rewrite before sending it, on the model the rewrite would use first
+pub async fn estimate_cost(request: &RewriteRequest) -> Result<CostEstimate, Box<dyn std::error::Error + Send + Sync>> {
+    let config = config::load();
+    let detected = detect_provider().await?;
+    let model = model_chain(&detected, &config, &request.mode).into_iter().next().unwrap_or(detected.model);
+    Ok(estimate_cost_for(request, &model, &config))
+}
+
+/// Prompt tokens from the messages the rewrite would send, reply tokens from
+/// config.completion_ratio, priced from config.model_prices. Paragraph-split
+/// requests are priced as one prompt, slightly undercounting repeated examples.
+fn estimate_cost_for(request: &RewriteRequest, model: &str, config: &Config) -> CostEstimate {
+    let prompt = request_messages(request)
+        .iter()
+        .map(|m| m.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let input_tokens = estimate_tokens(&prompt);
+    let output_tokens = (input_tokens as f64 * config.completion_ratio as f64).ceil() as usize;
+    let cost_usd = config.model_prices.get(model).map(|price| {
+        (input_tokens as f64 * price.input_per_million + output_tokens as f64 * price.output_per_million) / 1_000_000.0
+    });
+    CostEstimate { model: model.to_string(), input_tokens, output_tokens, cost_usd }
+}
+
+/// Validate a raw reply and split it into rewrite and explanation
+fn finish_response(
+    full: &str,
+    text: &str,
+    mode: &str,
+    config: &Config,
+) -> Result<(String, String), Box<dyn std::error::Error + Send + Sync>> {
+    validate_response(full, text)?;
+    let (mut rewritten, explanation) = parse_response_for_mode(full, mode);
+    if config.strip_formatting {
+        rewritten = strip_formatting(&rewritten);
+    }
+    Ok((rewritten, explanation))
+}
+
+/// Prompt preamble asking the model to respect must_keep and max_chars
+fn constraint_instructions(must_keep: &[String], max_chars: Option<usize>) -> String {
+    let mut lines = Vec::new();
+    if !must_keep.is_empty() {
+        let quoted: Vec<String> = must_keep.iter().map(|k| format!("\"{}\"", k)).collect();
+        lines.push(format!("- Keep these words exactly as written: {}", quoted.join(", ")));
+    }
+    if let Some(max) = max_chars {
+        lines.push(format!("- The rewritten text must be at most {} characters long", max));
+    }
+    format!("Constraints for the rewritten text:\n{}\n\n", lines.join("\n"))
+}
+
+/// Jaccard similarity of the two texts' content words (lowercased, stopwords
+/// dropped). Two texts with no content words at all count as identical.
+pub(crate) fn fidelity(original: &str, rewritten: &str) -> f32 {
+    let stopwords = crate::repetition::bundled_stopwords();
+    let content = |text: &str| -> std::collections::HashSet<String> {
+        crate::segment::words(text)
+            .into_iter()
+            .map(str::to_lowercase)
+            .filter(|w| !stopwords.contains(w))
+            .collect()
+    };
+    let (before, after) = (content(original), content(rewritten));
+    let union = before.union(&after).count();
+    if union == 0 {
+        return 1.0;
+    }
+    before.intersection(&after).count() as f32 / union as f32
+}
+
+/// Keywords are matched case-insensitively; length counts chars, not bytes
+pub(crate) fn check_constraints(rewrite: &str, must_keep: &[String], max_chars: Option<usize>) -> ConstraintReport {
+    let lowered = rewrite.to_lowercase();
+    let missing: Vec<String> = must_keep
+        .iter()
+        .filter(|k| !k.trim().is_empty() && !lowered.contains(&k.trim().to_lowercase()))
+        .cloned()
+        .collect();
+    let chars = rewrite.chars().count();
+    ConstraintReport {
+        satisfied: missing.is_empty() && max_chars.is_none_or(|max| chars <= max),
+        missing,
+        chars,
+        max_chars,
+        retried: false,
+    }
+}
+
+fn constraint_retry_prompt(report: &ConstraintReport) -> String {
+    let mut problems = Vec::new();
+    if !report.missing.is_empty() {
+        problems.push(format!("it dropped {}", report.missing.iter().map(|k| format!("\"{}\"", k)).collect::<Vec<_>>().join(", ")));
+    }
+    if let Some(max) = report.max_chars.filter(|max| report.chars > *max) {
+        problems.push(format!("it is {} characters long, over the {} limit", report.chars, max));
+    }
+    format!("That rewrite broke the constraints: {}. Rewrite the original text again and follow every constraint. Use the same format as before.", problems.join(" and "))
+}
+
+/// Prepended to the request when annotations are wanted, so points can be matched to changes
+const ANNOTATE_INSTRUCTION: &str = "Number each point of your explanation (1., 2., ...) and put the exact words you changed in double quotes.\n\n";
+
+/// Numbered points of an explanation ("1. ...", "2) ..."), with their numbers.
+/// Lines that don't start a point continue the one before.
+fn numbered_points(explanation: &str) -> Vec<(usize, String)> {
+    let mut points: Vec<(usize, String)> = Vec::new();
+    for line in explanation.lines().map(str::trim) {
+        let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let rest = &line[digits..];
+        match (line[..digits].parse::<usize>(), rest.strip_prefix(['.', ')'])) {
+            (Ok(number), Some(body)) if body.starts_with(' ') => points.push((number, body.trim().to_string())),
+            _ if !line.is_empty() => {
+                if let Some((_, body)) = points.last_mut() {
+                    body.push(' ');
+                    body.push_str(line);
+                }
+            }
+            _ => {}
+        }
+    }
+    points
+}
+
+/// Bulleted points of an explanation ("- ...", "* ...", "• ..."), joined with
+/// their continuation lines like numbered_points
+fn bullet_points(explanation: &str) -> Vec<String> {
+    let mut points: Vec<String> = Vec::new();
+    for line in explanation.lines().map(str::trim) {
+        match line.strip_prefix(['-', '*', '•']) {
+            Some(body) if body.starts_with(' ') => points.push(body.trim().to_string()),
+            _ if !line.is_empty() => {
+                if let Some(body) = points.last_mut() {
+                    body.push(' ');
+                    body.push_str(line);
+                }
+            }
+            _ => {}
+        }
+    }
+    points
+}
+
+/// An explanation as separate points: its numbered list, else its bullets,
+/// else one point per sentence
+pub(crate) fn explanation_points(explanation: &str) -> Vec<String> {
+    let numbered = numbered_points(explanation);
+    if !numbered.is_empty() {
+        return numbered.into_iter().map(|(_, point)| point).collect();
+    }
+    let bulleted = bullet_points(explanation);
+    if !bulleted.is_empty() {
+        return bulleted;
+    }
+    crate::segment::sentence_spans(explanation)
+        .into_iter()
+        .map(|span| explanation[span].trim().to_string())
+        .filter(|sentence| !sentence.is_empty())
+        .collect()
+}
+
+/// Text inside "straight" or “curly” double quotes
+fn quoted(point: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = point;
+    while let Some(open) = rest.find(['"', '“']) {
+        let after = &rest[open + rest[open..].chars().next().map_or(1, char::len_utf8)..];
+        let Some(close) = after.find(['"', '”']) else { break };
+        found.push(&after[..close]);
+        rest = &after[close + after[close..].chars().next().map_or(1, char::len_utf8)..];
+    }
+    found
+}
+
+/// Lowercase words with surrounding punctuation stripped, for loose comparison
+fn loose_words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+fn contains_words(haystack: &[String], needle: &[String]) -> bool {
+    !needle.is_empty() && haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+/// Tie each numbered explanation point to the one change its quotes describe.
+/// Points that quote no change, or several, are left out rather than guessed.
+pub(crate) fn annotate(original: &str, rewritten: &str, explanation: &str) -> Vec<Annotation> {
+    // Diffing rewritten → original puts each change's span in the rewritten text
+    let changes = crate::t5::compute_diff(rewritten, original);
+    let sides: Vec<[Vec<String>; 2]> = changes
+        .iter()
+        .map(|c| [loose_words(&c.original), loose_words(&c.replacement)])
+        .collect();
+
+    let mut annotations = Vec::new();
+    for (point, note) in numbered_points(explanation) {
+        let quotes: Vec<Vec<String>> = quoted(&note).into_iter().map(loose_words).filter(|q| !q.is_empty()).collect();
+        let matching: Vec<usize> = (0..changes.len())
+            .filter(|&i| {
+                quotes.iter().any(|q| sides[i].iter().any(|side| contains_words(side, q) || contains_words(q, side)))
+            })
+            .collect();
+        if let [only] = matching[..] {
+            annotations.push(Annotation { start: changes[only].start, end: changes[only].end, point, note });
+        }
+    }
+    annotations
+}
+
+/// Bundled few-shot examples, keyed by mode
+const FEW_SHOT_DATA: &str = include_str!("../data/few_shot.toml");
+
+/// One demonstration rewrite: the input text and the exact reply we want back
+#[derive(Deserialize)]
+struct FewShotExample {
+    text: String,
+    output: String,
+}
+
+fn few_shot_examples(mode: &str) -> &'static [FewShotExample] {
+    static EXAMPLES: OnceLock<HashMap<String, Vec<FewShotExample>>> = OnceLock::new();
+    EXAMPLES
+        .get_or_init(|| toml::from_str(FEW_SHOT_DATA).unwrap_or_default())
+        .get(mode)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+/// System prompt, then a user/assistant pair per few-shot example for the mode,
+/// then the real request. Examples use the same prompt template as the request
+/// so small models see the exact output format, delimiter included.
+fn build_messages(text: &str, mode: &str) -> Vec<ChatMessage> {
+    // One snapshot for every prompt so a mid-request reload can't mix templates
+    let templates = crate::modes::snapshot();
+    let mut messages = vec![ChatMessage {
+        role: "system".to_string(),
+        content: SYSTEM_PROMPT.to_string(),
+    }];
+    for example in few_shot_examples(mode) {
+        messages.push(ChatMessage {
+            role: "user".to_string(),
+            content: build_prompt_with(&templates, &example.text, mode),
+        });
+        messages.push(ChatMessage {
+            role: "assistant".to_string(),
+            content: example.output.clone(),
+        });
+    }
+    messages.push(ChatMessage {
+        role: "user".to_string(),
+        content: build_prompt_with(&templates, text, mode),
+    });
+    messages
+}
+
+/// Fixed benchmark prompt — long enough to produce roughly 100 tokens on any model
+const BENCHMARK_PROMPT: &str = "Write one paragraph of about 80 words describing a quiet morning in a small coastal town. Output only the paragraph.";
+
+/// Generation speed over the whole request, in tokens per second
+pub(crate) fn tokens_per_sec(tokens: usize, total_ms: u64) -> f64 {
+    if total_ms == 0 {
+        return 0.0;
+    }
+    tokens as f64 * 1000.0 / total_ms as f64
+}
+
+/// Time a fixed streaming completion against the detected provider
+pub async fn benchmark() -> Result<BenchmarkResult, Box<dyn std::error::Error + Send + Sync>> {
+    let detected = detect_provider().await?;
+    benchmark_with(&detected, &config::load()).await
+}
+
+async fn benchmark_with(
+    detected: &Detected,
+    config: &Config,
+) -> Result<BenchmarkResult, Box<dyn std::error::Error + Send + Sync>> {
+    use futures_util::StreamExt;
+
+    let messages = vec![ChatMessage {
+        role: "user".to_string(),
+        content: BENCHMARK_PROMPT.to_string(),
+    }];
+    let started = std::time::Instant::now();
+    let resp = detected
+        .client
+        .post(format!("{}/v1/chat/completions", detected.base_url))
+        .json(&ChatRequest {
+            model: detected.model.clone(),
+            messages,
+            stream: true,
+            temperature: config.temperature,
+            seed: None,
+            stream_options: Some(StreamOptions { include_usage: true }),
+        })
+        .timeout(std::time::Duration::from_secs(180))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let mut deltas = std::pin::pin!(stream::json_values::<StreamChunk, _, _, _>(resp.bytes_stream(), stream::Format::Sse));
+    let mut generated = String::new();
+    let mut first_token_ms = None;
+    let mut usage_tokens = None;
+
+    while let Some(chunk) = deltas.next().await {
+        let chunk = chunk?;
+        if let Some(usage) = chunk.usage {
+            usage_tokens = Some(usage.completion_tokens);
+        }
+        if let Some(content) = chunk.choices.first().and_then(|c| c.delta.content.as_ref()) {
+            if first_token_ms.is_none() && !content.is_empty() {
+                first_token_ms = Some(started.elapsed().as_millis() as u64);
+            }
+            generated.push_str(content);
+        }
+    }
+
+    let total_ms = started.elapsed().as_millis() as u64;
+    let (tokens_generated, tokens_estimated) = match usage_tokens {
+        Some(tokens) => (tokens, false),
+        None => (estimate_tokens(&generated), true),
+    };
+
+    Ok(BenchmarkResult {
+        provider: detected.provider.label().to_string(),
+        model: detected.model.clone(),
+        tokens_generated,
+        tokens_estimated,
+        total_ms,
+        time_to_first_token_ms: first_token_ms,
+        tokens_per_sec: tokens_per_sec(tokens_generated, total_ms),
+    })
+}
+
+/// Send the rewrite prompt and collect the reply, streaming it through `on_chunk` when set
+async fn request_completion(
+    detected: &Detected,
+    messages: Vec<ChatMessage>,
+    on_chunk: Option<&OnChunk>,
+    my_generation: u64,
+    sampling: Sampling,
+    stats: &mut StreamStats,
+    started: std::time::Instant,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let base_url = detected.base_url.as_str();
+    let model = detected.model.as_str();
+    let api_url = format!("{}/v1/chat/completions", base_url);
+
+    let client = &detected.client;
+    let sent = std::time::Instant::now();
+    let resp = client
+        .post(&api_url)
+        .json(&ChatRequest {
+            model: model.to_string(),
+            messages: messages.clone(),
+            stream: on_chunk.is_some(),
+            temperature: sampling.temperature,
+            seed: sampling.seed,
+            stream_options: None,
+        })
+        .timeout(std::time::Duration::from_secs(180))
+        .send()
+        .await
+        .map_err(|e| dropped_or(e, sent))?;
+
+    if resp.status() == reqwest::StatusCode::NOT_FOUND && matches!(detected.provider, Provider::Ollama) {
+        // Some Ollama builds don't serve the OpenAI-compatible path — use the native API.
+        // Not streamed; the finished text goes out as a single chunk.
+        let content = ollama_native_chat(client, base_url, model, messages, sampling).await?;
+        if let Some(emit) = on_chunk {
+            stats.chunks = 1;
+            stats.first_token_ms = Some(started.elapsed().as_millis() as u64);
+            emit(&content);
+        }
+        return Ok(content);
+    }
+    let resp = reject_unavailable_model(resp, model).await?;
+
+    let Some(emit) = on_chunk else {
+        // Non-streaming
+        let chat_resp = resp.json::<ChatResponse>().await.map_err(|e| dropped_or(e, sent))?;
+        return Ok(chat_resp
+            .choices
+            .first()
+            .map(|c| c.message.content.trim().to_string())
+            .unwrap_or_default());
+    };
+
+    // Stream tokens and report progress to the caller
+    use futures_util::StreamExt;
+    let mut accumulated = String::new();
+    let mut deltas = std::pin::pin!(stream::json_values::<StreamChunk, _, _, _>(resp.bytes_stream(), stream::Format::Sse));
+
+    while let Some(delta) = deltas.next().await {
+        if CANCEL_GENERATION.load(Ordering::SeqCst) == my_generation {
+            return Err("Rewrite cancelled by user".into());
+        }
+
+        let chunk = match delta {
+            Ok(chunk) => chunk,
+            Err(stream::StreamError::Transport(e)) => return Err(dropped_or(e, sent)),
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(content) = chunk.choices.first().and_then(|c| c.delta.content.as_ref()) {
+            stats.chunks += 1;
+            stats.first_token_ms.get_or_insert(started.elapsed().as_millis() as u64);
+            accumulated.push_str(content);
+            emit(&accumulated);
+        }
+    }
+
+    Ok(accumulated.trim().to_string())
+}
+
+/// Single non-streaming chat completion, with the same Ollama /api/chat fallback as rewrite
+async fn chat_once(
+    detected: &Detected,
+    messages: Vec<ChatMessage>,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let client = &detected.client;
+    let resp = client
+        .post(format!("{}/v1/chat/completions", detected.base_url))
+        .json(&ChatRequest {
+            model: detected.model.clone(),
+            messages: messages.clone(),
+            stream: false,
+            temperature: config.temperature,
+            seed: None,
+            stream_options: None,
+        })
+        .timeout(std::time::Duration::from_secs(180))
+        .send()
+        .await?;
+
+    if resp.status() == reqwest::StatusCode::NOT_FOUND && matches!(detected.provider, Provider::Ollama) {
+        return ollama_native_chat(client, &detected.base_url, &detected.model, messages, Sampling::unseeded(config)).await;
+    }
+
+    let chat_resp = resp.error_for_status()?.json::<ChatResponse>().await?;
+    Ok(chat_resp
+        .choices
+        .first()
+        .map(|c| c.message.content.trim().to_string())
+        .unwrap_or_default())
+}
+
+/// Focused teaching prompt for one grammar finding, quoting only its sentence
+pub(crate) fn build_issue_prompt(text: &str, issue: &GrammarIssue) -> String {
+    let byte_start = crate::char_to_byte(text, issue.start);
+    let byte_end = crate::char_to_byte(text, issue.end);
+    let flagged = text.get(byte_start..byte_end).unwrap_or("");
+    let sentence = crate::segment::sentence_spans(text)
+        .into_iter()
+        .find(|span| span.start <= byte_start && byte_start < span.end)
+        .map(|span| &text[span])
+        .unwrap_or(text);
+    let suggestion = issue
+        .suggestions
+        .first()
+        .map(|s| format!(" The suggested fix is '{}'.", s))
+        .unwrap_or_default();
+
+    format!(
+        "In this sentence, explain why '{}' is flagged: {}{}\n\nIn 2-3 sentences, explain the rule and why it matters so the writer learns it. Do not rewrite the sentence.\n\nSentence: {}",
+        flagged, issue.message, suggestion, sentence.trim()
+    )
+}
+
+/// Explain one grammar finding with the local model. Falls back to the
+/// linter's own message when no model is available or the call fails.
+pub async fn explain_issue(text: &str, issue: &GrammarIssue) -> String {
+    let Ok(detected) = detect_provider().await else {
+        return issue.message.clone();
+    };
+    let messages = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: SYSTEM_PROMPT.to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: build_issue_prompt(text, issue),
+        },
+    ];
+
+    match chat_once(&detected, messages, &config::load()).await {
+        Ok(explanation) if !explanation.is_empty() => explanation,
+        _ => issue.message.clone(),
+    }
+}
+
+/// Non-streaming chat via Ollama's native /api/chat endpoint
+async fn ollama_native_chat(
+    client: &reqwest::Client,
+    base_url: &str,
+    model: &str,
+    messages: Vec<ChatMessage>,
+    sampling: Sampling,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let resp = client
+        .post(format!("{}/api/chat", base_url))
+        .json(&OllamaChatRequest {
+            model: model.to_string(),
+            messages,
+            stream: false,
+            options: OllamaOptions { temperature: sampling.temperature, seed: sampling.seed },
+        })
+        .timeout(std::time::Duration::from_secs(180))
+        .send()
+        .await?;
+    let resp = reject_unavailable_model(resp, model).await?.error_for_status()?;
+
+    let chat_resp = resp.json::<OllamaChatResponse>().await?;
+    Ok(chat_resp.message.content.trim().to_string())
+}
+
+/// Render a word-level diff as Markdown tracked changes: ~~deleted~~ **inserted**.
+/// Unchanged text, including original whitespace, is copied through as-is.
+pub(crate) fn render_markdown_diff(original: &str, rewritten: &str) -> String {
+    let chars: Vec<char> = original.chars().collect();
+    let mut out = String::new();
+    let mut pos = 0;
+
+    for change in crate::t5::compute_diff(original, rewritten) {
+        if change.start < pos || change.end > chars.len() {
+            continue;
+        }
+        out.extend(&chars[pos..change.start]);
+
+        let mut parts = Vec::new();
+        if !change.original.is_empty() {
+            parts.push(format!("~~{}~~", change.original));
+        }
+        if !change.replacement.is_empty() {
+            parts.push(format!("**{}**", change.replacement));
+        }
+        let rendered = parts.join(" ");
+
+        if change.original.is_empty() {
+            // Pure insertion: sits before the next word, or after the last one
+            let before_word = chars.get(change.start).is_some_and(|c| !c.is_whitespace());
+            if before_word {
+                out.push_str(&rendered);
+                out.push(' ');
+            } else {
+                out.push(' ');
+                out.push_str(&rendered);
+            }
+        } else {
+            out.push_str(&rendered);
+        }
+        pos = change.end;
+    }
+
+    out.extend(&chars[pos..]);
+    out
+}
+
+/// The replace span must be given as a pair and cover exactly the input text
+/// (char offsets, as the editor sees them).
+pub(crate) fn validate_replace_span(request: &RewriteRequest) -> Result<(), String> {
+    match (request.replace_start, request.replace_end) {
+        (None, None) => Ok(()),
+        (Some(start), Some(end)) => {
+            let text_chars = request.text.chars().count();
+            if start > end {
+                Err(format!("Invalid replace span: start {} is after end {}", start, end))
+            } else if end - start != text_chars {
+                Err(format!(
+                    "Replace span {}..{} covers {} chars but the text has {}",
+                    start, end, end - start, text_chars
+                ))
+            } else {
+                Ok(())
+            }
+        }
+        _ => Err("replace_start and replace_end must be provided together".to_string()),
+    }
+}
+
+/// Estimate token count from word count (words × 1.3, rounded up)
+pub(crate) fn estimate_tokens(text: &str) -> usize {
+    let words = text.split_whitespace().count();
+    (words * TOKENS_PER_WORD_X10).div_ceil(10)
+}
+
+/// Reject input whose estimated token count exceeds the model's configured limit
+pub(crate) fn check_input_budget(text: &str, model: &str, config: &Config) -> Result<usize, String> {
+    let estimated = estimate_tokens(text);
+    let limit = config.max_input_tokens_for(model);
+    if estimated > limit {
+        return Err(format!(
+            "Text is too long for {} (~{} tokens, limit {}). Select a smaller portion and try again.",
+            model, estimated, limit
+        ));
+    }
+    Ok(estimated)
+}
+
+/// Bundled "model prefix: context window" table
+const CONTEXT_WINDOWS_DATA: &str = include_str!("../data/context_windows.txt");
+
+/// Window assumed for models missing from the table — small on purpose, so an
+/// unknown model errs toward a warning rather than silent truncation
+const DEFAULT_CONTEXT_WINDOW: usize = 2048;
+
+fn context_windows() -> &'static Vec<(String, usize)> {
+    static TABLE: OnceLock<Vec<(String, usize)>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        CONTEXT_WINDOWS_DATA
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(|l| {
+                // Split on the last colon — model ids like "qwen2.5:0.5b" contain one
+                let (prefix, tokens) = l.rsplit_once(':')?;
+                Some((prefix.trim().to_lowercase(), tokens.trim().parse().ok()?))
+            })
+            .collect()
+    })
+}
+
+/// Known context window for a model, by longest matching prefix
+pub(crate) fn context_window(model: &str) -> Option<usize> {
+    let model = model.trim().to_lowercase();
+    context_windows()
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, tokens)| *tokens)
+}
+
+/// Estimate whether a rewrite of `text` fits the model's context. The request
+/// needs room for the prompt scaffolding, the text, and a reply of similar length.
+pub fn context_check(text: &str, model: &str) -> ContextInfo {
+    let estimated_tokens = estimate_tokens(text);
+    let overhead = estimate_tokens(SYSTEM_PROMPT) + estimate_tokens(&build_prompt("", "clarity"));
+    let required_tokens = overhead + estimated_tokens * 2;
+    let known = context_window(model);
+    let context_window = known.unwrap_or(DEFAULT_CONTEXT_WINDOW);
+
+    ContextInfo {
+        estimated_tokens,
+        required_tokens,
+        context_window,
+        known_model: known.is_some(),
+        fits: required_tokens <= context_window,
+    }
+}
+
+/// Build the prompts a rewrite would send, without generating anything. The
+/// limit is that of the model the rewrite would start with; when no server
+/// answers, the mode's pinned model, if any.
+pub async fn preview_prompt(text: &str, mode: &str) -> PromptPreview {
+    let config = config::load();
+    let model = match detect_provider().await {
+        Ok(detected) => model_chain(&detected, &config, mode).into_iter().next(),
+        Err(_) => config.mode_models.get(mode).filter(|m| !m.trim().is_empty()).cloned(),
+    };
+    preview_prompt_for(text, mode, model, &config)
+}
+
+fn preview_prompt_for(text: &str, mode: &str, model: Option<String>, config: &Config) -> PromptPreview {
+    PromptPreview {
+        system_prompt: SYSTEM_PROMPT.to_string(),
+        user_prompt: build_prompt(text, mode),
+        few_shot_examples: few_shot_examples(mode).len(),
+        estimated_tokens: estimate_tokens(text),
+        max_input_tokens: model.as_deref().map_or(config.max_input_tokens, |m| config.max_input_tokens_for(m)),
+        model,
+    }
+}
+
+/// Validate LLM response for common failure modes
+pub(crate) fn validate_response(response: &str, original_text: &str) -> Result<(), String> {
+    let trimmed = response.trim();
+
+    // Empty response
+    if trimmed.is_empty() {
+        return Err("LLM returned an empty response. Try again or use a different model.".to_string());
+    }
+
+    // Repetition loops: any substring of 20+ chars repeated 3+ times consecutively
+    let bytes = trimmed.as_bytes();
+    for window_size in 20..=trimmed.len() / 3 {
+        let mut i = 0;
+        while i + window_size * 3 <= bytes.len() {
+            let pattern = &bytes[i..i + window_size];
+            if &bytes[i + window_size..i + window_size * 2] == pattern
+                && &bytes[i + window_size * 2..i + window_size * 3] == pattern
+            {
+                return Err("LLM response contains repetitive loops. Try again with a different model.".to_string());
+            }
+            i += 1;
+        }
+        // Only check a few window sizes to avoid O(n^3)
+        if window_size > 60 { break; }
+    }
+
+    // Length explosion: response > 5x input
+    if !original_text.is_empty() && trimmed.len() > original_text.len() * 5 {
+        return Err("LLM response is suspiciously long (>5x input). Discarding.".to_string());
+    }
+
+    // Non-printable chars (allow newlines, tabs, carriage returns)
+    if trimmed.chars().any(|c| c.is_control() && c != '\n' && c != '\r' && c != '\t') {
+        return Err("LLM response contains non-printable characters. Try again.".to_string());
+    }
+
+    Ok(())
+}
+
+/// Remove markdown a model wrapped around the whole rewrite: a surrounding code
+/// fence, a bold label like "**Rewrite:**", bold/italic markers enclosing the
+/// entire text, and a list marker on a single-line rewrite. Formatting inside
+/// the text is left alone — it may be the writer's own.
+pub(crate) fn strip_formatting(rewrite: &str) -> String {
+    let mut text = rewrite.trim();
+
+    // ```lang\n...\n``` around everything
+    if let Some(inner) = text.strip_prefix("```").and_then(|t| t.strip_suffix("```")) {
+        if let Some((_, body)) = inner.split_once('\n') {
+            text = body.trim();
+        }
+    }
+
+    // "**Rewritten text:** ..." — a bold label ending in a colon
+    for marker in ["**", "__"] {
+        if let Some(rest) = text.strip_prefix(marker) {
+            if let Some((label, after)) = rest.split_once(marker) {
+                if label.trim_end().ends_with(':') && !label.contains('\n') {
+                    text = after.trim();
+                }
+            }
+        }
+    }
+
+    // Emphasis wrapping the whole text, and nowhere else
+    for marker in ["**", "__", "*", "_"] {
+        if let Some(inner) = text.strip_prefix(marker).and_then(|t| t.strip_suffix(marker)) {
+            if !inner.is_empty() && !inner.contains(marker) {
+                text = inner.trim();
+                break;
+            }
+        }
+    }
+
+    // A lone bullet: "- The meeting ran long."
+    if !text.contains('\n') {
+        for marker in ["- ", "* ", "• "] {
+            if let Some(rest) = text.strip_prefix(marker) {
+                text = rest.trim();
+                break;
+            }
+        }
+    }
+
+    text.to_string()
+}
+
+pub(crate) fn parse_response(full: &str) -> (String, String) {
     // Try various delimiter patterns
     for delimiter in &["EXPLANATION:", "**Explanation:**", "**Why:**", "\nExplanation:", "\n\n**Changes"] {
         if let Some(idx) = full.find(delimiter) {
@@ -342,7 +1885,25 @@ pub(crate) fn parse_response(full: &str) -> (String, String) {
     (cleaned.to_string(), String::new())
 }
 
-fn build_prompt(text: &str, mode: &str) -> String {
+/// Proofread returns only corrected text, so none of it is treated as explanation
+pub(crate) fn parse_response_for_mode(full: &str, mode: &str) -> (String, String) {
+    if mode == "proofread" {
+        let cleaned = full.trim();
+        let cleaned = cleaned.strip_prefix("REWRITE:").unwrap_or(cleaned).trim();
+        return (cleaned.to_string(), String::new());
+    }
+    parse_response(full)
+}
+
+pub(crate) fn build_prompt(text: &str, mode: &str) -> String {
+    build_prompt_with(&crate::modes::snapshot(), text, mode)
+}
+
+/// Prompt for a mode: a modes.toml template if one is defined, else the built-in
+fn build_prompt_with(templates: &HashMap<String, String>, text: &str, mode: &str) -> String {
+    if let Some(template) = templates.get(mode) {
+        return crate::modes::render(template, text);
+    }
     match mode {
         "clarity" => format!(
             "Rewrite this text for maximum clarity. Keep the meaning identical.\n\nFirst, provide the rewritten text. Then write EXPLANATION: followed by what you changed and why the writer should care (teach them).\n\nText: {}", text
@@ -356,6 +1917,9 @@ fn build_prompt(text: &str, mode: &str) -> String {
         "casual" => format!(
             "Rewrite in a more casual, conversational tone.\n\nFirst, provide the rewritten text. Then write EXPLANATION: followed by what you changed to make it more natural.\n\nText: {}", text
         ),
+        "proofread" => format!(
+            "Proofread this text. Fix spelling, grammar, and punctuation errors only. Keep the original wording, tone, and structure — do not rephrase.\n\nReturn ONLY the corrected text. No explanation, no notes, no preamble.\n\nText: {}", text
+        ),
         "explain" => format!(
             "Analyze this text as a writing coach. Identify grammar issues, unclear phrasing, and style problems. For each issue, explain WHAT is wrong and WHY it matters — teach the writer, don't just flag.\n\nText: {}", text
         ),
@@ -365,12 +1929,114 @@ fn build_prompt(text: &str, mode: &str) -> String {
     }
 }
 
+/// A detected LM Studio server at a mock URL
+#[cfg(test)]
+fn mock_detected(url: &str) -> Detected {
+    Detected {
+        client: reqwest::Client::new(),
+        provider: Provider::LmStudio,
+        address: "127.0.0.1".to_string(),
+        base_url: url.to_string(),
+        model: "mock-model".to_string(),
+    }
+}
+
+/// Rewrite against a mock server, skipping provider detection
+#[cfg(test)]
+pub(crate) async fn rewrite_at(url: &str, request: &RewriteRequest) -> Result<RewriteResult, String> {
+    rewrite_with(&mock_detected(url), request, None, &Config::default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Severity;
+
+    // --- few-shot tests ---
+
+    #[test]
+    fn few_shot_pairs_precede_the_request() {
+        let messages = build_messages("Some text.", "clarity");
+        let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["system", "user", "assistant", "user"]);
+        assert!(messages.last().unwrap().content.ends_with("Text: Some text."));
+
+        // Modes without examples send just the system prompt and request
+        assert_eq!(build_messages("Some text.", "explain").len(), 2);
+    }
+
+    /// Parse success rate over the bundled examples: every demonstration must
+    /// parse into the rewrite/explanation split we ask the model to copy.
+    #[test]
+    fn few_shot_outputs_parse_cleanly() {
+        for mode in BUILTIN_MODES {
+            for example in few_shot_examples(mode) {
+                let (rewrite, explanation) = parse_response_for_mode(&example.output, mode);
+                assert!(!rewrite.is_empty(), "{} example has no rewrite", mode);
+                assert!(!rewrite.contains("EXPLANATION"), "{} example leaks the delimiter", mode);
+                assert_eq!(explanation.is_empty(), *mode == "proofread", "{} example explanation", mode);
+                assert!(estimate_tokens(&example.output) < 80, "{} example is too long", mode);
+            }
+        }
+        assert!(!few_shot_examples("clarity").is_empty());
+    }
 
     // --- parse_response tests ---
 
+    #[test]
+    fn strip_formatting_unwraps_code_fence() {
+        assert_eq!(strip_formatting("```text\nThe meeting ran long.\n```"), "The meeting ran long.");
+        assert_eq!(strip_formatting("```\nLine one.\nLine two.\n```"), "Line one.\nLine two.");
+    }
+
+    #[test]
+    fn strip_formatting_removes_bold_label_and_wrapper() {
+        assert_eq!(strip_formatting("**Rewritten text:** The meeting ran long."), "The meeting ran long.");
+        assert_eq!(strip_formatting("**The meeting ran long.**"), "The meeting ran long.");
+        assert_eq!(strip_formatting("- The meeting ran long."), "The meeting ran long.");
+    }
+
+    #[test]
+    fn strip_formatting_keeps_inner_formatting() {
+        let text = "Use **bold** sparingly.\n\n- first point\n- second point";
+        assert_eq!(strip_formatting(text), text);
+        assert_eq!(strip_formatting("**Key** terms and **more** terms"), "**Key** terms and **more** terms");
+    }
+
+    #[tokio::test]
+    async fn strip_formatting_applies_to_rewrite_only() {
+        let server = crate::test_support::chat_server("```\nThe meeting ran long.\n```\nEXPLANATION: Cut **very**.");
+        let request = RewriteRequest {
+            text: "The meeting ran very long.".to_string(),
+            mode: "concise".to_string(),
+            ..RewriteRequest::default()
+        };
+        let config = Config { strip_formatting: true, ..Config::default() };
+        let result = rewrite_with(&mock_detected(&server.url), &request, None, &config).await.unwrap();
+        assert_eq!(result.rewritten, "The meeting ran long.");
+        assert_eq!(result.explanation, "Cut **very**.");
+    }
+
+    #[test]
+    fn proofread_prompt_asks_for_text_only() {
+        let prompt = build_prompt("Their going home.", "proofread");
+        assert!(prompt.contains("Return ONLY the corrected text"));
+        assert!(!prompt.contains("EXPLANATION:"));
+        assert!(prompt.ends_with("Text: Their going home."));
+    }
+
+    #[test]
+    fn proofread_keeps_explanation_empty() {
+        let (rewrite, explanation) = parse_response_for_mode("They're going home.\n\nExplanation: fixed homophone", "proofread");
+        assert_eq!(rewrite, "They're going home.\n\nExplanation: fixed homophone");
+        assert_eq!(explanation, "");
+
+        let (rewrite, explanation) = parse_response_for_mode("Better.\nEXPLANATION: Clearer.", "clarity");
+        assert_eq!((rewrite.as_str(), explanation.as_str()), ("Better.", "Clearer."));
+    }
+
     #[test]
     fn parse_with_explanation_delimiter() {
         let (rewrite, explanation) = parse_response("Better text here.\nEXPLANATION: I made it clearer.");
@@ -399,6 +2065,646 @@ mod tests {
         assert!(explanation.is_empty());
     }
 
+    // --- markdown diff tests ---
+
+    #[test]
+    fn markdown_diff_strikes_removed_word() {
+        let marked = render_markdown_diff("I really like it", "I like it");
+        assert_eq!(marked, "I ~~really~~ like it");
+    }
+
+    #[test]
+    fn markdown_diff_marks_replacement_and_insertion() {
+        assert_eq!(render_markdown_diff("the cat sat", "the dog sat"), "the ~~cat~~ **dog** sat");
+        assert_eq!(render_markdown_diff("I went store", "I went to store"), "I went **to** store");
+    }
+
+    #[test]
+    fn seed_is_serialized_only_when_set() {
+        let request = |seed| ChatRequest {
+            model: "qwen2.5:3b".to_string(),
+            messages: vec![],
+            stream: false,
+            temperature: 0.0,
+            seed,
+            stream_options: None,
+        };
+        let unseeded = serde_json::to_value(request(None)).unwrap();
+        assert!(unseeded.get("seed").is_none());
+        let seeded = serde_json::to_value(request(Some(42))).unwrap();
+        assert_eq!(seeded["seed"], 42);
+
+        let options = |seed| serde_json::to_value(OllamaOptions { temperature: 0.0, seed }).unwrap();
+        assert!(options(None).get("seed").is_none());
+        assert_eq!(options(Some(42))["seed"], 42);
+    }
+
+    #[tokio::test]
+    async fn refused_model_falls_back_to_the_next() {
+        let server = crate::test_support::mock_server(|req| {
+            if req.body.contains(r#""model":"missing-model""#) {
+                (404, r#"{"error":"model not found"}"#.to_string())
+            } else {
+                (200, r#"{"choices":[{"message":{"content":"We use it.\nEXPLANATION: Shorter."}}]}"#.to_string())
+            }
+        });
+        let mut config = Config::default();
+        config.models.insert("lmstudio".to_string(), vec!["missing-model".to_string(), "backup-model".to_string()]);
+        let request = RewriteRequest { text: "We utilize it.".to_string(), mode: "clarity".to_string(), ..RewriteRequest::default() };
+
+        let result = rewrite_with(&mock_detected(&server.url), &request, None, &config).await.unwrap();
+        assert_eq!(result.model, "backup-model");
+        assert_eq!(result.rewritten, "We use it.");
+        assert_eq!(server.hits(), 2);
+    }
+
+    #[tokio::test]
+    async fn mode_model_is_used_for_its_mode() {
+        let server = crate::test_support::chat_server("Dear team, we use it.\nEXPLANATION: Formal tone.");
+        let mut config = Config::default();
+        config.mode_models.insert("formal".to_string(), "big-model".to_string());
+        let detected = mock_detected(&server.url);
+
+        let formal = RewriteRequest { text: "hey, we use it".to_string(), mode: "formal".to_string(), ..RewriteRequest::default() };
+        let result = rewrite_with(&detected, &formal, None, &config).await.unwrap();
+        assert_eq!(result.model, "big-model");
+        assert!(server.bodies()[0].contains(r#""model":"big-model""#));
+
+        let casual = RewriteRequest { mode: "casual".to_string(), ..formal };
+        let result = rewrite_with(&detected, &casual, None, &config).await.unwrap();
+        assert_eq!(result.model, "mock-model");
+    }
+
+    #[tokio::test]
+    async fn server_errors_do_not_try_other_models() {
+        let server = crate::test_support::mock_server(|_| (500, "internal error".to_string()));
+        let mut config = Config::default();
+        config.models.insert("lmstudio".to_string(), vec!["first".to_string(), "second".to_string()]);
+        let request = RewriteRequest { text: "We utilize it.".to_string(), mode: "clarity".to_string(), ..RewriteRequest::default() };
+
+        assert!(rewrite_with(&mock_detected(&server.url), &request, None, &config).await.is_err());
+        assert_eq!(server.hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn mid_response_disconnect_is_reported_as_dropped() {
+        let server = crate::test_support::truncating_server(r#"{"choices":[{"message":{"content":"We use"#);
+        let request = RewriteRequest { text: "We utilize it.".to_string(), mode: "clarity".to_string(), ..RewriteRequest::default() };
+
+        let err = rewrite_with(&mock_detected(&server.url), &request, None, &Config::default()).await.unwrap_err();
+        assert!(err.downcast_ref::<ConnectionDropped>().is_some(), "unexpected error: {}", err);
+        assert!(err.to_string().starts_with("connection dropped after 0 seconds"));
+        assert_eq!(server.hits(), 1);
+    }
+
+    // --- Ollama native API tests ---
+
+    #[test]
+    fn deserialize_native_chat_response() {
+        let sample = r#"{"model":"qwen2.5:3b","created_at":"2026-01-01T00:00:00Z","message":{"role":"assistant","content":"Hello there."},"done":true,"total_duration":123}"#;
+        let parsed: OllamaChatResponse = serde_json::from_str(sample).unwrap();
+        assert_eq!(parsed.message.content, "Hello there.");
+    }
+
+    #[tokio::test]
+    async fn ollama_falls_back_to_native_chat_on_404() {
+        let server = crate::test_support::mock_server(|req| {
+            if req.path == "/api/chat" {
+                (200, r#"{"message":{"role":"assistant","content":"Native reply.\nEXPLANATION: Used /api/chat."},"done":true}"#.to_string())
+            } else {
+                (404, "404 page not found".to_string())
+            }
+        });
+        let detected = Detected { provider: Provider::Ollama, ..mock_detected(&server.url) };
+        let request = RewriteRequest {
+            text: "Please use the native endpoint.".to_string(),
+            mode: "clarity".to_string(),
+            ..RewriteRequest::default()
+        };
+
+        let result = rewrite_with(&detected, &request, None, &Config::default()).await.unwrap();
+        assert_eq!(result.rewritten, "Native reply.");
+        assert_eq!(result.explanation, "Used /api/chat.");
+        assert_eq!(server.hits(), 2);
+    }
+
+    // --- explain_issue tests ---
+
+    #[test]
+    fn issue_prompt_quotes_span_and_sentence() {
+        let text = "I like cats. She dont like dogs.";
+        let issue = GrammarIssue {
+            start: 17,
+            end: 21,
+            message: "Missing apostrophe in contraction.".to_string(),
+            suggestions: vec!["don't".to_string()],
+            severity: Severity::Warning,
+            rule: "Grammar".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
+        };
+        let prompt = build_issue_prompt(text, &issue);
+        assert!(prompt.starts_with("In this sentence, explain why 'dont' is flagged: Missing apostrophe in contraction."));
+        assert!(prompt.contains("The suggested fix is 'don't'."));
+        assert!(prompt.ends_with("Sentence: She dont like dogs."));
+        assert!(!prompt.contains("I like cats"));
+    }
+
+    #[tokio::test]
+    async fn chat_once_returns_model_text() {
+        let server = crate::test_support::chat_server("  Because contractions need apostrophes.  ");
+        let messages = vec![ChatMessage { role: "user".to_string(), content: "Why?".to_string() }];
+        let reply = chat_once(&mock_detected(&server.url), messages, &Config::default()).await.unwrap();
+        assert_eq!(reply, "Because contractions need apostrophes.");
+    }
+
+    // --- benchmark tests ---
+
+    #[test]
+    fn tokens_per_sec_math() {
+        assert_eq!(tokens_per_sec(100, 2000), 50.0);
+        assert_eq!(tokens_per_sec(100, 0), 0.0);
+    }
+
+    #[tokio::test]
+    async fn pull_reports_streamed_progress() {
+        let server = crate::test_support::mock_server(|req| match req.path.as_str() {
+            "/api/tags" => (200, r#"{"models":[{"name":"llama3:latest"}]}"#.to_string()),
+            _ => (200, [
+                r#"{"status":"pulling manifest"}"#,
+                r#"{"status":"downloading","digest":"sha256:abc","total":2000,"completed":500}"#,
+                r#"{"status":"downloading","digest":"sha256:abc","total":2000,"completed":2000}"#,
+                r#"{"status":"success"}"#,
+            ].join("\n")),
+        });
+        let seen = Mutex::new(Vec::new());
+        let record = |p: &PullProgress| seen.lock().unwrap().push(p.clone());
+
+        let message = pull_model_at(&reqwest::Client::new(), &server.url, "qwen2.5:3b", &record).await.unwrap();
+        assert_eq!(message, "Pulled qwen2.5:3b");
+        let seen = seen.into_inner().unwrap();
+        let statuses: Vec<&str> = seen.iter().map(|p| p.status.as_str()).collect();
+        assert_eq!(statuses, ["pulling manifest", "downloading", "downloading", "success"]);
+        assert_eq!((seen[1].completed, seen[1].total), (Some(500), Some(2000)));
+        assert!(server.bodies()[1].contains("qwen2.5:3b"));
+    }
+
+    #[tokio::test]
+    async fn pull_skips_installed_model_and_reports_errors() {
+        let server = crate::test_support::mock_server(|req| match req.path.as_str() {
+            "/api/tags" => (200, r#"{"models":[{"name":"llama3:latest"}]}"#.to_string()),
+            _ => (200, r#"{"error":"pull model manifest: file does not exist"}"#.to_string()),
+        });
+        let client = reqwest::Client::new();
+        let message = pull_model_at(&client, &server.url, "llama3", &|_| {}).await.unwrap();
+        assert!(message.contains("already installed"));
+        assert_eq!(server.hits(), 1);
+
+        let err = pull_model_at(&client, &server.url, "nosuchmodel", &|_| {}).await.unwrap_err();
+        assert!(err.to_string().contains("file does not exist"));
+
+        let err = pull_model_at(&client, "http://127.0.0.1:9", "llama3", &|_| {}).await.unwrap_err();
+        assert!(err.to_string().contains("isn't running"));
+    }
+
+    #[tokio::test]
+    async fn blank_text_is_not_sent_to_the_model() {
+        let server = crate::test_support::chat_server("Should never be asked.");
+        for text in ["", "   ", "\n\t "] {
+            let request = RewriteRequest {
+                text: text.to_string(),
+                mode: "clarity".to_string(),
+                ..RewriteRequest::default()
+            };
+            let err = rewrite_at(&server.url, &request).await.unwrap_err();
+            assert!(err.contains("Nothing to rewrite"), "{}", err);
+            assert!(rewrite(&request, None).await.unwrap_err().to_string().contains("Nothing to rewrite"));
+        }
+        assert_eq!(server.hits(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn one_permit_serializes_rewrites() {
+        use std::sync::atomic::AtomicUsize;
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (active_in, peak_in) = (Arc::clone(&active), Arc::clone(&peak));
+        let server = crate::test_support::threaded_mock_server(move |_| {
+            let now = active_in.fetch_add(1, Ordering::SeqCst) + 1;
+            peak_in.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(150));
+            active_in.fetch_sub(1, Ordering::SeqCst);
+            let body = serde_json::json!({ "choices": [{ "message": { "content": "Done." } }] });
+            (200, body.to_string())
+        });
+        let detected = mock_detected(&server.url);
+        let config = Config { max_concurrent_rewrites: 1, ..Config::default() };
+        let request = |text: &str| RewriteRequest {
+            text: text.to_string(),
+            mode: "clarity".to_string(),
+            ..RewriteRequest::default()
+        };
+        let (first, second) = (request("First draft."), request("Second draft."));
+
+        let (a, b) = tokio::join!(
+            rewrite_with(&detected, &first, None, &config),
+            rewrite_with(&detected, &second, None, &config),
+        );
+        assert!(a.is_ok() && b.is_ok());
+        assert_eq!(server.hits(), 2);
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn offline_mode_skips_the_network() {
+        let config = Config { offline: true, ..Config::default() };
+        let started = std::time::Instant::now();
+
+        let cache = StatusCache::default();
+        let status = check_status_with(&config, &cache).await.unwrap();
+        assert!(!status.available);
+        assert_eq!(status.provider, "offline");
+        assert!(cache.last().is_none());
+
+        let err = detect_provider_with(&config).await.err().unwrap();
+        assert_eq!(err.to_string(), OFFLINE_ERROR);
+        assert_eq!(launch_lm_studio_with(&config).unwrap_err(), OFFLINE_ERROR);
+
+        // No probe timeouts: nothing was attempted
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn streamed_rewrite_logs_start_and_end() {
+        let sse = [
+            r#"data: {"choices":[{"delta":{"content":"The meeting "}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":"ran long."}}]}"#,
+            "data: [DONE]",
+        ]
+        .map(|l| format!("{}\n\n", l))
+        .concat();
+        let server = crate::test_support::mock_server(move |_| (200, sse.clone()));
+        let request = RewriteRequest {
+            text: "The meeting ran very long, honestly.".to_string(),
+            mode: "concise".to_string(),
+            ..RewriteRequest::default()
+        };
+
+        let partials = Mutex::new(Vec::new());
+        let on_chunk = |partial: &str| partials.lock().unwrap().push(partial.to_string());
+        let result = rewrite_with(&mock_detected(&server.url), &request, Some(&on_chunk), &Config::default())
+            .await
+            .unwrap();
+        assert_eq!(result.rewritten, "The meeting ran long.");
+        assert_eq!(partials.into_inner().unwrap(), ["The meeting ", "The meeting ran long."]);
+
+        let captured = crate::audit::CAPTURED.lock().unwrap();
+        let logged = |event: &str| {
+            captured
+                .iter()
+                .find(|(name, details)| name == event && details["mode"] == "concise")
+                .map(|(_, details)| details.clone())
+                .unwrap_or_else(|| panic!("{} not logged", event))
+        };
+        assert_eq!(logged("rewrite_start")["text_length"], request.text.len());
+        let end = logged("rewrite_end");
+        assert_eq!(end["success"], true);
+        assert_eq!(end["chunk_count"], 2);
+        assert!(end["time_to_first_token_ms"].is_u64());
+        assert!(end["duration_ms"].is_u64());
+    }
+
+    #[tokio::test]
+    async fn explain_stream_emits_each_chunk_then_returns_full_text() {
+        let sse = [
+            r#"data: {"choices":[{"delta":{"content":"The second sentence "}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":"repeats the first. "}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":"Cut it."}}]}"#,
+            "data: [DONE]",
+        ]
+        .map(|l| format!("{}\n\n", l))
+        .concat();
+        let server = crate::test_support::mock_server(move |_| (200, sse.clone()));
+
+        let partials = Mutex::new(Vec::new());
+        let on_chunk = |partial: &str| partials.lock().unwrap().push(partial.to_string());
+        let text = "We shipped the release today. The release shipped today as well.";
+        let critique = explain_stream_with(&mock_detected(&server.url), text, &on_chunk, &Config::default())
+            .await
+            .unwrap();
+
+        let partials = partials.into_inner().unwrap();
+        assert_eq!(partials.len(), 3);
+        assert_eq!(critique, "The second sentence repeats the first. Cut it.");
+        assert_eq!(partials.last().unwrap(), &critique);
+        assert!(server.bodies()[0].contains(r#""stream":true"#));
+    }
+
+    #[tokio::test]
+    async fn benchmark_reads_usage_from_stream() {
+        let sse = [
+            r#"data: {"choices":[{"delta":{"content":"Quiet "}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":"morning."}}]}"#,
+            r#"data: {"choices":[],"usage":{"prompt_tokens":20,"completion_tokens":96,"total_tokens":116}}"#,
+            "data: [DONE]",
+        ]
+        .map(|l| format!("{}\n\n", l))
+        .concat();
+        let server = crate::test_support::mock_server(move |_| (200, sse.clone()));
+
+        let result = benchmark_with(&mock_detected(&server.url), &Config::default()).await.unwrap();
+        assert_eq!(result.tokens_generated, 96);
+        assert!(!result.tokens_estimated);
+        assert!(result.time_to_first_token_ms.unwrap() <= result.total_ms);
+        assert_eq!(result.tokens_per_sec, tokens_per_sec(96, result.total_ms));
+        assert!(server.bodies()[0].contains(r#""include_usage":true"#));
+    }
+
+    #[tokio::test]
+    async fn benchmark_estimates_tokens_without_usage() {
+        let sse = "data: {\"choices\":[{\"delta\":{\"content\":\"one two three four five six seven eight nine ten\"}}]}\n\ndata: [DONE]\n\n";
+        let server = crate::test_support::mock_server(move |_| (200, sse.to_string()));
+
+        let result = benchmark_with(&mock_detected(&server.url), &Config::default()).await.unwrap();
+        assert_eq!(result.tokens_generated, 13);
+        assert!(result.tokens_estimated);
+    }
+
+    // --- replace span tests ---
+
+    #[test]
+    fn replace_span_must_match_text_length() {
+        let request = |start, end| RewriteRequest {
+            text: "café au lait".to_string(),
+            replace_start: start,
+            replace_end: end,
+            ..RewriteRequest::default()
+        };
+        assert!(validate_replace_span(&request(None, None)).is_ok());
+        assert!(validate_replace_span(&request(Some(10), Some(22))).is_ok());
+        assert!(validate_replace_span(&request(Some(10), Some(30))).is_err());
+        assert!(validate_replace_span(&request(Some(22), Some(10))).is_err());
+        assert!(validate_replace_span(&request(Some(10), None)).is_err());
+    }
+
+    #[tokio::test]
+    async fn rewrite_echoes_replace_span() {
+        let server = crate::test_support::chat_server("Anchored output.\nEXPLANATION: Kept the span.");
+        let request = RewriteRequest {
+            text: "Anchor this exact span.".to_string(),
+            mode: "clarity".to_string(),
+            replace_start: Some(40),
+            replace_end: Some(63),
+            ..RewriteRequest::default()
+        };
+
+        let result = rewrite_with(&mock_detected(&server.url), &request, None, &Config::default()).await.unwrap();
+        assert_eq!(result.replace_start, Some(40));
+        assert_eq!(result.replace_end, Some(63));
+    }
+
+    #[tokio::test]
+    async fn split_paragraphs_rewrites_each_and_keeps_the_break() {
+        let server = crate::test_support::mock_server(|req| {
+            let content = if req.body.contains("kettle") {
+                "The kettle boiled.\nEXPLANATION: Cut filler."
+            } else {
+                "We drank tea.\nEXPLANATION: Simpler verb."
+            };
+            (200, serde_json::json!({ "choices": [{ "message": { "content": content } }] }).to_string())
+        });
+        let request = RewriteRequest {
+            text: "The kettle was, at last, finally boiling.\n\nWe then proceeded to drink the tea.\n".to_string(),
+            mode: "concise".to_string(),
+            split_paragraphs: true,
+            ..RewriteRequest::default()
+        };
+
+        let result = rewrite_with(&mock_detected(&server.url), &request, None, &Config::default()).await.unwrap();
+        assert_eq!(server.hits(), 2);
+        assert_eq!(result.rewritten, "The kettle boiled.\n\nWe drank tea.\n");
+        assert_eq!(result.explanation, "Cut filler.\n\nSimpler verb.");
+    }
+
+    #[tokio::test]
+    async fn split_paragraphs_pass_constraints_to_each_paragraph() {
+        let server = crate::test_support::mock_server(|req| {
+            let content = if req.body.contains("kettle") {
+                "The kettle boiled.\nEXPLANATION: Cut filler."
+            } else {
+                "We drank Assam.\nEXPLANATION: Simpler verb."
+            };
+            (200, serde_json::json!({ "choices": [{ "message": { "content": content } }] }).to_string())
+        });
+        let request = RewriteRequest {
+            text: "The kettle was, at last, finally boiling.\n\nWe then proceeded to drink the Assam.".to_string(),
+            mode: "concise".to_string(),
+            split_paragraphs: true,
+            must_keep: vec!["Assam".to_string()],
+            max_chars: Some(60),
+            ..RewriteRequest::default()
+        };
+
+        let result = rewrite_with(&mock_detected(&server.url), &request, None, &Config::default()).await.unwrap();
+        let bodies = server.bodies();
+        assert_eq!(bodies.len(), 2);
+        for body in &bodies {
+            assert!(body.contains("characters long"));
+            assert_eq!(body.contains("Keep these words"), body.contains("drink the Assam"));
+        }
+        let report = result.constraints.unwrap();
+        assert!(report.satisfied && !report.retried);
+
+        // 60 chars less the 2-char break, shared by paragraph length (41:37)
+        let spans = crate::segment::paragraph_spans(&request.text);
+        let shares: Vec<_> = paragraph_constraints(&request, &spans).into_iter().map(|(_, max)| max).collect();
+        assert_eq!(shares, [Some(30), Some(27)]);
+    }
+
+    // --- annotation tests ---
+
+    #[test]
+    fn explanation_splits_into_points() {
+        let numbered = "1. Replaced \"utilize\" with \"use\".\n2. Cut the filler phrase,\nwhich added nothing.\n3) Merged two sentences.";
+        assert_eq!(
+            explanation_points(numbered),
+            ["Replaced \"utilize\" with \"use\".", "Cut the filler phrase, which added nothing.", "Merged two sentences."]
+        );
+        assert_eq!(explanation_points("- Shorter words\n- Active voice"), ["Shorter words", "Active voice"]);
+        assert_eq!(explanation_points("I cut filler. Then I fixed tense."), ["I cut filler.", "Then I fixed tense."]);
+        assert!(explanation_points("  ").is_empty());
+    }
+
+    #[test]
+    fn numbered_points_link_to_quoted_changes() {
+        let original = "We utilize the tool in order to save time.";
+        let rewritten = "We use the tool to save time.";
+        let explanation = "1. Replaced \"utilize\" with \"use\" — plainer.\n2) Cut “in order to” down to \"to\";\n   it says the same thing.\n3. Kept the rest as written.";
+
+        let annotations = annotate(original, rewritten, explanation);
+        assert_eq!(annotations.len(), 2);
+        assert_eq!((annotations[0].point, annotations[0].start, annotations[0].end), (1, 3, 6));
+        assert_eq!(&rewritten[3..6], "use");
+        // The deletion sits just before "to" in the rewrite
+        assert_eq!((annotations[1].point, annotations[1].start, annotations[1].end), (2, 16, 16));
+        assert!(annotations[1].note.ends_with("it says the same thing."));
+    }
+
+    #[test]
+    fn ambiguous_or_unnumbered_explanations_give_no_annotations() {
+        let original = "The big dog saw the big cat.";
+        let rewritten = "The huge dog saw the large cat.";
+        // "big" was changed twice — no way to tell which one the point means
+        assert!(annotate(original, rewritten, "1. Swapped \"big\" for stronger words.").is_empty());
+        assert!(annotate(original, rewritten, "Swapped \"big\" for \"huge\".").is_empty());
+    }
+
+    #[tokio::test]
+    async fn annotate_request_asks_for_numbered_points() {
+        let server = crate::test_support::chat_server("We use the tool.\nEXPLANATION: 1. Replaced \"utilize\" with \"use\".");
+        let request = RewriteRequest {
+            text: "We utilize the tool.".to_string(),
+            mode: "clarity".to_string(),
+            annotate: true,
+            ..RewriteRequest::default()
+        };
+        let result = rewrite_with(&mock_detected(&server.url), &request, None, &Config::default()).await.unwrap();
+        assert!(server.bodies()[0].contains("Number each point"));
+        assert_eq!(result.annotations.len(), 1);
+        assert_eq!((result.annotations[0].start, result.annotations[0].end), (3, 6));
+    }
+
+    #[tokio::test]
+    async fn raw_reply_only_when_requested() {
+        let reply = "We use the tool.\nEXPLANATION: Shorter verb.";
+        let server = crate::test_support::chat_server(reply);
+        let mut request = RewriteRequest {
+            text: "We utilize the tool.".to_string(),
+            mode: "clarity".to_string(),
+            ..RewriteRequest::default()
+        };
+        let plain = rewrite_with(&mock_detected(&server.url), &request, None, &Config::default()).await.unwrap();
+        assert_eq!(plain.raw, None);
+
+        request.include_raw = true;
+        let debug = rewrite_with(&mock_detected(&server.url), &request, None, &Config::default()).await.unwrap();
+        assert_eq!(debug.raw.as_deref(), Some(reply));
+        assert_eq!(debug.rewritten, "We use the tool.");
+    }
+
+    // --- constraint tests ---
+
+    #[test]
+    fn fidelity_is_low_for_an_unrelated_rewrite() {
+        let original = "The quarterly report shows revenue grew slowly across northern stores.";
+        assert_eq!(fidelity(original, original), 1.0);
+        assert!(fidelity(original, "The quarterly report shows that revenue grew slowly in northern stores.") > 0.7);
+        assert!(fidelity(original, "Dragons sleep beneath frozen mountains, dreaming of gold.") < 0.1);
+    }
+
+    #[tokio::test]
+    async fn aggressive_rewrite_is_flagged() {
+        let server = crate::test_support::chat_server("Dragons sleep beneath frozen mountains, dreaming of gold.");
+        let request = RewriteRequest {
+            text: "The quarterly report shows revenue grew slowly.".to_string(),
+            mode: "clarity".to_string(),
+            ..RewriteRequest::default()
+        };
+        let result = rewrite_with(&mock_detected(&server.url), &request, None, &Config::default()).await.unwrap();
+        assert!(result.fidelity < 0.1);
+        assert!(result.low_fidelity);
+    }
+
+    #[test]
+    fn check_constraints_flags_missing_keyword_and_length() {
+        let keep = vec!["Ghostpen".to_string(), "local-first".to_string()];
+
+        let ok = check_constraints("ghostpen is a local-first editor.", &keep, Some(40));
+        assert!(ok.satisfied);
+        assert!(ok.missing.is_empty());
+
+        let bad = check_constraints("Ghostpen is an editor that runs entirely offline.", &keep, Some(20));
+        assert!(!bad.satisfied);
+        assert_eq!(bad.missing, vec!["local-first".to_string()]);
+        assert_eq!(bad.chars, 49);
+    }
+
+    #[tokio::test]
+    async fn constrained_rewrite_sends_keep_list_and_retries_once() {
+        let server = crate::test_support::chat_server("A much shorter line.\nEXPLANATION: Trimmed.");
+        let request = RewriteRequest {
+            text: "Ghostpen keeps every draft on your own machine at all times.".to_string(),
+            mode: "concise".to_string(),
+            must_keep: vec!["Ghostpen".to_string()],
+            max_chars: Some(50),
+            ..RewriteRequest::default()
+        };
+
+        let result = rewrite_with(&mock_detected(&server.url), &request, None, &Config::default()).await.unwrap();
+
+        let bodies = server.bodies();
+        assert_eq!(bodies.len(), 2, "a violated constraint earns exactly one retry");
+        assert!(bodies[0].contains(r#"Keep these words exactly as written: \"Ghostpen\""#));
+        assert!(bodies[0].contains("at most 50 characters"));
+        assert!(bodies[1].contains("it dropped"));
+
+        let report = result.constraints.unwrap();
+        assert!(!report.satisfied);
+        assert!(report.retried);
+        assert_eq!(report.missing, vec!["Ghostpen".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn unconstrained_rewrite_reports_nothing() {
+        let server = crate::test_support::chat_server("Plain output.\nEXPLANATION: Fine.");
+        let request = RewriteRequest {
+            text: "Plain input with no constraints at all.".to_string(),
+            mode: "clarity".to_string(),
+            ..RewriteRequest::default()
+        };
+        let result = rewrite_with(&mock_detected(&server.url), &request, None, &Config::default()).await.unwrap();
+        assert!(result.constraints.is_none());
+        assert_eq!(server.hits(), 1);
+    }
+
+    // --- rewrite cache tests ---
+
+    #[tokio::test]
+    async fn cached_rewrite_skips_second_http_call() {
+        let server = crate::test_support::chat_server("Cache me please.\nEXPLANATION: Tightened wording.");
+        let config = Config { rewrite_cache: true, ..Config::default() };
+        let request = RewriteRequest {
+            text: "Please cache me, this is the cache test.".to_string(),
+            mode: "clarity".to_string(),
+            ..RewriteRequest::default()
+        };
+
+        let detected = mock_detected(&server.url);
+        let first = rewrite_with(&detected, &request, None, &config).await.unwrap();
+        let second = rewrite_with(&detected, &request, None, &config).await.unwrap();
+
+        assert_eq!(server.hits(), 1);
+        assert!(!first.cached);
+        assert!(second.cached);
+        assert_eq!(second.rewritten, "Cache me please.");
+    }
+
+    #[tokio::test]
+    async fn cache_disabled_always_queries() {
+        let server = crate::test_support::chat_server("Fresh each time.\nEXPLANATION: None needed.");
+        let config = Config { rewrite_cache: false, ..Config::default() };
+        let request = RewriteRequest {
+            text: "Never cache this uncached test input.".to_string(),
+            mode: "clarity".to_string(),
+            ..RewriteRequest::default()
+        };
+
+        let detected = mock_detected(&server.url);
+        rewrite_with(&detected, &request, None, &config).await.unwrap();
+        rewrite_with(&detected, &request, None, &config).await.unwrap();
+        assert_eq!(server.hits(), 2);
+    }
+
     // --- validate_response tests ---
 
     #[test]
@@ -426,6 +2732,202 @@ mod tests {
         assert!(result.unwrap_err().contains("long"));
     }
 
+    // --- host resolution tests ---
+
+    #[test]
+    fn localhost_probes_both_loopbacks() {
+        assert_eq!(candidate_hosts("localhost"), vec!["127.0.0.1", "::1"]);
+    }
+
+    #[test]
+    fn custom_host_is_honored() {
+        let config = crate::config::parse("llm_host = \"192.168.1.20\"").unwrap();
+        let hosts = candidate_hosts(&config.llm_host);
+        assert_eq!(hosts, vec!["192.168.1.20"]);
+        assert_eq!(base_url(&hosts[0], OLLAMA_PORT), "http://192.168.1.20:11434");
+    }
+
+    #[test]
+    fn ipv6_host_is_bracketed() {
+        let hosts = candidate_hosts("[::1]");
+        assert_eq!(base_url(&hosts[0], LMSTUDIO_PORT), "http://[::1]:1234");
+    }
+
+    #[test]
+    fn probe_urls_derive_mount_from_path() {
+        let (probe, base) = probe_urls("http://127.0.0.1:1234", "/v1/models", "/v1/models").unwrap();
+        assert_eq!(probe, "http://127.0.0.1:1234/v1/models");
+        assert_eq!(base, "http://127.0.0.1:1234");
+
+        let (probe, base) = probe_urls("http://127.0.0.1:11434", "ollama/", "").unwrap();
+        assert_eq!(probe, "http://127.0.0.1:11434/ollama/");
+        assert_eq!(base, "http://127.0.0.1:11434/ollama");
+
+        assert!(probe_urls("http://bad host:1234", "/v1/models", "/v1/models").is_err());
+    }
+
+    #[tokio::test]
+    async fn lm_studio_detected_under_base_path() {
+        let server = crate::test_support::mock_server(|req| match req.path.as_str() {
+            "/llm/v1/models" => (200, r#"{"data":[{"id":"nomic-embed"},{"id":"qwen2.5-3b"}]}"#.to_string()),
+            _ => (404, String::new()),
+        });
+        let client = reqwest::Client::new();
+
+        assert!(probe_lm_studio(&client, "127.0.0.1", &server.url, "/v1/models").await.is_none());
+
+        let detected = probe_lm_studio(&client, "127.0.0.1", &server.url, "/llm/v1/models").await.unwrap();
+        assert_eq!(detected.base_url, format!("{}/llm", server.url));
+        assert_eq!(detected.model, "qwen2.5-3b");
+    }
+
+    #[tokio::test]
+    async fn diagnostics_report_each_endpoint_state() {
+        let lm_studio = crate::test_support::mock_server(|req| match req.path.as_str() {
+            "/v1/models" => (200, r#"{"data":[]}"#.to_string()),
+            _ => (404, String::new()),
+        });
+        let ollama = crate::test_support::mock_server(|_| (500, "internal error".to_string()));
+        let client = reqwest::Client::new();
+
+        let empty = diagnose_endpoint(&client, Provider::LmStudio, &lm_studio.url, "/v1/models").await;
+        assert!(empty.reachable);
+        assert_eq!(empty.status, Some(200));
+        assert!(empty.models.is_empty());
+        assert!(empty.latency_ms.is_some());
+        assert_eq!(empty.summary, "LM Studio reachable but no model loaded");
+
+        let broken = diagnose_endpoint(&client, Provider::Ollama, &ollama.url, "/").await;
+        assert!(broken.reachable);
+        assert_eq!(broken.status, Some(500));
+        assert!(broken.error.is_none());
+        assert!(broken.summary.contains("HTTP 500"));
+
+        // Nothing listens on the discard port
+        let down = diagnose_endpoint(&client, Provider::Ollama, "http://127.0.0.1:9", "/").await;
+        assert!(!down.reachable);
+        assert!(down.status.is_none());
+        assert!(down.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn ollama_diagnostics_list_installed_models() {
+        let ollama = crate::test_support::mock_server(|req| match req.path.as_str() {
+            "/api/tags" => (200, r#"{"models":[{"name":"qwen2.5:3b"},{"name":"mistral:latest"}]}"#.to_string()),
+            _ => (200, "Ollama is running".to_string()),
+        });
+        let report = diagnose_endpoint(&reqwest::Client::new(), Provider::Ollama, &ollama.url, "/").await;
+        assert_eq!(report.models, vec!["qwen2.5:3b", "mistral:latest"]);
+        assert_eq!(report.summary, "Ollama reachable with 2 models");
+    }
+
+    #[tokio::test]
+    async fn offline_diagnostics_probe_nothing() {
+        let config = Config { offline: true, ..Config::default() };
+        let report = diagnose_with(&config).await.unwrap();
+        assert!(report.offline);
+        assert!(report.endpoints.is_empty());
+    }
+
+    // --- context window tests ---
+
+    #[test]
+    fn context_window_uses_longest_prefix() {
+        assert_eq!(context_window("qwen2.5:3b"), Some(8192));
+        assert_eq!(context_window("qwen2.5:0.5b"), Some(4096));
+        assert_eq!(context_window("Llama3.1:8B"), Some(8192));
+        assert_eq!(context_window("some-custom-model"), None);
+    }
+
+    #[test]
+    fn long_input_does_not_fit_small_model() {
+        let long_text = "word ".repeat(1500);
+        let info = context_check(&long_text, "tinyllama");
+        assert_eq!(info.context_window, 2048);
+        assert_eq!(info.estimated_tokens, 1950);
+        assert!(!info.fits);
+
+        let short = context_check("A short sentence.", "unknown-model");
+        assert!(!short.known_model);
+        assert_eq!(short.context_window, DEFAULT_CONTEXT_WINDOW);
+        assert!(short.fits);
+    }
+
+    // --- cached status tests ---
+
+    #[tokio::test]
+    async fn probe_result_is_cached_without_refetch() {
+        let server = crate::test_support::mock_server(|_| (200, r#"{"data":[{"id":"cached-model"}]}"#.to_string()));
+        let client = reqwest::Client::new();
+
+        let detected = probe_lm_studio(&client, "127.0.0.1", &server.url, "/v1/models").await.ok_or(());
+        let cache = StatusCache::default();
+        assert!(cache.last().is_none());
+        let status = record_probe(detected, &cache);
+        assert_eq!(server.hits(), 1);
+
+        let cached = cache.last().unwrap();
+        assert!(cached.status.available);
+        assert_eq!(cached.status.model, status.model);
+        assert_eq!(cached.status_age_secs, 0);
+        assert_eq!(server.hits(), 1);
+    }
+
+    // --- input budget tests ---
+
+    #[test]
+    fn cost_estimate_uses_price_table() {
+        let mut config = Config { completion_ratio: 0.5, ..Config::default() };
+        config.model_prices.insert(
+            "gpt-4o-mini".to_string(),
+            config::ModelPrice { input_per_million: 2.0, output_per_million: 8.0 },
+        );
+        let request = RewriteRequest { text: "We utilize it daily.".to_string(), mode: "clarity".to_string(), ..RewriteRequest::default() };
+
+        let estimate = estimate_cost_for(&request, "gpt-4o-mini", &config);
+        let prompt: Vec<String> = request_messages(&request).into_iter().map(|m| m.content).collect();
+        assert_eq!(estimate.input_tokens, estimate_tokens(&prompt.join("\n")));
+        assert_eq!(estimate.output_tokens, estimate.input_tokens.div_ceil(2));
+        let expected = (estimate.input_tokens as f64 * 2.0 + estimate.output_tokens as f64 * 8.0) / 1_000_000.0;
+        assert!((estimate.cost_usd.unwrap() - expected).abs() < 1e-12);
+
+        // Local models have no price, so no dollar figure
+        assert_eq!(estimate_cost_for(&request, "qwen2.5:3b", &config).cost_usd, None);
+    }
+
+    #[test]
+    fn estimate_tokens_uses_word_heuristic() {
+        assert_eq!(estimate_tokens("one two three four five six seven eight nine ten"), 13);
+        assert_eq!(estimate_tokens("   "), 0);
+    }
+
+    #[test]
+    fn preview_reports_the_resolved_models_limit() {
+        let mut config = Config { max_input_tokens: 4000, ..Config::default() };
+        config.model_max_input_tokens.insert("qwen2.5:3b".to_string(), 1000);
+        let text = "word ".repeat(1000);
+
+        let preview = preview_prompt_for(&text, "clarity", Some("qwen2.5:3b".to_string()), &config);
+        assert_eq!(preview.max_input_tokens, 1000);
+        assert_eq!(preview.estimated_tokens > preview.max_input_tokens, check_input_budget(&text, "qwen2.5:3b", &config).is_err());
+        assert_eq!(preview_prompt_for(&text, "clarity", None, &config).max_input_tokens, 4000);
+    }
+
+    #[test]
+    fn budget_rejects_oversized_input() {
+        let config = Config { max_input_tokens: 100, ..Config::default() };
+        let long_text = "word ".repeat(1000);
+        let result = check_input_budget(&long_text, "qwen2.5:3b", &config);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("too long"));
+    }
+
+    #[test]
+    fn budget_accepts_small_input() {
+        let config = Config::default();
+        assert_eq!(check_input_budget("Short text here.", "qwen2.5:3b", &config), Ok(4));
+    }
+
     #[test]
     fn validate_accepts_valid() {
         let result = validate_response("This is a perfectly fine rewrite.", "Original text here.");
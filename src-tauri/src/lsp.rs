@@ -0,0 +1,108 @@
+//! Grammar issues as Language Server Protocol diagnostics, for editor integrations.
+
+use crate::{char_to_byte, GrammarIssue, Severity};
+use serde::Serialize;
+
+/// Zero-based line and UTF-16 column, as LSP counts them
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub character: usize,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Extra payload for code actions
+#[derive(Serialize, Debug, PartialEq)]
+pub struct DiagnosticData {
+    pub suggestions: Vec<String>,
+    /// Ghostpen's own category ("Spelling", "Style", ...)
+    pub kind: String,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub range: Range,
+    /// 1 = Error, 2 = Warning, 3 = Information, 4 = Hint
+    pub severity: u8,
+    pub message: String,
+    pub source: &'static str,
+    pub data: DiagnosticData,
+}
+
+/// Same buckets the editor uses for underline colors, plus Hint for consistency notes
+fn lsp_severity(issue: &GrammarIssue) -> u8 {
+    match issue.severity {
+        _ if issue.rule == "Consistency" => 4,
+        Severity::Error => 1,
+        Severity::Warning => 2,
+        Severity::Suggestion => 3,
+    }
+}
+
+/// Line and UTF-16 column of a byte offset
+fn position(text: &str, byte: usize) -> Position {
+    let before = &text[..byte];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: before.matches('\n').count(),
+        character: before[line_start..].encode_utf16().count(),
+    }
+}
+
+pub fn diagnostics(text: &str, issues: &[GrammarIssue]) -> Vec<Diagnostic> {
+    issues
+        .iter()
+        .map(|issue| Diagnostic {
+            range: Range {
+                start: position(text, char_to_byte(text, issue.start)),
+                end: position(text, char_to_byte(text, issue.end)),
+            },
+            severity: lsp_severity(issue),
+            message: issue.message.clone(),
+            source: "ghostpen",
+            data: DiagnosticData {
+                suggestions: issue.suggestions.clone(),
+                kind: issue.rule.clone(),
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issue_maps_to_lsp_range_and_severity() {
+        let text = "First line.\nA 😀 recieve here.";
+        let issue = GrammarIssue {
+            start: 16,
+            end: 23,
+            message: "Did you mean \"receive\"?".to_string(),
+            suggestions: vec!["receive".to_string()],
+            severity: Severity::Error,
+            rule: "Spelling".to_string(),
+            best_fix: Some("receive".to_string()),
+            matched_text: String::new(),
+        };
+        assert_eq!(diagnostics(text, &[issue]), vec![Diagnostic {
+            // The emoji is one char but two UTF-16 units
+            range: Range {
+                start: Position { line: 1, character: 5 },
+                end: Position { line: 1, character: 12 },
+            },
+            severity: 1,
+            message: "Did you mean \"receive\"?".to_string(),
+            source: "ghostpen",
+            data: DiagnosticData {
+                suggestions: vec!["receive".to_string()],
+                kind: "Spelling".to_string(),
+            },
+        }]);
+    }
+}
@@ -0,0 +1,123 @@
+//! User prompt templates from ~/.ghostpen/modes.toml, reloaded whenever the file
+//! changes so prompt tweaks take effect without restarting.
+//!
+//! ```toml
+//! [pirate]
+//! prompt = "Rewrite this like a pirate would say it.\n\nText: {text}"
+//! ```
+//!
+//! A template replaces the built-in prompt of the same mode name. `{text}` marks
+//! where the user's text goes; without it the text is appended on its own line.
+
+use notify::{RecursiveMode, Watcher};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::Emitter;
+
+const MODES_FILE: &str = "modes.toml";
+
+#[derive(Deserialize)]
+struct ModeTemplate {
+    prompt: String,
+}
+
+/// Current templates, swapped whole on reload. Readers clone the Arc, so a
+/// rewrite already underway keeps the set it started with.
+static TEMPLATES: Mutex<Option<Arc<HashMap<String, String>>>> = Mutex::new(None);
+
+fn modes_file() -> Option<PathBuf> {
+    crate::paths::data_dir().map(|d| d.join(MODES_FILE))
+}
+
+/// The template set in effect right now
+pub fn snapshot() -> Arc<HashMap<String, String>> {
+    TEMPLATES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(Default::default)
+        .clone()
+}
+
+/// Fill a template with the user's text
+pub fn render(template: &str, text: &str) -> String {
+    if template.contains("{text}") {
+        template.replace("{text}", text)
+    } else {
+        format!("{}\n\nText: {}", template.trim_end(), text)
+    }
+}
+
+/// Read templates from `path` and make them current. A missing file clears them;
+/// a malformed one is reported and the previous set stays in place.
+/// Returns the loaded mode names, sorted.
+pub fn reload_from(path: &Path) -> Result<Vec<String>, String> {
+    let templates: HashMap<String, String> = match std::fs::read_to_string(path) {
+        Ok(content) => toml::from_str::<HashMap<String, ModeTemplate>>(&content)
+            .map_err(|e| format!("Invalid {}: {}", MODES_FILE, e))?
+            .into_iter()
+            .map(|(name, t)| (name, t.prompt))
+            .collect(),
+        Err(_) => HashMap::new(),
+    };
+    let mut names: Vec<String> = templates.keys().cloned().collect();
+    names.sort();
+    *TEMPLATES.lock().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(templates));
+    Ok(names)
+}
+
+/// Load modes.toml and keep watching it. Every successful reload emits
+/// `modes_reloaded` with the mode names so the UI can refresh its list.
+/// The watcher lives for the rest of the process.
+pub fn watch(app: tauri::AppHandle) {
+    static WATCHER: OnceLock<Mutex<notify::RecommendedWatcher>> = OnceLock::new();
+
+    let Some(path) = modes_file() else { return };
+    let _ = reload_from(&path);
+    let Some(dir) = path.parent().map(Path::to_path_buf) else { return };
+    let _ = std::fs::create_dir_all(&dir);
+
+    // Editors often save by writing a temp file and renaming it over the
+    // original, so watch the directory rather than the file itself
+    let watched = path.clone();
+    let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else { return };
+        if !event.paths.iter().any(|p| p.file_name() == watched.file_name()) {
+            return;
+        }
+        match reload_from(&watched) {
+            Ok(names) => {
+                let _ = app.emit("modes_reloaded", &names);
+            }
+            Err(e) => crate::audit::log_event("modes_reload_failed", serde_json::json!({ "error": e })),
+        }
+    });
+    let Ok(mut watcher) = watcher else { return };
+    if watcher.watch(&dir, RecursiveMode::NonRecursive).is_ok() {
+        let _ = WATCHER.set(Mutex::new(watcher));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modifying_the_file_updates_the_prompt() {
+        let path = std::env::temp_dir().join(format!("ghostpen-modes-{}.toml", std::process::id()));
+        std::fs::write(&path, "[hot-reload-test]\nprompt = \"Version one: {text}\"\n").unwrap();
+        assert_eq!(reload_from(&path).unwrap(), vec!["hot-reload-test"]);
+        assert_eq!(crate::llm::build_prompt("Hi.", "hot-reload-test"), "Version one: Hi.");
+
+        std::fs::write(&path, "[hot-reload-test]\nprompt = \"Version two.\"\n").unwrap();
+        reload_from(&path).unwrap();
+        assert_eq!(crate::llm::build_prompt("Hi.", "hot-reload-test"), "Version two.\n\nText: Hi.");
+
+        // A broken edit keeps the last good set
+        std::fs::write(&path, "[hot-reload-test\n").unwrap();
+        assert!(reload_from(&path).is_err());
+        assert_eq!(crate::llm::build_prompt("Hi.", "hot-reload-test"), "Version two.\n\nText: Hi.");
+        let _ = std::fs::remove_file(&path);
+    }
+}
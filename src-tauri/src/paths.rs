@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+/// Environment variable that roots all Ghostpen data in one directory (portable installs)
+pub const DATA_DIR_ENV: &str = "GHOSTPEN_DATA_DIR";
+
+/// Root from GHOSTPEN_DATA_DIR, if set and non-empty
+fn env_root() -> Option<PathBuf> {
+    std::env::var_os(DATA_DIR_ENV)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Explicit data root: `env` (GHOSTPEN_DATA_DIR) first, then `data_dir` in config.toml
+fn override_root(env: Option<PathBuf>) -> Option<PathBuf> {
+    env.or_else(|| {
+        crate::config::load_file()
+            .data_dir
+            .filter(|d| !d.trim().is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// Directory for user data (dictionary, feedback, ...). Defaults to ~/.ghostpen
+pub fn data_dir() -> Option<PathBuf> {
+    data_dir_in(env_root())
+}
+
+/// data_dir with `env` standing in for GHOSTPEN_DATA_DIR
+pub(crate) fn data_dir_in(env: Option<PathBuf>) -> Option<PathBuf> {
+    override_root(env).or_else(|| Some(dirs::home_dir()?.join(".ghostpen")))
+}
+
+/// Location of config.toml. Only the environment variable can move it —
+/// the config file can't relocate itself.
+pub fn config_file() -> Option<PathBuf> {
+    config_file_in(env_root())
+}
+
+/// config_file with `env` standing in for GHOSTPEN_DATA_DIR
+pub(crate) fn config_file_in(env: Option<PathBuf>) -> Option<PathBuf> {
+    env.or_else(|| Some(dirs::home_dir()?.join(".ghostpen")))
+        .map(|d| d.join("config.toml"))
+}
+
+/// Directory for audit logs. Defaults to the OS local data dir (%LOCALAPPDATA%/ghostpen/logs)
+pub fn log_dir() -> PathBuf {
+    log_dir_in(env_root())
+}
+
+/// log_dir with `env` standing in for GHOSTPEN_DATA_DIR
+pub(crate) fn log_dir_in(env: Option<PathBuf>) -> PathBuf {
+    match override_root(env) {
+        Some(root) => root.join("logs"),
+        None => dirs::data_local_dir()
+            .unwrap_or_default()
+            .join("ghostpen")
+            .join("logs"),
+    }
+}
@@ -0,0 +1,164 @@
+//! Named bundles of settings ("Email", "Academic", "Fiction") layered over
+//! config.toml. Each profile is a partial config in ~/.ghostpen/profiles/<name>.toml;
+//! while one is active its keys override config.toml wherever config is loaded.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Holds the active profile's name. The leading dot keeps it out of profile names.
+const ACTIVE_FILE: &str = ".active";
+
+/// Settings a profile may not override: data_dir decides where profiles live
+const RESERVED_KEYS: &[&str] = &["data_dir"];
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub active: bool,
+    /// The profile's overrides, as saved
+    pub settings: serde_json::Value,
+}
+
+fn profiles_dir() -> Option<PathBuf> {
+    Some(crate::paths::data_dir()?.join("profiles"))
+}
+
+/// Names double as file names: letters, digits, spaces, '-' and '_' only
+fn check_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name.chars().count() <= 64
+        && name.chars().all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'));
+    if valid {
+        Ok(name)
+    } else {
+        Err(format!("Invalid profile name '{}': use letters, digits, spaces, '-' or '_'", name))
+    }
+}
+
+fn read_profile(dir: &Path, name: &str) -> Option<toml::Table> {
+    let content = std::fs::read_to_string(dir.join(format!("{}.toml", name))).ok()?;
+    toml::from_str(&content).ok()
+}
+
+fn active_name(dir: &Path) -> Option<String> {
+    let name = std::fs::read_to_string(dir.join(ACTIVE_FILE)).ok()?;
+    Some(name.trim().to_string()).filter(|n| !n.is_empty())
+}
+
+/// Overrides of the active profile. None when no profile is active or its file
+/// is missing or unreadable, so a broken profile never takes config down with it.
+pub(crate) fn active_settings() -> Option<toml::Table> {
+    active_settings_in(&profiles_dir()?)
+}
+
+fn active_settings_in(dir: &Path) -> Option<toml::Table> {
+    read_profile(dir, &active_name(dir)?)
+}
+
+pub fn list() -> Vec<ProfileInfo> {
+    profiles_dir().map(|dir| list_in(&dir)).unwrap_or_default()
+}
+
+/// Saved profiles by name
+fn list_in(dir: &Path) -> Vec<ProfileInfo> {
+    let active = active_name(dir);
+    let Ok(entries) = std::fs::read_dir(dir) else { return vec![] };
+    let mut profiles: Vec<ProfileInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "toml" {
+                return None;
+            }
+            let name = path.file_stem()?.to_string_lossy().to_string();
+            let settings = serde_json::to_value(read_profile(dir, &name)?).ok()?;
+            Some(ProfileInfo { active: active.as_deref() == Some(name.as_str()), name, settings })
+        })
+        .collect();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+}
+
+pub fn save(name: &str, settings: &serde_json::Value) -> Result<(), String> {
+    let dir = profiles_dir().ok_or_else(|| "Could not determine data directory".to_string())?;
+    save_in(&dir, name, settings)
+}
+
+/// Write a profile, replacing one of the same name. `settings` is a JSON object
+/// of config keys, checked the same way as config.toml.
+fn save_in(dir: &Path, name: &str, settings: &serde_json::Value) -> Result<(), String> {
+    let name = check_name(name)?;
+    let table = match toml::Value::try_from(settings) {
+        Ok(toml::Value::Table(table)) => table,
+        Ok(_) => return Err("Profile settings must be an object of config keys".to_string()),
+        Err(e) => return Err(format!("Profile settings can't be stored: {}", e)),
+    };
+    if let Some(key) = RESERVED_KEYS.iter().find(|k| table.contains_key(**k)) {
+        return Err(format!("'{}' can't be set by a profile", key));
+    }
+    let content = toml::to_string(&table).map_err(|e| e.to_string())?;
+    let (errors, warnings) = crate::config::validate(&content);
+    if let Some(problem) = errors.first().or(warnings.first()) {
+        return Err(format!("Profile '{}': {}", name, problem.message));
+    }
+
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create profiles directory: {}", e))?;
+    std::fs::write(dir.join(format!("{}.toml", name)), content).map_err(|e| format!("Failed to save profile '{}': {}", name, e))
+}
+
+pub fn activate(name: &str) -> Result<(), String> {
+    let dir = profiles_dir().ok_or_else(|| "Could not determine data directory".to_string())?;
+    activate_in(&dir, name)
+}
+
+/// Make `name` the active profile. An empty name goes back to plain config.toml.
+fn activate_in(dir: &Path, name: &str) -> Result<(), String> {
+    let path = dir.join(ACTIVE_FILE);
+    if name.trim().is_empty() {
+        return match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("Failed to clear active profile: {}", e)),
+            _ => Ok(()),
+        };
+    }
+    let name = check_name(name)?;
+    if read_profile(dir, name).is_none() {
+        return Err(format!("No profile named '{}'", name));
+    }
+    std::fs::write(&path, name).map_err(|e| format!("Failed to activate profile '{}': {}", name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_profile_is_listed_and_changes_active_settings() {
+        let dir = std::env::temp_dir().join(format!("ghostpen-profiles-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let base = "temperature = 0.3\ndialect = \"american\"\n";
+
+        save_in(&dir, "Academic", &serde_json::json!({ "temperature": 0.1, "dialect": "british" })).unwrap();
+        save_in(&dir, "Email", &serde_json::json!({ "temperature": 0.7 })).unwrap();
+        assert!(save_in(&dir, "Broken", &serde_json::json!({ "temperature": 9.0 })).is_err());
+        assert!(save_in(&dir, "../escape", &serde_json::json!({})).is_err());
+
+        let names: Vec<String> = list_in(&dir).into_iter().map(|p| p.name).collect();
+        assert_eq!(names, ["Academic", "Email"]);
+        assert!(active_settings_in(&dir).is_none());
+
+        activate_in(&dir, "Academic").unwrap();
+        let config = crate::config::with_overrides(base, active_settings_in(&dir).unwrap()).unwrap();
+        assert_eq!((config.temperature, config.dialect.as_str()), (0.1, "british"));
+        assert!(list_in(&dir).iter().any(|p| p.name == "Academic" && p.active));
+
+        activate_in(&dir, "Email").unwrap();
+        let config = crate::config::with_overrides(base, active_settings_in(&dir).unwrap()).unwrap();
+        assert_eq!((config.temperature, config.dialect.as_str()), (0.7, "american"));
+
+        assert!(activate_in(&dir, "Fiction").is_err());
+        activate_in(&dir, "").unwrap();
+        assert!(active_settings_in(&dir).is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
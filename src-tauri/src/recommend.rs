@@ -0,0 +1,92 @@
+//! rewrite_recommendation: a cheap nudge toward the rewrite feature, from issue
+//! density and reading grade. No model is involved.
+
+use crate::config::Config;
+use crate::TextStats;
+use serde::Serialize;
+
+/// Below this many words the numbers swing too much to mean anything
+const MIN_WORDS: usize = 5;
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Recommendation {
+    pub issues_per_100_words: f32,
+    /// Flesch-Kincaid grade level
+    pub grade_level: f32,
+    /// Rewrite mode worth trying, or None when the text reads fine
+    pub mode: Option<String>,
+    /// Sentence for the UI, e.g. "Consider a clarity rewrite: ..."
+    pub suggestion: Option<String>,
+    /// 0.5 right at a threshold, rising to 1.0 at twice the threshold; 0 without a mode
+    pub confidence: f32,
+}
+
+/// Vowel groups, less a silent trailing "e"; at least one per word
+fn syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let mut count = 0;
+    let mut in_vowel = false;
+    for c in word.chars() {
+        let vowel = "aeiouy".contains(c);
+        if vowel && !in_vowel {
+            count += 1;
+        }
+        in_vowel = vowel;
+    }
+    if word.ends_with('e') && !word.ends_with("le") && count > 1 {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+/// Flesch-Kincaid grade: 0.39 × words per sentence + 11.8 × syllables per word − 15.59
+pub fn grade_level(text: &str, sentence_count: usize) -> f32 {
+    let words = crate::segment::words(text);
+    if words.is_empty() {
+        return 0.0;
+    }
+    let syllable_count: usize = words.iter().map(|w| syllables(w)).sum();
+    let words_per_sentence = words.len() as f32 / sentence_count.max(1) as f32;
+    let syllables_per_word = syllable_count as f32 / words.len() as f32;
+    0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59
+}
+
+fn confidence(ratio: f32) -> f32 {
+    (0.5 + (ratio - 1.0) * 0.5).clamp(0.5, 1.0)
+}
+
+/// Weigh a finished check against config.rewrite_issue_density and
+/// config.rewrite_grade_level. Many issues point to "proofread"; hard-to-read
+/// text points to "clarity". Whichever threshold is exceeded more wins.
+pub fn recommend(text: &str, stats: &TextStats, config: &Config) -> Recommendation {
+    let density = if stats.word_count == 0 {
+        0.0
+    } else {
+        stats.total_issues as f32 * 100.0 / stats.word_count as f32
+    };
+    let grade = grade_level(text, stats.sentence_count);
+    let mut recommendation = Recommendation {
+        issues_per_100_words: density,
+        grade_level: grade,
+        mode: None,
+        suggestion: None,
+        confidence: 0.0,
+    };
+    if stats.word_count < MIN_WORDS {
+        return recommendation;
+    }
+
+    let density_ratio = density / config.rewrite_issue_density;
+    let grade_ratio = grade / config.rewrite_grade_level;
+    let (mode, ratio, reason) = if density_ratio >= grade_ratio {
+        ("proofread", density_ratio, format!("has {:.0} issues per 100 words", density))
+    } else {
+        ("clarity", grade_ratio, format!("reads at grade {:.0}", grade))
+    };
+    if ratio >= 1.0 {
+        recommendation.mode = Some(mode.to_string());
+        recommendation.suggestion = Some(format!("Consider a {} rewrite: this text {}.", mode, reason));
+        recommendation.confidence = confidence(ratio);
+    }
+    recommendation
+}
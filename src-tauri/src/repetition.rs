@@ -0,0 +1,79 @@
+//! Overused-word report: counts content words (stopwords excluded) so the UI
+//! can highlight the ones a writer leans on.
+
+use crate::checks::word_spans;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Bundled stopword list, one word per line
+const STOPWORDS_DATA: &str = include_str!("../data/stopwords.txt");
+
+/// A word used more often than the configured threshold
+#[derive(Serialize, Debug)]
+pub struct WordCount {
+    /// Lowercased word
+    pub word: String,
+    pub count: usize,
+    /// Byte range of each occurrence, in document order
+    pub spans: Vec<[usize; 2]>,
+}
+
+pub(crate) fn bundled_stopwords() -> &'static [String] {
+    static WORDS: OnceLock<Vec<String>> = OnceLock::new();
+    WORDS.get_or_init(|| {
+        STOPWORDS_DATA
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(str::to_lowercase)
+            .collect()
+    })
+}
+
+/// Words appearing more than `threshold` times, most frequent first (ties alphabetical).
+/// `stopwords` replaces the bundled list when given. Numbers are skipped.
+pub fn report(text: &str, threshold: usize, stopwords: Option<&[String]>) -> Vec<WordCount> {
+    let stopwords = stopwords.unwrap_or(bundled_stopwords());
+    let mut counts: HashMap<String, Vec<[usize; 2]>> = HashMap::new();
+    for span in word_spans(text) {
+        let word = text[span.clone()].to_lowercase();
+        if !word.chars().any(char::is_alphabetic) || stopwords.contains(&word) {
+            continue;
+        }
+        counts.entry(word).or_default().push([span.start, span.end]);
+    }
+
+    let mut report: Vec<WordCount> = counts
+        .into_iter()
+        .filter(|(_, spans)| spans.len() > threshold)
+        .map(|(word, spans)| WordCount { word, count: spans.len(), spans })
+        .collect();
+    report.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overused_content_word_surfaces() {
+        let text = "The project was great. The team did great work on the project, \
+                    and the great results show it. It was a great year.";
+        let report = report(text, 2, None);
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].word, "great");
+        assert_eq!(report[0].count, 4);
+        assert_eq!(&text[report[0].spans[0][0]..report[0].spans[0][1]], "great");
+        assert!(report.iter().all(|w| w.word != "the"));
+    }
+
+    #[test]
+    fn custom_stopwords_replace_the_bundled_list() {
+        let stopwords = vec!["great".to_string()];
+        let report = report("the great the great the great", 2, Some(&stopwords));
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].word, "the");
+    }
+}
@@ -0,0 +1,185 @@
+//! deep_review: Harper's findings merged with the model's "explain" critique.
+//! Model points that quote text Harper already flagged are folded into that
+//! finding; the rest are listed on their own, located in the text when possible.
+
+use crate::{GrammarIssue, Severity};
+use serde::Serialize;
+
+/// Where a review item came from
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Harper,
+    Llm,
+    /// Flagged by Harper and also discussed by the model
+    Both,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReviewItem {
+    pub source: Source,
+    /// Char offsets, like GrammarIssue. None when a model point quotes nothing found in the text.
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+    /// Harper's message, or the model's point for model-only items
+    pub message: String,
+    pub suggestions: Vec<String>,
+    /// The Harper finding's severity; model-only items are suggestions
+    pub severity: Severity,
+    /// Harper's rule name; "Llm" for model-only items
+    pub rule: String,
+    /// The model's comment on a Harper finding
+    pub model_note: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DeepReview {
+    /// Sorted by position; items without a span come last
+    pub items: Vec<ReviewItem>,
+    /// False when no model answered and the review is Harper-only
+    pub llm_available: bool,
+    pub llm_error: Option<String>,
+}
+
+/// Split a critique into points: one per bullet or numbered line, continuation
+/// lines joined onto the point above
+fn points(explanation: &str) -> Vec<String> {
+    let mut points: Vec<String> = Vec::new();
+    for line in explanation.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        let stripped = line.trim_start_matches(|c: char| c.is_ascii_digit() || matches!(c, '-' | '*' | '•' | '.' | ')'));
+        let is_new = stripped.len() < line.len() || points.is_empty();
+        if is_new {
+            points.push(stripped.trim().to_string());
+        } else if let Some(last) = points.last_mut() {
+            last.push(' ');
+            last.push_str(line);
+        }
+    }
+    points.retain(|p| !p.is_empty());
+    points
+}
+
+/// Text inside straight or curly double quotes
+fn quotes(point: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = point;
+    while let Some(open) = rest.find(['"', '“']) {
+        let after = &rest[open + rest[open..].chars().next().map_or(1, char::len_utf8)..];
+        let Some(close) = after.find(['"', '”']) else { break };
+        let quoted = after[..close].trim();
+        if !quoted.is_empty() {
+            found.push(quoted);
+        }
+        rest = &after[close + after[close..].chars().next().map_or(1, char::len_utf8)..];
+    }
+    found
+}
+
+/// Char range of the first case-insensitive occurrence of `needle` in `text`
+fn locate(text: &str, needle: &str) -> Option<(usize, usize)> {
+    let lower = text.to_lowercase();
+    // Lowercasing can change byte lengths outside ASCII; only trust it when it didn't
+    if lower.len() != text.len() {
+        let byte = text.find(needle)?;
+        let start = text[..byte].chars().count();
+        return Some((start, start + needle.chars().count()));
+    }
+    let byte = lower.find(&needle.to_lowercase())?;
+    let start = text[..byte].chars().count();
+    Some((start, start + needle.chars().count()))
+}
+
+/// Merge Harper issues with the model's critique. A point is attached to every
+/// Harper issue whose span overlaps text the point quotes.
+pub fn merge(text: &str, issues: &[GrammarIssue], explanation: &str) -> Vec<ReviewItem> {
+    let mut items: Vec<ReviewItem> = issues
+        .iter()
+        .map(|issue| ReviewItem {
+            source: Source::Harper,
+            start: Some(issue.start),
+            end: Some(issue.end),
+            message: issue.message.clone(),
+            suggestions: issue.suggestions.clone(),
+            severity: issue.severity,
+            rule: issue.rule.clone(),
+            model_note: None,
+        })
+        .collect();
+
+    for point in points(explanation) {
+        let spans: Vec<(usize, usize)> = quotes(&point).into_iter().filter_map(|q| locate(text, q)).collect();
+        let mut matched = false;
+        for item in items.iter_mut().filter(|i| i.source != Source::Llm) {
+            let (Some(start), Some(end)) = (item.start, item.end) else { continue };
+            if spans.iter().any(|&(s, e)| s < end.max(start + 1) && start < e.max(s + 1)) {
+                item.source = Source::Both;
+                match &mut item.model_note {
+                    Some(note) => {
+                        note.push('\n');
+                        note.push_str(&point);
+                    }
+                    None => item.model_note = Some(point.clone()),
+                }
+                matched = true;
+            }
+        }
+        if !matched {
+            let span = spans.first().copied();
+            items.push(ReviewItem {
+                source: Source::Llm,
+                start: span.map(|s| s.0),
+                end: span.map(|s| s.1),
+                message: point,
+                suggestions: Vec::new(),
+                severity: Severity::Suggestion,
+                rule: "Llm".to_string(),
+                model_note: None,
+            });
+        }
+    }
+
+    items.sort_by_key(|item| (item.start.is_none(), item.start, item.end));
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Severity;
+
+    fn issue(start: usize, end: usize, message: &str) -> GrammarIssue {
+        GrammarIssue {
+            start,
+            end,
+            message: message.to_string(),
+            suggestions: vec![],
+            severity: Severity::Error,
+            rule: "Spelling".to_string(),
+            best_fix: None,
+            matched_text: String::new(),
+        }
+    }
+
+    #[test]
+    fn quoted_text_attaches_to_harper_span() {
+        let text = "The recieve button works. It was very very slow.";
+        let issues = vec![issue(4, 11, "Did you mean \"receive\"?")];
+        let explanation = "1. \"recieve\" is misspelled; the rule is i before e except after c.\n\
+                           2. \"very very\" repeats a word and weakens the sentence.\n\
+                           3. Overall the tone is fine.";
+        let items = merge(text, &issues, explanation);
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].source, Source::Both);
+        assert_eq!((items[0].severity, items[0].rule.as_str()), (Severity::Error, "Spelling"));
+        assert!(items[0].model_note.as_deref().unwrap().starts_with("\"recieve\" is misspelled"));
+        assert_eq!(items[1].source, Source::Llm);
+        assert_eq!((items[1].start, items[1].end), (Some(33), Some(42)));
+        assert_eq!((items[1].severity, items[1].rule.as_str()), (Severity::Suggestion, "Llm"));
+        assert_eq!(items[2].source, Source::Llm);
+        assert_eq!(items[2].start, None);
+    }
+}
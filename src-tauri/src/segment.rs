@@ -0,0 +1,365 @@
+use std::ops::Range;
+use std::sync::OnceLock;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Abbreviations whose trailing period does not end a sentence
+const ABBREVIATIONS: &[&str] = &["Mr", "Mrs", "Dr", "Ms", "Jr", "Sr", "vs", "etc", "e.g", "i.e"];
+
+/// User additions to ABBREVIATIONS, one per line in ~/.ghostpen/abbreviations.txt
+const USER_ABBREVIATIONS_FILE: &str = "abbreviations.txt";
+
+/// Abbreviations from the user's file, trailing period optional ("Fig." or "Fig").
+/// Read once per run; blank lines and # comments are skipped.
+fn user_abbreviations() -> &'static [String] {
+    static WORDS: OnceLock<Vec<String>> = OnceLock::new();
+    WORDS.get_or_init(|| {
+        crate::paths::data_dir()
+            .and_then(|d| std::fs::read_to_string(d.join(USER_ABBREVIATIONS_FILE)).ok())
+            .map(|content| parse_abbreviations(&content))
+            .unwrap_or_default()
+    })
+}
+
+fn parse_abbreviations(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| l.trim_end_matches('.').to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// Split text into sentence byte ranges. Each range starts at the first
+/// non-whitespace char of the sentence and ends just after its terminator,
+/// so inter-sentence whitespace is not covered by any range.
+pub fn sentence_spans(text: &str) -> Vec<Range<usize>> {
+    sentence_spans_with(text, user_abbreviations())
+}
+
+/// sentence_spans with `extra` abbreviations on top of the built-in list
+fn sentence_spans_with(text: &str, extra: &[String]) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, ch)) = chars.next() {
+        if start.is_none() {
+            if ch.is_whitespace() {
+                continue;
+            }
+            start = Some(i);
+        }
+
+        if ch != '.' && ch != '!' && ch != '?' {
+            continue;
+        }
+
+        // Absorb runs like "?!" or "..." into the same sentence
+        let mut end = i + ch.len_utf8();
+        while let Some(&(j, next)) = chars.peek() {
+            if next == '.' || next == '!' || next == '?' {
+                end = j + next.len_utf8();
+                chars.next();
+            } else {
+                break;
+            }
+        }
+
+        let at_boundary = match chars.peek() {
+            None => true,
+            Some(&(_, next)) => next.is_whitespace(),
+        };
+        if !at_boundary {
+            continue;
+        }
+
+        let sentence_start = start.unwrap_or(i);
+        if ch == '.' && is_abbreviation(&text[sentence_start..i], extra) {
+            continue;
+        }
+
+        spans.push(sentence_start..end);
+        start = None;
+    }
+
+    if let Some(s) = start {
+        let trimmed_end = s + text[s..].trim_end().len();
+        spans.push(s..trimmed_end);
+    }
+
+    spans
+}
+
+/// Byte ranges of paragraphs: runs of non-blank lines, separated by one or more
+/// blank (whitespace-only) lines. Each range ends at its last line's content.
+pub fn paragraph_spans(text: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut current: Option<Range<usize>> = None;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let content = line.trim_end_matches(['\n', '\r']);
+        if content.trim().is_empty() {
+            spans.extend(current.take());
+            continue;
+        }
+        let line_end = line_start + content.trim_end().len();
+        match current.as_mut() {
+            Some(span) => span.end = line_end,
+            None => current = Some(line_start + (content.len() - content.trim_start().len())..line_end),
+        }
+    }
+    spans.extend(current);
+    spans
+}
+
+/// Words by Unicode word boundaries (UAX #29), keeping only those with a letter
+/// or digit. "don't" is one word; combining marks stay with their base letter.
+pub fn words(text: &str) -> Vec<&str> {
+    text.unicode_words().collect()
+}
+
+/// Number of words, as `words` counts them
+pub fn word_count(text: &str) -> usize {
+    if text.is_ascii() {
+        ascii_word_count(text)
+    } else {
+        text.unicode_words().count()
+    }
+}
+
+/// UAX #29 restricted to ASCII: alphanumeric runs, joined across `_`, across
+/// ' . : between letters, and across ' . , ; between digits
+fn ascii_word_count(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut count = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        if !b.is_ascii_alphanumeric() {
+            continue;
+        }
+        let joined = match i.checked_sub(1).map(|j| bytes[j]) {
+            Some(prev) if prev.is_ascii_alphanumeric() || prev == b'_' => true,
+            Some(prev) if i >= 2 => {
+                let before = bytes[i - 2];
+                (b.is_ascii_alphabetic() && before.is_ascii_alphabetic() && matches!(prev, b'\'' | b'.' | b':'))
+                    || (b.is_ascii_digit() && before.is_ascii_digit() && matches!(prev, b'\'' | b'.' | b',' | b';'))
+            }
+            _ => false,
+        };
+        if !joined {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Letters of right-to-left scripts (Hebrew, Arabic, Syriac, Thaana, N'Ko and their
+/// presentation forms) and the bidirectional control marks that come with them
+pub fn is_rtl(c: char) -> bool {
+    matches!(c,
+        '\u{0590}'..='\u{08FF}'
+        | '\u{FB1D}'..='\u{FDFF}'
+        | '\u{FE70}'..='\u{FEFF}'
+        | '\u{200E}' | '\u{200F}'
+        | '\u{202A}'..='\u{202E}'
+        | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// Split text into contiguous byte ranges of at most `max_len`, cut only where a
+/// paragraph starts. A paragraph longer than `max_len` gets a chunk to itself;
+/// `max_len` 0 means one chunk. Together the ranges cover all of `text`.
+pub fn chunk_spans(text: &str, max_len: usize) -> Vec<Range<usize>> {
+    if max_len == 0 || text.len() <= max_len {
+        return vec![0..text.len()];
+    }
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    for paragraph in paragraph_spans(text) {
+        if paragraph.start > start && paragraph.end - start > max_len {
+            chunks.push(start..paragraph.start);
+            start = paragraph.start;
+        }
+    }
+    chunks.push(start..text.len());
+    chunks
+}
+
+/// Byte ranges of code in mixed prose: ``` fenced blocks (fence lines included),
+/// indented blocks (4 spaces or a tab, after a blank line), and `inline` spans
+/// (backticks included). Ranges are sorted and don't overlap.
+pub fn code_regions(text: &str) -> Vec<Range<usize>> {
+    let mut regions: Vec<Range<usize>> = Vec::new();
+    let mut fence_start: Option<usize> = None;
+    let mut previous_blank = true;
+    let mut in_indented = false;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let content = line.trim_end_matches(['\n', '\r']);
+        let line_end = line_start + content.len();
+        let indent = content.len() - content.trim_start().len();
+        let is_fence = content.trim_start().starts_with("```") && indent < 4;
+
+        if let Some(start) = fence_start {
+            if is_fence {
+                regions.push(start..line_end);
+                fence_start = None;
+            }
+            continue;
+        }
+        if is_fence {
+            fence_start = Some(line_start);
+            in_indented = false;
+            previous_blank = false;
+            continue;
+        }
+
+        let blank = content.trim().is_empty();
+        let indented = content.starts_with("    ") || content.starts_with('\t');
+        if indented && !blank && (previous_blank || in_indented) {
+            // Consecutive indented lines (blank lines between included) form one block
+            match regions.last_mut() {
+                Some(last) if in_indented => last.end = line_end,
+                _ => regions.push(line_start..line_end),
+            }
+            in_indented = true;
+        } else if !blank {
+            in_indented = false;
+            regions.extend(inline_code(content, line_start));
+        }
+        previous_blank = blank;
+    }
+    // An unclosed fence runs to the end of the text
+    if let Some(start) = fence_start {
+        regions.push(start..text.len());
+    }
+
+    regions
+}
+
+/// `code` spans within one line. A run of N backticks closes only on another run of N.
+fn inline_code(line: &str, line_start: usize) -> Vec<Range<usize>> {
+    let bytes = line.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'`' {
+            i += 1;
+            continue;
+        }
+        let run = bytes[i..].iter().take_while(|b| **b == b'`').count();
+        let open = i;
+        i += run;
+        let mut j = i;
+        let mut closed = None;
+        while j < bytes.len() {
+            if bytes[j] == b'`' {
+                let close_run = bytes[j..].iter().take_while(|b| **b == b'`').count();
+                if close_run == run {
+                    closed = Some(j + close_run);
+                    break;
+                }
+                j += close_run;
+            } else {
+                j += 1;
+            }
+        }
+        if let Some(end) = closed {
+            spans.push(line_start + open..line_start + end);
+            i = end;
+        }
+    }
+    spans
+}
+
+fn is_abbreviation(before_period: &str, extra: &[String]) -> bool {
+    let last_word = before_period
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or("");
+    ABBREVIATIONS.iter().any(|a| last_word == *a) || extra.iter().any(|a| last_word == a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_count_follows_word_boundaries() {
+        assert_eq!(word_count("don't won't"), 2);
+        // "e" + combining acute is one letter, not a word break
+        assert_eq!(word_count("cafe\u{301} ole\u{301} now"), 3);
+        assert_eq!(word_count("日本語のテキスト"), words("日本語のテキスト").len());
+        assert_eq!(word_count("  -- !! "), 0);
+    }
+
+    #[test]
+    fn ascii_fast_path_matches_unicode_words() {
+        for text in ["don't won't", "e.g. 3.5 and 1,000", "foo/bar -- baz_qux", "a..b 'quoted' it's", "9,9 v2 2b"] {
+            assert_eq!(ascii_word_count(text), text.unicode_words().count(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn chunks_cover_text_and_cut_at_paragraphs() {
+        let text = "First para.\n\nSecond one here.\n\nThird.\n";
+        let chunks = chunk_spans(text, 20);
+        assert_eq!(chunks, vec![0..13, 13..31, 31..text.len()]);
+        assert_eq!(chunk_spans(text, 0), vec![0..text.len()]);
+    }
+
+    #[test]
+    fn user_abbreviations_do_not_end_sentences() {
+        let text = "See Fig. 3 for details.";
+        assert_eq!(sentence_spans_with(text, &[]).len(), 2);
+
+        let extra = parse_abbreviations("# academic\nFig.\nal\n\n");
+        assert_eq!(extra, vec!["Fig", "al"]);
+        assert_eq!(sentence_spans_with(text, &extra), vec![0..text.len()]);
+    }
+
+    #[test]
+    fn paragraphs_split_on_blank_lines() {
+        let text = "First para\nstill first.\n\n  \nSecond para.\n";
+        let paragraphs: Vec<&str> = paragraph_spans(text).into_iter().map(|r| &text[r]).collect();
+        assert_eq!(paragraphs, vec!["First para\nstill first.", "Second para."]);
+        assert!(paragraph_spans(" \n\n").is_empty());
+    }
+
+    #[test]
+    fn spans_cover_each_sentence() {
+        let text = "First one. Second one!  Third?";
+        let spans = sentence_spans(text);
+        let sentences: Vec<&str> = spans.iter().map(|r| &text[r.clone()]).collect();
+        assert_eq!(sentences, vec!["First one.", "Second one!", "Third?"]);
+    }
+
+    #[test]
+    fn spans_skip_abbreviations_and_trailing_fragment() {
+        let text = "Dr. Smith met Mr. Jones. No period here ";
+        let spans = sentence_spans(text);
+        let sentences: Vec<&str> = spans.iter().map(|r| &text[r.clone()]).collect();
+        assert_eq!(sentences, vec!["Dr. Smith met Mr. Jones.", "No period here"]);
+    }
+
+    #[test]
+    fn code_regions_cover_fenced_inline_and_indented() {
+        let text = "Call `someVariable` here.\n```\nlet x = 1;\n```\nProse again.\n\n    indented code\n    more code\nBack.";
+        let regions: Vec<&str> = code_regions(text).into_iter().map(|r| &text[r]).collect();
+        assert_eq!(
+            regions,
+            vec!["`someVariable`", "```\nlet x = 1;\n```", "    indented code\n    more code"]
+        );
+    }
+
+    #[test]
+    fn unmatched_backtick_is_not_code() {
+        assert!(code_regions("It's a `typo without a close.").is_empty());
+    }
+}
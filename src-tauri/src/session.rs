@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// How many recently used custom modes to remember
+const MAX_RECENT_MODES: usize = 5;
+
+/// UI state restored on launch, persisted to ~/.ghostpen/session.json
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SessionState {
+    pub last_mode: Option<String>,
+    pub last_dialect: Option<String>,
+    /// Most recent first
+    pub recent_custom_modes: Vec<String>,
+}
+
+/// Serializes read-modify-write cycles on session.json
+static SESSION_LOCK: Mutex<()> = Mutex::new(());
+
+fn session_path() -> Option<PathBuf> {
+    Some(crate::paths::data_dir()?.join("session.json"))
+}
+
+/// Current session state. A missing or corrupt file yields defaults.
+pub fn load() -> SessionState {
+    session_path().map(|p| load_from(&p)).unwrap_or_default()
+}
+
+fn load_from(path: &Path) -> SessionState {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn update_at(path: &Path, f: impl FnOnce(&mut SessionState)) {
+    let _guard = SESSION_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut state = load_from(path);
+    let before = state.clone();
+    f(&mut state);
+    // check_grammar runs on every edit — skip the write when nothing changed
+    if state == before && path.exists() {
+        return;
+    }
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&state) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+fn apply_rewrite(state: &mut SessionState, mode: &str) {
+    state.last_mode = Some(mode.to_string());
+    if !crate::llm::BUILTIN_MODES.contains(&mode) {
+        state.recent_custom_modes.retain(|m| m != mode);
+        state.recent_custom_modes.insert(0, mode.to_string());
+        state.recent_custom_modes.truncate(MAX_RECENT_MODES);
+    }
+}
+
+/// Id for a new writing session: start time plus a counter, unique per run
+pub fn new_session_id() -> String {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    format!(
+        "{}-{}",
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%3f"),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Remember the mode used by a rewrite
+pub fn record_rewrite(mode: &str) {
+    if let Some(path) = session_path() {
+        update_at(&path, |state| apply_rewrite(state, mode));
+    }
+}
+
+/// Remember the dialect used by a grammar check
+pub fn record_check(dialect: &str) {
+    if let Some(path) = session_path() {
+        update_at(&path, |state| state.last_dialect = Some(dialect.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrite_updates_last_mode_in_file() {
+        let path = std::env::temp_dir().join(format!("ghostpen-session-{}.json", std::process::id()));
+        update_at(&path, |state| apply_rewrite(state, "formal"));
+        update_at(&path, |state| apply_rewrite(state, "pirate"));
+        let saved = load_from(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(saved.last_mode.as_deref(), Some("pirate"));
+        assert_eq!(saved.recent_custom_modes, vec!["pirate"]);
+    }
+
+    #[test]
+    fn corrupt_file_yields_defaults() {
+        let path = std::env::temp_dir().join(format!("ghostpen-session-bad-{}.json", std::process::id()));
+        std::fs::write(&path, "{ not json").unwrap();
+        let state = load_from(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(state, SessionState::default());
+    }
+}
@@ -0,0 +1,207 @@
+//! Streamed replies: OpenAI-style server-sent events (rewrite, benchmark) and
+//! Ollama's newline-delimited JSON (pull progress). TCP chunks split lines —
+//! and UTF-8 sequences — anywhere, so bytes are reassembled into whole lines
+//! before anything is parsed.
+
+use futures_util::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+
+/// A line this long without a newline means the server isn't streaming lines at all
+const MAX_PENDING_BYTES: usize = 1_048_576;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// "data: {...}" lines, ended by "data: [DONE]". Other lines (comments,
+    /// event names, keep-alives) and data that doesn't parse are skipped.
+    Sse,
+    /// One JSON value per line; a line that doesn't parse is an error
+    Jsonl,
+}
+
+#[derive(Debug)]
+pub enum StreamError<E> {
+    /// Reading the body failed
+    Transport(E),
+    /// More than MAX_PENDING_BYTES arrived without a line break
+    Overflow,
+    /// A JSONL line that isn't the expected JSON
+    Malformed(serde_json::Error),
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for StreamError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::Transport(e) => write!(f, "{}", e),
+            StreamError::Overflow => write!(f, "Stream buffer overflow — malformed LLM response"),
+            StreamError::Malformed(e) => write!(f, "Malformed line in streamed response: {}", e),
+        }
+    }
+}
+
+impl<E: std::error::Error> std::error::Error for StreamError<E> {}
+
+/// Bytes in, complete lines out
+#[derive(Default)]
+struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    /// Add a chunk and return the lines it completes, without line endings.
+    /// None once too much has piled up without a newline.
+    fn push(&mut self, chunk: &[u8]) -> Option<Vec<String>> {
+        self.pending.extend_from_slice(chunk);
+        let mut lines = Vec::new();
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            lines.push(String::from_utf8_lossy(&line).trim_end_matches(['\n', '\r']).to_string());
+        }
+        (self.pending.len() <= MAX_PENDING_BYTES).then_some(lines)
+    }
+
+    /// A final line the stream ended without terminating
+    fn finish(&mut self) -> Option<String> {
+        let rest = String::from_utf8_lossy(&std::mem::take(&mut self.pending)).trim().to_string();
+        (!rest.is_empty()).then_some(rest)
+    }
+}
+
+enum Parsed<T> {
+    Value(T),
+    Skip,
+    Done,
+    Malformed(serde_json::Error),
+}
+
+fn parse_line<T: DeserializeOwned>(line: &str, format: Format) -> Parsed<T> {
+    let line = line.trim();
+    let payload = match format {
+        Format::Sse => match line.strip_prefix("data:").map(str::trim) {
+            Some("[DONE]") => return Parsed::Done,
+            Some(data) => data,
+            None => return Parsed::Skip,
+        },
+        Format::Jsonl => line,
+    };
+    if payload.is_empty() {
+        return Parsed::Skip;
+    }
+    match (serde_json::from_str(payload), format) {
+        (Ok(value), _) => Parsed::Value(value),
+        (Err(_), Format::Sse) => Parsed::Skip,
+        (Err(e), Format::Jsonl) => Parsed::Malformed(e),
+    }
+}
+
+struct State<S, T> {
+    bytes: S,
+    format: Format,
+    lines: LineBuffer,
+    ready: VecDeque<String>,
+    ended: bool,
+    _item: std::marker::PhantomData<T>,
+}
+
+/// Parse a streamed body (e.g. `Response::bytes_stream()`) into one `T` per
+/// message, in order. The stream ends at "[DONE]", at the end of the body, or
+/// after the first error. Pin it before polling: `std::pin::pin!(json_values(..))`.
+pub fn json_values<T, S, B, E>(bytes: S, format: Format) -> impl Stream<Item = Result<T, StreamError<E>>>
+where
+    T: DeserializeOwned,
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    let state = State {
+        bytes,
+        format,
+        lines: LineBuffer::default(),
+        ready: VecDeque::new(),
+        ended: false,
+        _item: std::marker::PhantomData,
+    };
+    futures_util::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(line) = state.ready.pop_front() {
+                match parse_line(&line, state.format) {
+                    Parsed::Value(value) => return Some((Ok(value), state)),
+                    Parsed::Skip => continue,
+                    Parsed::Done => return None,
+                    Parsed::Malformed(e) => {
+                        state.ready.clear();
+                        state.ended = true;
+                        return Some((Err(StreamError::Malformed(e)), state));
+                    }
+                }
+            }
+            if state.ended {
+                return None;
+            }
+            match state.bytes.next().await {
+                Some(Ok(chunk)) => match state.lines.push(chunk.as_ref()) {
+                    Some(lines) => state.ready.extend(lines),
+                    None => {
+                        state.ended = true;
+                        return Some((Err(StreamError::Overflow), state));
+                    }
+                },
+                Some(Err(e)) => {
+                    state.ended = true;
+                    return Some((Err(StreamError::Transport(e)), state));
+                }
+                None => {
+                    state.ended = true;
+                    state.ready.extend(state.lines.finish());
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed `chunks` as a body and collect everything parsed from it
+    async fn collect(chunks: &[&[u8]], format: Format) -> Vec<Result<serde_json::Value, StreamError<std::io::Error>>> {
+        let body = futures_util::stream::iter(chunks.iter().map(|c| Ok::<_, std::io::Error>(c.to_vec())).collect::<Vec<_>>());
+        json_values(body, format).collect().await
+    }
+
+    #[tokio::test]
+    async fn sse_lines_split_across_chunks_are_reassembled() {
+        let chunks: &[&[u8]] = &[
+            b": keep-alive\n\nda",
+            b"ta: {\"delta\":\"caf",
+            // "é" split between its two bytes
+            b"\xc3",
+            b"\xa9\"}\r\n\ndata: {\"delta\":\"two\"}\n",
+            b"data: not json\n\ndata: [DO",
+            b"NE]\n\ndata: {\"delta\":\"after done\"}\n",
+        ];
+        let values: Vec<String> = collect(chunks, Format::Sse)
+            .await
+            .into_iter()
+            .map(|v| v.unwrap()["delta"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(values, ["café", "two"]);
+    }
+
+    #[tokio::test]
+    async fn jsonl_keeps_an_unterminated_last_line_and_rejects_garbage() {
+        let values = collect(&[b"{\"status\":\"pull", b"ing\"}\n{\"status\":", b"\"success\"}"], Format::Jsonl).await;
+        let statuses: Vec<String> = values.into_iter().map(|v| v.unwrap()["status"].as_str().unwrap().to_string()).collect();
+        assert_eq!(statuses, ["pulling", "success"]);
+
+        let values = collect(&[b"{\"status\":\"ok\"}\nnot json\n{\"status\":\"never\"}\n"], Format::Jsonl).await;
+        assert_eq!(values.len(), 2);
+        assert!(matches!(values[1], Err(StreamError::Malformed(_))));
+    }
+
+    #[tokio::test]
+    async fn endless_line_overflows() {
+        let big = vec![b'x'; MAX_PENDING_BYTES + 1];
+        let values = collect(&[&big], Format::Sse).await;
+        assert!(matches!(values.as_slice(), [Err(StreamError::Overflow)]));
+    }
+}
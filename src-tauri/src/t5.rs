@@ -289,7 +289,7 @@ fn join_with_separators(sentences: &[String], separators: &[String]) -> String {
     result
 }
 
-fn compute_diff(original: &str, corrected: &str) -> Vec<TextChange> {
+pub(crate) fn compute_diff(original: &str, corrected: &str) -> Vec<TextChange> {
     let orig_words: Vec<&str> = original.split_whitespace().collect();
     let corr_words: Vec<&str> = corrected.split_whitespace().collect();
 
@@ -0,0 +1,170 @@
+//! Test-only helpers: a tiny blocking HTTP server standing in for LM Studio / Ollama.
+// Not every test reads every field
+#![allow(dead_code)]
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+}
+
+pub struct MockServer {
+    /// Base URL, e.g. "http://127.0.0.1:53211"
+    pub url: String,
+    pub requests: Arc<Mutex<Vec<MockRequest>>>,
+}
+
+impl MockServer {
+    /// Number of requests served so far
+    pub fn hits(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    /// Request bodies in arrival order
+    pub fn bodies(&self) -> Vec<String> {
+        self.requests.lock().unwrap().iter().map(|r| r.body.clone()).collect()
+    }
+}
+
+/// Start a server on a random port. `respond` maps each request to (status, body).
+/// Every response closes the connection, so bodies can be read to EOF.
+pub fn mock_server<F>(respond: F) -> MockServer
+where
+    F: Fn(&MockRequest) -> (u16, String) + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&requests);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            serve(stream, &respond, &log);
+        }
+    });
+
+    MockServer { url, requests }
+}
+
+/// Like mock_server, but each connection gets its own thread so requests can overlap
+pub fn threaded_mock_server<F>(respond: F) -> MockServer
+where
+    F: Fn(&MockRequest) -> (u16, String) + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&requests);
+    let respond = Arc::new(respond);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let respond = Arc::clone(&respond);
+            let log = Arc::clone(&log);
+            std::thread::spawn(move || serve(stream, respond.as_ref(), &log));
+        }
+    });
+
+    MockServer { url, requests }
+}
+
+fn serve<F>(mut stream: TcpStream, respond: &F, log: &Mutex<Vec<MockRequest>>)
+where
+    F: Fn(&MockRequest) -> (u16, String),
+{
+    let Some(request) = read_request(&mut stream) else { return };
+    let (status, body) = respond(&request);
+    log.lock().unwrap().push(request);
+    let response = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+/// Answer every request with 200 and a Content-Length well past `partial`, then
+/// close the connection after sending only `partial` — a connection cut mid-reply
+pub fn truncating_server(partial: &str) -> MockServer {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&requests);
+    let partial = partial.to_string();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let Some(request) = read_request(&mut stream) else { continue };
+            log.lock().unwrap().push(request);
+            let response = format!(
+                "HTTP/1.1 200 Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                partial.len() + 1000,
+                partial
+            );
+            let _ = stream.write_all(response.as_bytes());
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+        }
+    });
+
+    MockServer { url, requests }
+}
+
+/// Serve the same OpenAI-style chat completion for every request
+pub fn chat_server(content: &str) -> MockServer {
+    let body = serde_json::json!({
+        "choices": [{ "message": { "content": content } }]
+    })
+    .to_string();
+    mock_server(move |_| (200, body.clone()))
+}
+
+fn read_request(stream: &mut TcpStream) -> Option<MockRequest> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        let n = stream.read(&mut chunk).ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut request_line = head.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let content_length = head
+        .lines()
+        .find_map(|l| {
+            let (key, value) = l.split_once(':')?;
+            if key.eq_ignore_ascii_case("content-length") {
+                value.trim().parse::<usize>().ok()
+            } else {
+                None
+            }
+        })
+        .unwrap_or(0);
+
+    while buf.len() < header_end + content_length {
+        let n = stream.read(&mut chunk).ok()?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    let body = String::from_utf8_lossy(&buf[header_end..]).to_string();
+    Some(MockRequest { method, path, body })
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Bundled at compile time so lookups never touch disk or network
+const THESAURUS_DATA: &str = include_str!("../data/thesaurus.txt");
+
+fn thesaurus() -> &'static HashMap<String, Vec<String>> {
+    static MAP: OnceLock<HashMap<String, Vec<String>>> = OnceLock::new();
+    MAP.get_or_init(|| parse(THESAURUS_DATA))
+}
+
+/// Parse "word: syn, syn, ..." lines. Blank lines and # comments are skipped.
+fn parse(data: &str) -> HashMap<String, Vec<String>> {
+    data.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| {
+            let (word, synonyms) = l.split_once(':')?;
+            let synonyms: Vec<String> = synonyms
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+            Some((word.trim().to_lowercase(), synonyms))
+        })
+        .collect()
+}
+
+/// Look up synonyms for a word. Case and surrounding punctuation are ignored;
+/// unknown words return an empty list.
+pub fn lookup(word: &str) -> Vec<String> {
+    let normalized = word
+        .trim()
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    thesaurus().get(&normalized).cloned().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_word_has_several_synonyms() {
+        let synonyms = lookup("happy");
+        assert!(synonyms.len() >= 3);
+        assert!(synonyms.contains(&"cheerful".to_string()));
+    }
+
+    #[test]
+    fn lookup_normalizes_case_and_punctuation() {
+        assert_eq!(lookup("\"Happy!\""), lookup("happy"));
+    }
+
+    #[test]
+    fn unknown_word_returns_empty() {
+        assert!(lookup("xylograph").is_empty());
+    }
+}
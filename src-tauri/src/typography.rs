@@ -0,0 +1,69 @@
+//! Convert between typographic ("smart") punctuation and plain ASCII.
+
+/// Straighten curly quotes, turn em dashes into "--", en dashes into "-",
+/// and … into "...". Everything else is copied through.
+pub fn to_ascii(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '‘' | '’' | '‚' | '‛' | '′' => out.push('\''),
+            '“' | '”' | '„' | '‟' | '″' => out.push('"'),
+            '—' => out.push_str("--"),
+            '–' | '‑' | '‒' => out.push('-'),
+            '…' => out.push_str("..."),
+            '\u{00A0}' => out.push(' '),
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Curl straight quotes by context, turn "--" into an em dash and "..." into …
+/// Single hyphens are left alone, since most are real hyphens.
+pub fn to_smart(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let prev = out.chars().last();
+        // A quote opens after whitespace, an opening bracket, a dash, or at the start
+        let opens = prev.is_none_or(|p| p.is_whitespace() || matches!(p, '(' | '[' | '{' | '—' | '“' | '‘'));
+
+        match c {
+            '"' => out.push(if opens { '“' } else { '”' }),
+            '\'' => out.push(if opens { '‘' } else { '’' }),
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                out.push('—');
+                i += 1;
+            }
+            '.' if chars.get(i + 1) == Some(&'.') && chars.get(i + 2) == Some(&'.') => {
+                out.push('…');
+                i += 2;
+            }
+            other => out.push(other),
+        }
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_quotes_and_em_dash() {
+        let smart = "“Don’t go,” she said — it’s late… ‘Really?’";
+        let ascii = to_ascii(smart);
+        assert_eq!(ascii, "\"Don't go,\" she said -- it's late... 'Really?'");
+        assert_eq!(to_smart(&ascii), smart);
+    }
+
+    #[test]
+    fn plain_text_and_hyphens_are_preserved() {
+        assert_eq!(to_smart("A well-known fact"), "A well-known fact");
+        assert_eq!(to_ascii("Pages 10–12 café"), "Pages 10-12 café");
+    }
+}
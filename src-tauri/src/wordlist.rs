@@ -0,0 +1,78 @@
+//! Dictionary selection for spell checking: Harper's curated list, optionally
+//! extended with a bundled list of rarer words ("full").
+//!
+//! Harper has no separate British or Canadian word lists to load. Its one
+//! curated dictionary tags entries with the dialects that use them, and the
+//! `Dialect` a linter is built with (see lint_pool) decides which are accepted:
+//! "realise" and "centre" pass for British and fail for American. Config
+//! `dialect` is the only knob, and every check builds its linters from it.
+
+use harper_core::spell::{Dictionary, FstDictionary};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// Bundled at compile time, like the thesaurus
+const EXTENDED_WORDS_DATA: &str = include_str!("../data/extended_words.txt");
+
+/// Accepted values for config `dictionary`
+pub const VARIANTS: &[&str] = &["curated", "full"];
+
+/// Which dictionary is active, reported by dictionary_info
+#[derive(Serialize)]
+pub struct DictionaryInfo {
+    /// "curated" or "full"
+    pub variant: String,
+    /// Words known to the spell checker, including the extended list for "full"
+    pub word_count: Option<usize>,
+    /// Config `dialect`, which decides the regional spellings accepted
+    pub dialect: String,
+}
+
+fn extended_words() -> &'static HashSet<String> {
+    static WORDS: OnceLock<HashSet<String>> = OnceLock::new();
+    WORDS.get_or_init(|| {
+        EXTENDED_WORDS_DATA
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(str::to_lowercase)
+            .collect()
+    })
+}
+
+/// Whether the selected dictionary accepts a word Harper flagged as misspelled.
+/// The curated variant adds nothing; "full" also accepts the extended list.
+pub fn accepts(variant: &str, word: &str) -> bool {
+    variant == "full" && extended_words().contains(&word.trim().to_lowercase())
+}
+
+/// Describe the dictionary selected by `variant`, as read for `dialect`
+pub fn info(variant: &str, dialect: &str) -> DictionaryInfo {
+    let curated = FstDictionary::curated().word_count();
+    let extra = if variant == "full" { extended_words().len() } else { 0 };
+    DictionaryInfo {
+        variant: variant.to_string(),
+        word_count: Some(curated + extra),
+        dialect: dialect.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_variant_accepts_extended_words() {
+        assert!(accepts("full", "Sesquipedalian"));
+        assert!(!accepts("curated", "sesquipedalian"));
+        assert!(!accepts("full", "teh"));
+    }
+
+    #[test]
+    fn full_reports_more_words() {
+        let curated = info("curated", "american").word_count.unwrap();
+        let full = info("full", "american").word_count.unwrap();
+        assert_eq!(full - curated, extended_words().len());
+    }
+}
@@ -469,7 +469,7 @@ function App() {
     setSelectionRange(null);
   };
 
-  const handleFeedback = async (rating: "good" | "bad", rewriteText: string, mode: string) => {
+  const handleFeedback = async (rating: "up" | "down", rewriteText: string, mode: string) => {
     logEvent("rewrite_feedback", { rating, mode });
     try {
       await invoke("save_feedback", {
@@ -562,9 +562,9 @@ function App() {
           </div>
           <div className={`status-badge ${llmStatus.available ? "connected" : "disconnected"}`}>
             <div className="status-dot" />
-            {llmStatus.available ? `${llmStatus.provider}` : "No LLM"}
+            {llmStatus.available ? `${llmStatus.provider}` : llmStatus.provider === "offline" ? "Offline mode" : "No LLM"}
           </div>
-          {!llmStatus.available && !llmLaunching && (
+          {!llmStatus.available && llmStatus.provider !== "offline" && !llmLaunching && (
             <button
               className="launch-llm-btn"
               onClick={async () => {
@@ -632,7 +632,7 @@ function App() {
       <div className="main">
         <div className="editor-panel">
           <div className="editor-toolbar">
-            {["clarity", "concise", "formal", "casual"].map((mode) => (
+            {["clarity", "concise", "formal", "casual", "proofread"].map((mode) => (
               <button
                 key={mode}
                 className={`toolbar-btn ${activeMode === mode ? "active" : ""}`}
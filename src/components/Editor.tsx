@@ -11,7 +11,10 @@ interface GrammarIssue {
   end: number;
   message: string;
   suggestions: string[];
-  severity: string;
+  severity: "error" | "warning" | "suggestion";
+  rule: string;
+  best_fix: string | null;
+  matched_text: string;
 }
 
 interface CheckResult {
@@ -63,11 +66,8 @@ const highlightField = StateField.define<DecorationSet>({
   provide: (f) => EditorView.decorations.from(f),
 });
 
-function mapSeverity(severity: string): "error" | "warning" | "info" {
-  const s = severity.toLowerCase();
-  if (s.includes("error") || s.includes("spell")) return "error";
-  if (s.includes("style") || s.includes("readability")) return "info";
-  return "warning";
+function mapSeverity(severity: GrammarIssue["severity"]): "error" | "warning" | "info" {
+  return severity === "suggestion" ? "info" : severity;
 }
 
 // Store latest issues callback in a ref so the linter closure can access it
@@ -132,7 +132,7 @@ const grammarLinter = linter(
     }
 
     try {
-      const result = await invoke<CheckResult>("check_grammar", { text });
+      const result = await invoke<CheckResult>("check_grammar", { request: { text } });
 
       if (issuesCallback) {
         issuesCallback(result.issues, result.stats);
@@ -9,11 +9,8 @@ interface IssueSidebarProps {
   onDictionaryAdd?: () => void;
 }
 
-function issueSeverityClass(severity: string): string {
-  const s = severity.toLowerCase();
-  if (s.includes("error") || s.includes("spell")) return "error";
-  if (s.includes("style") || s.includes("readability")) return "style";
-  return "warning";
+function issueSeverityClass(severity: GrammarIssue["severity"]): string {
+  return severity === "suggestion" ? "style" : severity;
 }
 
 export default function IssueSidebar({ issues, text, onApplySuggestion, onScrollToIssue, onDictionaryAdd }: IssueSidebarProps) {
@@ -28,9 +25,8 @@ export default function IssueSidebar({ issues, text, onApplySuggestion, onScroll
     }
   };
 
-  const isSpellingIssue = (severity: string): boolean => {
-    const s = severity.toLowerCase();
-    return s.includes("spell") || s.includes("error");
+  const isSpellingIssue = (issue: GrammarIssue): boolean => {
+    return issue.rule === "Spelling" || issue.rule === "Typo";
   };
   const getIssueSnippet = (issue: GrammarIssue): string => {
     return text.substring(issue.start, issue.end);
@@ -81,7 +77,7 @@ export default function IssueSidebar({ issues, text, onApplySuggestion, onScroll
                     {s}
                   </button>
                 ))}
-                {isSpellingIssue(issue.severity) && (
+                {isSpellingIssue(issue) && (
                   <button
                     className="suggestion-chip dict-chip"
                     onClick={(e) => {
@@ -13,7 +13,7 @@ interface RewritePanelProps {
   onApply: () => void;
   onDismiss: () => void;
   onCancel?: () => void;
-  onFeedback: (rating: "good" | "bad", rewriteText: string, mode: string) => void;
+  onFeedback: (rating: "up" | "down", rewriteText: string, mode: string) => void;
   mode: string;
 }
 
@@ -21,7 +21,7 @@ export default function RewritePanel({ rewriteResult, rewriteLoading, streamingT
   const [feedbackGiven, setFeedbackGiven] = useState(false);
   const [showThanks, setShowThanks] = useState(false);
 
-  const handleFeedback = (rating: "good" | "bad") => {
+  const handleFeedback = (rating: "up" | "down") => {
     if (feedbackGiven || !rewriteResult?.rewritten) return;
     setFeedbackGiven(true);
     setShowThanks(true);
@@ -103,7 +103,7 @@ export default function RewritePanel({ rewriteResult, rewriteLoading, streamingT
               <div className="feedback-group">
                 <button
                   className={`feedback-btn ${feedbackGiven ? "disabled" : ""}`}
-                  onClick={() => handleFeedback("good")}
+                  onClick={() => handleFeedback("up")}
                   disabled={feedbackGiven}
                   title="Helpful"
                 >
@@ -111,7 +111,7 @@ export default function RewritePanel({ rewriteResult, rewriteLoading, streamingT
                 </button>
                 <button
                   className={`feedback-btn ${feedbackGiven ? "disabled" : ""}`}
-                  onClick={() => handleFeedback("bad")}
+                  onClick={() => handleFeedback("down")}
                   disabled={feedbackGiven}
                   title="Not helpful"
                 >